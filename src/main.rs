@@ -1,12 +1,14 @@
 extern crate sdl2;
 extern crate sdl2_image;
 extern crate sdl2_ttf;
+extern crate toml;
 
 mod phi;
 mod views;
 
 fn main() {
-    ::phi::spawn("rusty-shooter", |phi| {
+    let settings = ::views::settings::Settings::load();
+    ::phi::spawn("rusty-shooter", settings.resolution, settings.pacing, |phi| {
         Box::new(::views::main_menu::MainMenuView::new(phi))
     });
 }
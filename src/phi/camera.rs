@@ -0,0 +1,182 @@
+use phi::data::{Rectangle, Vec2};
+use phi::rng::Rng;
+use phi::snapshot::{field, table, Snapshot};
+use phi::toml::Value;
+
+/// The amount of trauma recovered every second.
+const TRAUMA_DECAY: f64 = 1.2;
+
+/// The offset, in pixels, applied to the scene when the trauma is maximal.
+pub const MAX_SHAKE_OFFSET: f64 = 24.0;
+
+/// Converts world coordinates to screen coordinates. By default, both are the
+/// same; moving the camera to `pos` makes that point appear in the top-left
+/// corner of the screen, and `zoom` scales the scene around the center of the
+/// screen.
+///
+/// The camera also implements "trauma"-based screen shake: events such as
+/// explosions add trauma, which decays over time, and the shake is
+/// proportional to the square of the trauma. Small hits therefore barely move
+/// the screen, while several big ones in a row are very noticeable.
+pub struct Camera {
+    pub pos: Vec2,
+    pub zoom: f64,
+    /// Multiplies the screen shake, which players may find uncomfortable.
+    pub shake_scale: f64,
+
+    /// The size of the screen, in pixels.
+    viewport: Vec2,
+
+    /// Between 0 (calm) and 1 (maximum shake).
+    trauma: f64,
+    shake: Vec2,
+    rng: Rng,
+}
+
+impl Camera {
+    pub fn new(viewport: (f64, f64)) -> Camera {
+        Camera {
+            pos: Vec2::zero(),
+            zoom: 1.0,
+            shake_scale: 1.0,
+            viewport: Vec2::new(viewport.0, viewport.1),
+            trauma: 0.0,
+            shake: Vec2::zero(),
+            rng: Rng::new(0),
+        }
+    }
+
+    /// Brings the camera back to its default position, zoom and trauma. The
+    /// shake starts over too, so that replays shake the same way.
+    pub fn reset(&mut self) {
+        self.pos = Vec2::zero();
+        self.zoom = 1.0;
+        self.trauma = 0.0;
+        self.shake = Vec2::zero();
+        self.rng = Rng::new(0);
+    }
+
+    /// Adds `amount` (between 0 and 1) of trauma to the camera.
+    pub fn add_trauma(&mut self, amount: f64) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    /// Updates the screen shake. Must be called once per frame, before
+    /// rendering anything through the camera.
+    pub fn update(&mut self, elapsed: f64) {
+        self.trauma = (self.trauma - TRAUMA_DECAY * elapsed).max(0.0);
+
+        let strength = self.trauma * self.trauma * MAX_SHAKE_OFFSET * self.shake_scale;
+        self.shake = Vec2::new(
+            self.rng.range(-1.0, 1.0) * strength,
+            self.rng.range(-1.0, 1.0) * strength);
+    }
+
+    /// Returns where a point of the world appears on the screen.
+    pub fn point_to_screen(&self, point: Vec2) -> Vec2 {
+        let center = self.viewport / 2.0;
+        (point - self.pos - center) * self.zoom + center + self.shake
+    }
+
+    /// Returns which point of the world appears at `point` on the screen.
+    pub fn point_to_world(&self, point: Vec2) -> Vec2 {
+        let center = self.viewport / 2.0;
+        (point - self.shake - center) / self.zoom + center + self.pos
+    }
+
+    /// Returns where a rectangle of the world should be rendered on the screen.
+    pub fn to_screen(&self, rect: Rectangle) -> Rectangle {
+        let origin = self.point_to_screen(rect.origin());
+
+        Rectangle {
+            x: origin.x,
+            y: origin.y,
+            w: rect.w * self.zoom,
+            h: rect.h * self.zoom,
+        }
+    }
+
+    /// Returns the region of the world which is visible on the screen.
+    pub fn visible(&self) -> Rectangle {
+        let origin = self.point_to_world(Vec2::zero());
+
+        Rectangle {
+            x: origin.x,
+            y: origin.y,
+            w: self.viewport.x / self.zoom,
+            h: self.viewport.y / self.zoom,
+        }
+    }
+}
+
+/// The scale of the shake is a setting of the players, which is not saved.
+impl Snapshot for Camera {
+    fn snapshot(&self) -> Value {
+        table(vec![
+            ("pos", self.pos.snapshot()),
+            ("zoom", self.zoom.snapshot()),
+            ("viewport", self.viewport.snapshot()),
+            ("trauma", self.trauma.snapshot()),
+            ("shake", self.shake.snapshot()),
+            ("rng", self.rng.snapshot()),
+        ])
+    }
+
+    fn restore(value: &Value) -> Result<Camera, String> {
+        Ok(Camera {
+            pos: field(value, "pos")?,
+            zoom: field(value, "zoom")?,
+            shake_scale: 1.0,
+            viewport: field(value, "viewport")?,
+            trauma: field(value, "trauma")?,
+            shake: field(value, "shake")?,
+            rng: field(value, "rng")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn camera_transforms() {
+        use phi::camera::{Camera, MAX_SHAKE_OFFSET};
+        use phi::data::{Rectangle, Vec2};
+
+        let mut camera = Camera::new((800.0, 600.0));
+        let rect = Rectangle { x: 100.0, y: 50.0, w: 40.0, h: 20.0 };
+
+        // By default, world and screen coordinates are the same.
+        assert_eq!(camera.to_screen(rect), rect);
+        assert_eq!(camera.visible(), Rectangle { x: 0.0, y: 0.0, w: 800.0, h: 600.0 });
+
+        camera.pos = Vec2::new(100.0, 0.0);
+        assert_eq!(camera.to_screen(rect), Rectangle { x: 0.0, .. rect });
+
+        // Zooming keeps the center of the screen in place.
+        camera.pos = Vec2::zero();
+        camera.zoom = 2.0;
+        assert_eq!(camera.point_to_screen(Vec2::new(400.0, 300.0)), Vec2::new(400.0, 300.0));
+        assert_eq!(camera.to_screen(rect), Rectangle { x: -200.0, y: -200.0, w: 80.0, h: 40.0 });
+        assert_eq!(camera.visible(), Rectangle { x: 200.0, y: 150.0, w: 400.0, h: 300.0 });
+
+        let point = Vec2::new(123.0, 456.0);
+        assert_eq!(camera.point_to_world(camera.point_to_screen(point)), point);
+
+        // Trauma is clamped, and decays with time.
+        camera.add_trauma(0.7);
+        camera.add_trauma(0.7);
+        assert_eq!(camera.trauma, 1.0);
+
+        // The shake is proportional to the square of the trauma.
+        camera.update(0.5);
+        assert!((camera.trauma - 0.4).abs() < 1e-12);
+        let shaken = camera.to_screen(rect);
+        assert!((shaken.x + 200.0).abs() <= 0.16 * MAX_SHAKE_OFFSET);
+        assert!((shaken.y + 200.0).abs() <= 0.16 * MAX_SHAKE_OFFSET);
+
+        camera.update(10.0);
+        camera.zoom = 1.0;
+        assert_eq!(camera.trauma, 0.0);
+        assert_eq!(camera.to_screen(rect), rect);
+    }
+}
@@ -0,0 +1,391 @@
+use phi::{ImmediateEvents, Phi};
+use phi::data::Rectangle;
+use phi::gfx::{self, Sprite};
+use phi::tween::{Easing, Tween};
+use sdl2::pixels::Color;
+use sdl2::render::BlendMode;
+use std::mem;
+
+const FONT: &str = "assets/belligerent.ttf";
+const FONT_SIZE: u16 = 14;
+
+/// The number of lines of output which are kept, and of commands which are
+/// remembered.
+const OUTPUT_LEN: usize = 100;
+const HISTORY_LEN: usize = 50;
+
+/// The fraction of the screen covered by the console, and the seconds it
+/// takes to drop down.
+const HEIGHT: f64 = 0.4;
+const DROP_DURATION: f64 = 0.15;
+
+/// The key which opens the console types this character, which is ignored.
+const TOGGLE_CHAR: char = '`';
+
+/// Turns the arguments of a command into what the view applies.
+type Parse<T> = Box<dyn Fn(&[&str]) -> Result<T, String>>;
+
+/// A command which can be typed in the console.
+struct Command<T> {
+    name: &'static str,
+    /// The arguments, as shown by `help`.
+    usage: &'static str,
+    help: &'static str,
+    parse: Parse<T>,
+}
+
+/// The commands accepted by a view. Every command is parsed into a `T`,
+/// usually an enum, which the view then applies to itself.
+///
+/// `help` and `clear` are always available.
+pub struct Commands<T> {
+    commands: Vec<Command<T>>,
+}
+
+impl<T> Commands<T> {
+    pub fn new() -> Commands<T> {
+        Commands {
+            commands: Vec::new(),
+        }
+    }
+
+    /// Returns the same commands, along with `name`, whose arguments are
+    /// parsed by `parse`.
+    pub fn add<F>(mut self, name: &'static str, usage: &'static str, help: &'static str, parse: F) -> Commands<T>
+    where F: Fn(&[&str]) -> Result<T, String> + 'static {
+        self.commands.push(Command { name: name, usage: usage, help: help, parse: Box::new(parse) });
+        self
+    }
+
+    /// Parses a line typed in the console.
+    pub fn parse(&self, line: &str) -> Result<T, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let name = words.first().cloned().unwrap_or("");
+
+        match self.commands.iter().find(|command| command.name == name) {
+            Some(command) => (command.parse)(&words[1..])
+                .map_err(|e| format!("{}: {} (usage: {} {})", name, e, name, command.usage)),
+            None => Err(format!("Unknown command `{}`, try `help`", name)),
+        }
+    }
+
+    /// The names of the commands which start with `prefix`, in order.
+    pub fn complete(&self, prefix: &str) -> Vec<&'static str> {
+        let mut names: Vec<&'static str> = self.commands.iter().map(|command| command.name)
+            .chain(vec!["clear", "help"])
+            .filter(|name| name.starts_with(prefix))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn help(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.commands.iter()
+            .map(|command| format!("{} {} - {}", command.name, command.usage, command.help))
+            .collect();
+        lines.push("clear - Clears the console".to_string());
+        lines
+    }
+}
+
+/// Parses the argument `i` of a command, which may be `optional`.
+pub fn arg<T: ::std::str::FromStr>(args: &[&str], i: usize, optional: Option<T>) -> Result<T, String> {
+    match (args.get(i), optional) {
+        (Some(arg), _) => arg.parse().map_err(|_| format!("invalid argument `{}`", arg)),
+        (None, Some(default)) => Ok(default),
+        (None, None) => Err("missing argument".to_string()),
+    }
+}
+
+/// Parses an optional `on` or `off` argument. It is `None` when missing,
+/// which usually means that the setting is toggled.
+pub fn switch(args: &[&str]) -> Result<Option<bool>, String> {
+    match args.first() {
+        None => Ok(None),
+        Some(&"on") => Ok(Some(true)),
+        Some(&"off") => Ok(Some(false)),
+        Some(arg) => Err(format!("expected `on` or `off`, not `{}`", arg)),
+    }
+}
+
+/// A drop-down console, in which commands are typed while the game runs.
+///
+/// The main loop opens and closes it, and gives it the keyboard while it is
+/// open, so that views do not react to what is typed. Views which accept
+/// commands call `update` on every frame to receive them.
+pub struct Console {
+    open: bool,
+    input: String,
+    output: Vec<String>,
+    history: Vec<String>,
+    /// The entry of the history shown in the input, while browsing it.
+    browsing: Option<usize>,
+    /// The line entered, and whether completion was asked for, until the
+    /// view handles them.
+    entered: Option<String>,
+    tab: bool,
+    /// How far the console dropped down, from 0 to 1.
+    drop: Tween<f64>,
+    /// The sprites of the lines shown, kept until their text changes.
+    sprites: Vec<Option<(String, Sprite)>>,
+}
+
+impl Console {
+    pub fn new() -> Console {
+        Console {
+            open: false,
+            input: String::new(),
+            output: Vec::new(),
+            history: Vec::new(),
+            browsing: None,
+            entered: None,
+            tab: false,
+            drop: Tween::idle(0.0, DROP_DURATION, Easing::QuadOut),
+            sprites: Vec::new(),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.drop.retarget(if self.open { 1.0 } else { 0.0 });
+    }
+
+    pub fn print(&mut self, line: &str) {
+        self.output.push(line.to_string());
+        if self.output.len() > OUTPUT_LEN {
+            self.output.remove(0);
+        }
+    }
+
+    /// Called by the main loop. While the console is open, it takes the
+    /// keyboard events away from the view, and edits the input with them.
+    pub fn capture(&mut self, now: &mut ImmediateEvents) {
+        if !self.open {
+            return;
+        }
+
+        // Views which do not call `update` accept no commands.
+        if self.entered.take().is_some() {
+            self.print("There are no commands here");
+        }
+
+        let keys = mem::replace(now, ImmediateEvents::new());
+        now.quit = keys.quit;
+
+        if keys.key_escape == Some(true) {
+            self.toggle();
+            return;
+        }
+
+        self.input.extend(keys.text.chars().filter(|&c| c != TOGGLE_CHAR && !c.is_control()));
+
+        if keys.key_backspace == Some(true) {
+            self.input.pop();
+        }
+
+        if keys.key_up == Some(true) && !self.history.is_empty() {
+            let i = self.browsing.map_or(self.history.len() - 1, |i| i.saturating_sub(1));
+            self.browsing = Some(i);
+            self.input = self.history[i].clone();
+        }
+
+        if keys.key_down == Some(true) {
+            self.browsing = self.browsing.map(|i| i + 1).filter(|&i| i < self.history.len());
+            self.input = self.browsing.map_or(String::new(), |i| self.history[i].clone());
+        }
+
+        if keys.key_tab == Some(true) {
+            self.tab = true;
+        }
+
+        if keys.key_return == Some(true) {
+            let line = self.input.trim().to_string();
+            self.input.clear();
+            self.browsing = None;
+
+            if !line.is_empty() {
+                self.print(&format!("> {}", line));
+                if self.history.last() != Some(&line) {
+                    self.history.push(line.clone());
+                    if self.history.len() > HISTORY_LEN {
+                        self.history.remove(0);
+                    }
+                }
+                self.entered = Some(line);
+            }
+        }
+    }
+
+    /// Completes the name of the command being typed, and returns the
+    /// command which was entered, if any. Errors and the built-in commands
+    /// are handled by the console.
+    pub fn update<T>(&mut self, commands: &Commands<T>) -> Option<T> {
+        if mem::replace(&mut self.tab, false) && !self.input.contains(' ') {
+            let names = commands.complete(&self.input);
+            match names.len() {
+                0 => {},
+                1 => self.input = format!("{} ", names[0]),
+                _ => {
+                    self.print(&names.join("  "));
+                    self.input = common_prefix(&names).to_string();
+                },
+            }
+        }
+
+        let line = self.entered.take()?;
+
+        match line.as_str() {
+            "help" => {
+                for help in commands.help() {
+                    self.print(&help);
+                }
+                None
+            },
+            "clear" => {
+                self.output.clear();
+                None
+            },
+            _ => match commands.parse(&line) {
+                Ok(command) => Some(command),
+                Err(e) => {
+                    self.print(&e);
+                    None
+                },
+            },
+        }
+    }
+}
+
+/// The longest prefix shared by all of the `names`.
+fn common_prefix<'a>(names: &[&'a str]) -> &'a str {
+    let first = names[0];
+    let len = names.iter().map(|name| {
+        first.chars().zip(name.chars()).take_while(|&(a, b)| a == b).map(|(a, _)| a.len_utf8()).sum()
+    }).min().unwrap_or(0);
+    &first[..len]
+}
+
+/// Renders the console on top of the current frame, if it is visible.
+pub fn render(phi: &mut Phi, elapsed: f64) {
+    let drop = phi.console.drop.update(elapsed);
+    if drop <= 0.0 {
+        return;
+    }
+
+    let (win_w, win_h) = phi.output_size();
+    let height = win_h * HEIGHT;
+    let bottom = height * drop;
+
+    phi.renderer.set_blend_mode(BlendMode::Blend);
+    phi.renderer.set_draw_color(Color::RGBA(10, 10, 30, 220));
+    gfx::fill_rect(&mut phi.renderer, Rectangle { x: 0.0, y: bottom - height, w: win_w, h: height });
+
+    // The input comes last, under as many lines of output as fit.
+    let line_h = FONT_SIZE as f64 * 1.4;
+    let fit = ((height / line_h) as usize).saturating_sub(1);
+    let rows: Vec<String> = {
+        let console = &phi.console;
+        let skip = console.output.len().saturating_sub(fit);
+        console.output[skip..].iter().cloned()
+            .chain(Some(format!("> {}_", console.input)))
+            .collect()
+    };
+
+    let mut sprites = mem::take(&mut phi.console.sprites);
+    sprites.resize(rows.len(), None);
+    for (cached, text) in sprites.iter_mut().zip(rows.iter()) {
+        if cached.as_ref().is_none_or(|(t, _)| t != text) {
+            *cached = phi.ttf_str_sprite(text, FONT, FONT_SIZE, Color::RGB(220, 220, 220))
+                .map(|sprite| (text.clone(), sprite));
+        }
+    }
+
+    let mut y = bottom - line_h * rows.len() as f64 - 4.0;
+    for (_, sprite) in sprites.iter().filter_map(Option::as_ref) {
+        let (w, h) = sprite.size();
+        sprite.render(&mut phi.renderer, Rectangle { x: 8.0, y: y, w: w, h: h });
+        y += line_h;
+    }
+    phi.console.sprites = sprites;
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn console_commands() {
+        use phi::ImmediateEvents;
+        use phi::console::{arg, switch, Commands, Console};
+
+        #[derive(Debug, PartialEq)]
+        enum Cheat {
+            Lives(u32),
+            God(Option<bool>),
+        }
+
+        let commands = Commands::new()
+            .add("lives", "<count>", "Sets the lives", |args| arg(args, 0, None).map(Cheat::Lives))
+            .add("god", "[on|off]", "Toggles god mode", |args| switch(args).map(Cheat::God));
+
+        assert_eq!(commands.parse("lives 3"), Ok(Cheat::Lives(3)));
+        assert_eq!(commands.parse("  god   on "), Ok(Cheat::God(Some(true))));
+        assert_eq!(commands.parse("god"), Ok(Cheat::God(None)));
+        assert!(commands.parse("god maybe").is_err());
+        assert!(commands.parse("lives many").is_err());
+        assert!(commands.parse("lives").is_err());
+        assert!(commands.parse("fly").is_err());
+        assert_eq!(commands.complete("g"), vec!["god"]);
+        assert_eq!(commands.complete(""), vec!["clear", "god", "help", "lives"]);
+
+        let type_in = |console: &mut Console, text: &str| {
+            let mut now = ImmediateEvents::new();
+            now.text = text.to_string();
+            console.capture(&mut now);
+            assert!(now.text.is_empty());
+        };
+        let press = |console: &mut Console, set: &dyn Fn(&mut ImmediateEvents)| {
+            let mut now = ImmediateEvents::new();
+            set(&mut now);
+            console.capture(&mut now);
+        };
+
+        // Nothing is captured while the console is closed.
+        let mut console = Console::new();
+        let mut now = ImmediateEvents::new();
+        now.text = "w".to_string();
+        console.capture(&mut now);
+        assert_eq!(now.text, "w");
+
+        console.toggle();
+        type_in(&mut console, "`li");
+        press(&mut console, &|now| now.key_tab = Some(true));
+        assert_eq!(console.update(&commands), None);
+        assert_eq!(console.input, "lives ");
+
+        type_in(&mut console, "5");
+        press(&mut console, &|now| now.key_return = Some(true));
+        assert_eq!(console.update(&commands), Some(Cheat::Lives(5)));
+        assert_eq!(console.update(&commands), None);
+
+        type_in(&mut console, "nope");
+        press(&mut console, &|now| now.key_return = Some(true));
+        assert_eq!(console.update(&commands), None);
+        assert!(console.output.last().unwrap().starts_with("Unknown command"));
+
+        // The history is browsed with the arrows.
+        press(&mut console, &|now| now.key_up = Some(true));
+        press(&mut console, &|now| now.key_up = Some(true));
+        assert_eq!(console.input, "lives 5");
+        press(&mut console, &|now| now.key_down = Some(true));
+        assert_eq!(console.input, "nope");
+        press(&mut console, &|now| now.key_down = Some(true));
+        assert_eq!(console.input, "");
+
+        press(&mut console, &|now| now.key_escape = Some(true));
+        assert!(!console.is_open());
+    }
+}
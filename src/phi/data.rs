@@ -1,4 +1,145 @@
+// The geometry helpers are a toolbox for the views: not all of them are used
+// by the game yet.
+#![allow(dead_code)]
+
 use sdl2::rect::Rect as SdlRect;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+/// A two-dimensional vector, used both for positions and for displacements.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec2 {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Vec2 {
+    pub fn new(x: f64, y: f64) -> Vec2 {
+        Vec2 { x: x, y: y }
+    }
+
+    pub fn zero() -> Vec2 {
+        Vec2 { x: 0.0, y: 0.0 }
+    }
+
+    /// Returns the unit vector pointing in the direction of `angle`, expressed
+    /// in radians. Since the y axis points downwards, positive angles go
+    /// clockwise on the screen.
+    pub fn from_angle(angle: f64) -> Vec2 {
+        Vec2 { x: angle.cos(), y: angle.sin() }
+    }
+
+    pub fn length(self) -> f64 {
+        self.length_sq().sqrt()
+    }
+
+    pub fn length_sq(self) -> f64 {
+        self.dot(self)
+    }
+
+    pub fn distance(self, other: Vec2) -> f64 {
+        (other - self).length()
+    }
+
+    /// Returns a vector of length 1 pointing in the same direction as `self`,
+    /// or the zero vector if `self` has no direction.
+    pub fn normalize(self) -> Vec2 {
+        let len = self.length();
+        if len == 0.0 { self } else { self / len }
+    }
+
+    pub fn dot(self, other: Vec2) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// Returns the z component of the cross product of `self` and `other`. It
+    /// is positive if `other` is clockwise from `self` (on the screen).
+    pub fn cross(self, other: Vec2) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// Linear interpolation: returns `self` for `t == 0`, and `other` for
+    /// `t == 1`.
+    pub fn lerp(self, other: Vec2, t: f64) -> Vec2 {
+        self + (other - self) * t
+    }
+
+    /// Returns the angle of `self` in radians, in the range `[-pi, pi]`.
+    pub fn angle(self) -> f64 {
+        self.y.atan2(self.x)
+    }
+
+    /// Returns the signed angle needed to rotate `self` onto `other`.
+    pub fn angle_to(self, other: Vec2) -> f64 {
+        self.cross(other).atan2(self.dot(other))
+    }
+
+    pub fn rotate(self, angle: f64) -> Vec2 {
+        let (sin, cos) = angle.sin_cos();
+        Vec2 {
+            x: self.x * cos - self.y * sin,
+            y: self.x * sin + self.y * cos,
+        }
+    }
+
+    /// Returns the vector rotated by a quarter turn clockwise (on the screen).
+    pub fn perp(self) -> Vec2 {
+        Vec2 { x: -self.y, y: self.x }
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Vec2;
+
+    fn add(self, rhs: Vec2) -> Vec2 {
+        Vec2 { x: self.x + rhs.x, y: self.y + rhs.y }
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Vec2;
+
+    fn sub(self, rhs: Vec2) -> Vec2 {
+        Vec2 { x: self.x - rhs.x, y: self.y - rhs.y }
+    }
+}
+
+impl Mul<f64> for Vec2 {
+    type Output = Vec2;
+
+    fn mul(self, rhs: f64) -> Vec2 {
+        Vec2 { x: self.x * rhs, y: self.y * rhs }
+    }
+}
+
+impl Div<f64> for Vec2 {
+    type Output = Vec2;
+
+    fn div(self, rhs: f64) -> Vec2 {
+        Vec2 { x: self.x / rhs, y: self.y / rhs }
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Vec2;
+
+    fn neg(self) -> Vec2 {
+        Vec2 { x: -self.x, y: -self.y }
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, rhs: Vec2) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+    }
+}
+
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, rhs: Vec2) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+    }
+}
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Rectangle {
@@ -9,6 +150,74 @@ pub struct Rectangle {
 }
 
 impl Rectangle {
+    /// Creates a rectangle of the given dimensions, centered on `center`.
+    pub fn from_center(center: Vec2, w: f64, h: f64) -> Rectangle {
+        Rectangle {
+            x: center.x - w / 2.0,
+            y: center.y - h / 2.0,
+            w: w,
+            h: h,
+        }
+    }
+
+    /// Returns the position of the top-left corner.
+    pub fn origin(&self) -> Vec2 {
+        Vec2 { x: self.x, y: self.y }
+    }
+
+    pub fn center(&self) -> Vec2 {
+        Vec2 {
+            x: self.x + self.w / 2.0,
+            y: self.y + self.h / 2.0,
+        }
+    }
+
+    /// Returns a copy of `self` moved by `offset`.
+    pub fn translate(self, offset: Vec2) -> Rectangle {
+        Rectangle {
+            x: self.x + offset.x,
+            y: self.y + offset.y,
+            .. self
+        }
+    }
+
+    /// Returns a copy of `self` with its dimensions multiplied by `factor`,
+    /// keeping the same center.
+    pub fn scale(self, factor: f64) -> Rectangle {
+        Rectangle::from_center(self.center(), self.w * factor, self.h * factor)
+    }
+
+    /// Returns the region covered by both `self` and `other`, or `None` if
+    /// they do not overlap.
+    pub fn intersection(&self, other: Rectangle) -> Option<Rectangle> {
+        if !self.overlaps(other) {
+            return None;
+        }
+
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+
+        Some(Rectangle {
+            x: x,
+            y: y,
+            w: (self.x + self.w).min(other.x + other.w) - x,
+            h: (self.y + self.h).min(other.y + other.h) - y,
+        })
+    }
+
+    /// Returns the smallest rectangle containing both `self` and `other`.
+    pub fn union(&self, other: Rectangle) -> Rectangle {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+
+        Rectangle {
+            x: x,
+            y: y,
+            w: (self.x + self.w).max(other.x + other.w) - x,
+            h: (self.y + self.h).max(other.y + other.h) - y,
+        }
+    }
+
     /// Generates an SDL-compatible Rect equivalent to `self`.
     /// Panics if it could not be created, for example if a
     /// coordinate of a corner overflows an `i32`.
@@ -49,6 +258,11 @@ impl Rectangle {
         y_max >= self.y && y_max <= self.y + self.h
     }
 
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        point.x >= self.x && point.x <= self.x + self.w &&
+        point.y >= self.y && point.y <= self.y + self.h
+    }
+
     pub fn overlaps(&self, other: Rectangle) -> bool {
         self.x < other.x + other.w &&
         other.x < self.x + self.w &&
@@ -57,6 +271,361 @@ impl Rectangle {
     }
 }
 
+/// A circle, described by its center and radius.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Circle {
+    pub x: f64,
+    pub y: f64,
+    pub r: f64,
+}
+
+/// A line segment "swept" by a circle, i.e. a stadium shape. Its ends are
+/// the centers of the two half-circles. This is a good fit for elongated
+/// objects such as ship hulls or lasers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Capsule {
+    pub x1: f64,
+    pub y1: f64,
+    pub x2: f64,
+    pub y2: f64,
+    pub r: f64,
+}
+
+/// A convex polygon, with its vertices listed in order (either clockwise or
+/// counter-clockwise). The result of the intersection tests is undefined for
+/// concave polygons.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon {
+    pub points: Vec<Vec2>,
+}
+
+impl Polygon {
+    /// Returns `None` if there are fewer than 3 `points`, which would not
+    /// enclose anything.
+    pub fn new(points: Vec<Vec2>) -> Option<Polygon> {
+        if points.len() < 3 {
+            None
+        } else {
+            Some(Polygon { points: points })
+        }
+    }
+}
+
+/// Any of the shapes which can be tested against each other for intersection.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Rect(Rectangle),
+    Circle(Circle),
+    Capsule(Capsule),
+    Polygon(Polygon),
+}
+
+impl Shape {
+    /// Returns whether `self` and `other` share at least one point which is
+    /// not on their border.
+    pub fn overlaps(&self, other: &Shape) -> bool {
+        match (self.as_round(), other.as_round()) {
+            (Some((a1, a2, ra)), Some((b1, b2, rb))) =>
+                segment_segment_distance(a1, a2, b1, b2) < ra + rb,
+
+            (Some((a1, a2, r)), None) =>
+                segment_polygon_distance(a1, a2, &other.polygon_points()) < r,
+
+            (None, Some((b1, b2, r))) =>
+                segment_polygon_distance(b1, b2, &self.polygon_points()) < r,
+
+            (None, None) =>
+                polygons_overlap(&self.polygon_points(), &other.polygon_points()),
+        }
+    }
+
+    /// Returns a copy of `self` moved by `offset`.
+    pub fn translate(&self, offset: Vec2) -> Shape {
+        match *self {
+            Shape::Rect(r) =>
+                Shape::Rect(r.translate(offset)),
+
+            Shape::Circle(c) =>
+                Shape::Circle(Circle { x: c.x + offset.x, y: c.y + offset.y, .. c }),
+
+            Shape::Capsule(c) =>
+                Shape::Capsule(Capsule {
+                    x1: c.x1 + offset.x, y1: c.y1 + offset.y,
+                    x2: c.x2 + offset.x, y2: c.y2 + offset.y,
+                    .. c
+                }),
+
+            Shape::Polygon(ref p) =>
+                Shape::Polygon(Polygon {
+                    points: p.points.iter().map(|&point| point + offset).collect()
+                }),
+        }
+    }
+
+    /// Returns the smallest axis-aligned rectangle containing the shape.
+    pub fn bounding_box(&self) -> Rectangle {
+        match *self {
+            Shape::Rect(r) => r,
+
+            Shape::Circle(c) =>
+                Rectangle { x: c.x - c.r, y: c.y - c.r, w: 2.0 * c.r, h: 2.0 * c.r },
+
+            Shape::Capsule(c) => {
+                let x = c.x1.min(c.x2) - c.r;
+                let y = c.y1.min(c.y2) - c.r;
+                Rectangle {
+                    x: x,
+                    y: y,
+                    w: c.x1.max(c.x2) + c.r - x,
+                    h: c.y1.max(c.y2) + c.r - y,
+                }
+            },
+
+            Shape::Polygon(ref p) => {
+                let x_min = p.points.iter().fold(f64::INFINITY, |acc, p| acc.min(p.x));
+                let x_max = p.points.iter().fold(f64::NEG_INFINITY, |acc, p| acc.max(p.x));
+                let y_min = p.points.iter().fold(f64::INFINITY, |acc, p| acc.min(p.y));
+                let y_max = p.points.iter().fold(f64::NEG_INFINITY, |acc, p| acc.max(p.y));
+                Rectangle { x: x_min, y: y_min, w: x_max - x_min, h: y_max - y_min }
+            },
+        }
+    }
+
+    /// Circles and capsules are both handled as a segment and a radius (a
+    /// circle being a segment of length zero).
+    fn as_round(&self) -> Option<(Vec2, Vec2, f64)> {
+        match *self {
+            Shape::Circle(c) => Some((Vec2::new(c.x, c.y), Vec2::new(c.x, c.y), c.r)),
+            Shape::Capsule(c) => Some((Vec2::new(c.x1, c.y1), Vec2::new(c.x2, c.y2), c.r)),
+            _ => None,
+        }
+    }
+
+    fn polygon_points(&self) -> Vec<Vec2> {
+        match *self {
+            Shape::Rect(r) => vec![
+                Vec2::new(r.x, r.y),
+                Vec2::new(r.x + r.w, r.y),
+                Vec2::new(r.x + r.w, r.y + r.h),
+                Vec2::new(r.x, r.y + r.h),
+            ],
+            Shape::Polygon(ref p) => p.points.clone(),
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn point_segment_distance(p: Vec2, a: Vec2, b: Vec2) -> f64 {
+    let ab = b - a;
+    let len_sq = ab.length_sq();
+
+    // Project `p` on the segment, and clamp the result to its ends.
+    let t = if len_sq == 0.0 { 0.0 } else { ((p - a).dot(ab) / len_sq).clamp(0.0, 1.0) };
+
+    p.distance(a + ab * t)
+}
+
+fn segments_intersect(a1: Vec2, a2: Vec2, b1: Vec2, b2: Vec2) -> bool {
+    let d1 = (a2 - a1).cross(b1 - a1);
+    let d2 = (a2 - a1).cross(b2 - a1);
+    let d3 = (b2 - b1).cross(a1 - b1);
+    let d4 = (b2 - b1).cross(a2 - b1);
+
+    // Collinear and touching segments are handled by the distance tests, which
+    // report them as being at distance zero.
+    ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0)) &&
+    ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+}
+
+fn segment_segment_distance(a1: Vec2, a2: Vec2, b1: Vec2, b2: Vec2) -> f64 {
+    if segments_intersect(a1, a2, b1, b2) {
+        return 0.0;
+    }
+
+    point_segment_distance(a1, b1, b2)
+        .min(point_segment_distance(a2, b1, b2))
+        .min(point_segment_distance(b1, a1, a2))
+        .min(point_segment_distance(b2, a1, a2))
+}
+
+fn point_in_polygon(p: Vec2, points: &[Vec2]) -> bool {
+    // For a convex polygon, the point is inside iff it lies on the same side
+    // of every edge.
+    let mut sign = 0.0;
+
+    for i in 0..points.len() {
+        let a = points[i];
+        let b = points[(i + 1) % points.len()];
+        let side = (b - a).cross(p - a);
+
+        if side == 0.0 {
+            continue;
+        } else if sign == 0.0 {
+            sign = side.signum();
+        } else if side.signum() != sign {
+            return false;
+        }
+    }
+
+    true
+}
+
+fn segment_polygon_distance(a: Vec2, b: Vec2, points: &[Vec2]) -> f64 {
+    if point_in_polygon(a, points) || point_in_polygon(b, points) {
+        return 0.0;
+    }
+
+    (0..points.len())
+        .map(|i| segment_segment_distance(a, b, points[i], points[(i + 1) % points.len()]))
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Separating axis test: two convex polygons do not overlap iff there is an
+/// edge of one of them along which their projections are disjoint.
+fn polygons_overlap(a: &[Vec2], b: &[Vec2]) -> bool {
+    for points in &[a, b] {
+        for i in 0..points.len() {
+            let axis = (points[(i + 1) % points.len()] - points[i]).perp();
+
+            let project = |poly: &[Vec2]| {
+                poly.iter().map(|&p| p.dot(axis))
+                    .fold((f64::INFINITY, f64::NEG_INFINITY),
+                          |(min, max), d| (min.min(d), max.max(d)))
+            };
+
+            let (a_min, a_max) = project(a);
+            let (b_min, b_max) = project(b);
+
+            if a_max <= b_min || b_max <= a_min {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+/// The solid parts of a sprite, which are usually much smaller than the
+/// rectangle it is rendered to. The shapes are expressed relative to the
+/// top-left corner of that rectangle.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hitbox {
+    shapes: Vec<Shape>,
+}
+
+impl Hitbox {
+    pub fn new(shapes: Vec<Shape>) -> Hitbox {
+        Hitbox {
+            shapes: shapes,
+        }
+    }
+
+    pub fn shapes(&self) -> &[Shape] {
+        &self.shapes
+    }
+
+    /// Returns the hitbox of a sprite rendered to `dest`, in the same
+    /// coordinates as `dest`.
+    pub fn at(&self, dest: Rectangle) -> Hitbox {
+        Hitbox {
+            shapes: self.shapes.iter().map(|s| s.translate(dest.origin())).collect(),
+        }
+    }
+
+    pub fn overlaps(&self, other: &Hitbox) -> bool {
+        self.shapes.iter().any(|a| other.shapes.iter().any(|b| a.overlaps(b)))
+    }
+
+    pub fn overlaps_shape(&self, shape: &Shape) -> bool {
+        self.shapes.iter().any(|a| a.overlaps(shape))
+    }
+}
+
+/// Tells which pixels of an image are opaque, for pixel-perfect collision
+/// detection. Those tests are much more expensive than `Hitbox` ones, and
+/// should only be performed once the hitboxes are known to overlap.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PixelMask {
+    w: usize,
+    h: usize,
+    solid: Vec<bool>,
+}
+
+impl PixelMask {
+    /// Creates a mask from the row-major `solid` flags of a `w`x`h` image.
+    pub fn new(w: usize, h: usize, solid: Vec<bool>) -> PixelMask {
+        assert_eq!(solid.len(), w * h);
+
+        PixelMask {
+            w: w,
+            h: h,
+            solid: solid,
+        }
+    }
+
+    /// Returns whether the pixel at `(x, y)` is opaque. Pixels outside of the
+    /// mask are considered transparent.
+    pub fn is_solid(&self, x: i64, y: i64) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.w && (y as usize) < self.h &&
+        self.solid[y as usize * self.w + x as usize]
+    }
+
+    /// Returns the mask of a sub-region of the current one, in the same way
+    /// as `Sprite::region`.
+    pub fn region(&self, rect: Rectangle) -> PixelMask {
+        let (x0, y0) = (rect.x as i64, rect.y as i64);
+        let (w, h) = (rect.w as usize, rect.h as usize);
+        let mut solid = Vec::with_capacity(w * h);
+
+        for y in 0..h {
+            for x in 0..w {
+                solid.push(self.is_solid(x0 + x as i64, y0 + y as i64));
+            }
+        }
+
+        PixelMask::new(w, h, solid)
+    }
+
+    /// Returns the mask of the mirrored image.
+    pub fn flip_horizontal(&self) -> PixelMask {
+        let mut solid = Vec::with_capacity(self.solid.len());
+
+        for y in 0..self.h {
+            for x in (0..self.w).rev() {
+                solid.push(self.solid[y * self.w + x]);
+            }
+        }
+
+        PixelMask::new(self.w, self.h, solid)
+    }
+
+    /// Returns whether an opaque pixel of `self`, stretched over `rect`,
+    /// covers an opaque pixel of `other` stretched over `other_rect`. The test
+    /// samples the center of every pixel of the world where both overlap.
+    pub fn overlaps(&self, rect: Rectangle, other: &PixelMask, other_rect: Rectangle) -> bool {
+        let both = match rect.intersection(other_rect) {
+            Some(both) => both,
+            None => return false,
+        };
+
+        // The pixel of `mask`, stretched over `rect`, at the point `(x, y)`.
+        let solid_at = |mask: &PixelMask, rect: Rectangle, x: f64, y: f64| mask.is_solid(
+            ((x - rect.x) * mask.w as f64 / rect.w).floor() as i64,
+            ((y - rect.y) * mask.h as f64 / rect.h).floor() as i64);
+
+        for py in both.y.floor() as i64..(both.y + both.h).ceil() as i64 {
+            for px in both.x.floor() as i64..(both.x + both.w).ceil() as i64 {
+                let (x, y) = (px as f64 + 0.5, py as f64 + 0.5);
+                if solid_at(self, rect, x, y) && solid_at(other, other_rect, x, y) {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+}
+
 #[cfg(test)]
 mod test {
     #[test]
@@ -110,4 +679,169 @@ mod test {
         assert!(bounding_rect.contains(inside_rect));
         assert!(bounding_rect.overlaps(inside_rect));
     }
+
+    #[test]
+    fn vec_basics() {
+        use phi::data::Vec2;
+        use std::f64::consts::PI;
+
+        let a = Vec2::new(3.0, 4.0);
+        let b = Vec2::new(-1.0, 2.0);
+
+        // Operators
+        assert_eq!(a + b, Vec2::new(2.0, 6.0));
+        assert_eq!(a - b, Vec2::new(4.0, 2.0));
+        assert_eq!(a * 2.0, Vec2::new(6.0, 8.0));
+        assert_eq!(a / 2.0, Vec2::new(1.5, 2.0));
+        assert_eq!(-a, Vec2::new(-3.0, -4.0));
+
+        let mut c = a;
+        c += b;
+        assert_eq!(c, Vec2::new(2.0, 6.0));
+        c -= b;
+        assert_eq!(c, a);
+
+        // Lengths
+        assert_eq!(a.length(), 5.0);
+        assert_eq!(a.length_sq(), 25.0);
+        assert_eq!(a.distance(Vec2::new(3.0, 0.0)), 4.0);
+        assert_eq!(a.normalize(), Vec2::new(0.6, 0.8));
+        assert_eq!(Vec2::zero().normalize(), Vec2::zero());
+
+        let diagonal = Vec2::new(1.0, 1.0).normalize();
+        assert!((diagonal.length() - 1.0).abs() < 1e-12);
+        assert!((diagonal.x - 1.0 / 2.0f64.sqrt()).abs() < 1e-12);
+
+        // Products
+        assert_eq!(a.dot(b), 5.0);
+        assert_eq!(a.cross(b), 10.0);
+        assert_eq!(b.cross(a), -10.0);
+        assert_eq!(a.dot(a.perp()), 0.0);
+
+        // Interpolation
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 1.0), b);
+        assert_eq!(a.lerp(b, 0.5), Vec2::new(1.0, 3.0));
+
+        // Angles
+        let right = Vec2::new(1.0, 0.0);
+        let down = Vec2::new(0.0, 1.0);
+        assert_eq!(right.angle(), 0.0);
+        assert_eq!(down.angle(), PI / 2.0);
+        assert_eq!(right.angle_to(down), PI / 2.0);
+        assert_eq!(down.angle_to(right), -PI / 2.0);
+        assert!(Vec2::from_angle(PI / 2.0).distance(down) < 1e-12);
+        assert!(right.rotate(PI / 2.0).distance(down) < 1e-12);
+        assert!(a.rotate(1.234).rotate(-1.234).distance(a) < 1e-12);
+    }
+
+    #[test]
+    fn rect_operations() {
+        use phi::data::{Rectangle, Vec2};
+
+        let rect = Rectangle { x: 1.0, y: 2.0, w: 4.0, h: 2.0 };
+
+        assert_eq!(rect.origin(), Vec2::new(1.0, 2.0));
+        assert_eq!(rect.center(), Vec2::new(3.0, 3.0));
+        assert_eq!(Rectangle::from_center(rect.center(), 4.0, 2.0), rect);
+
+        assert_eq!(rect.translate(Vec2::new(-1.0, 1.0)), Rectangle { x: 0.0, y: 3.0, .. rect });
+
+        let scaled = rect.scale(2.0);
+        assert_eq!(scaled, Rectangle { x: -1.0, y: 1.0, w: 8.0, h: 4.0 });
+        assert_eq!(scaled.center(), rect.center());
+        assert!(scaled.contains(rect));
+
+        // Intersections
+        let other = Rectangle { x: 4.0, y: 3.0, w: 3.0, h: 3.0 };
+        assert_eq!(rect.intersection(other), Some(Rectangle { x: 4.0, y: 3.0, w: 1.0, h: 1.0 }));
+        assert_eq!(other.intersection(rect), rect.intersection(other));
+        assert_eq!(rect.intersection(scaled), Some(rect));
+        assert_eq!(rect.intersection(Rectangle { x: 5.0, .. rect }), None);
+
+        // Unions
+        assert_eq!(rect.union(other), Rectangle { x: 1.0, y: 2.0, w: 6.0, h: 4.0 });
+        assert_eq!(other.union(rect), rect.union(other));
+        assert_eq!(rect.union(scaled), scaled);
+    }
+
+    #[test]
+    fn shape_intersections() {
+        use phi::data::{Capsule, Circle, Polygon, Rectangle, Shape, Vec2};
+
+        let rect = Shape::Rect(Rectangle { x: 0.0, y: 0.0, w: 2.0, h: 2.0 });
+
+        // Circles
+        let circle = Shape::Circle(Circle { x: 3.0, y: 1.0, r: 1.5 });
+        assert!(rect.overlaps(&circle));
+        assert!(circle.overlaps(&rect));
+
+        let far_circle = Shape::Circle(Circle { x: 3.0, y: 3.0, r: 1.0 });
+        assert!(!rect.overlaps(&far_circle));
+        assert!(!circle.overlaps(&Shape::Circle(Circle { x: 6.0, y: 1.0, r: 1.5 })));
+        assert!(circle.overlaps(&Shape::Circle(Circle { x: 5.0, y: 1.0, r: 1.0 })));
+
+        // A circle inside of a rectangle
+        assert!(rect.overlaps(&Shape::Circle(Circle { x: 1.0, y: 1.0, r: 0.1 })));
+
+        // Capsules
+        let capsule = Shape::Capsule(Capsule { x1: -2.0, y1: 3.0, x2: 4.0, y2: 3.0, r: 0.5 });
+        assert!(!rect.overlaps(&capsule));
+        assert!(capsule.overlaps(&far_circle));
+        assert!(!capsule.overlaps(&circle));
+
+        let crossing = Shape::Capsule(Capsule { x1: 1.0, y1: -5.0, x2: 1.0, y2: 5.0, r: 0.1 });
+        assert!(crossing.overlaps(&capsule));
+        assert!(crossing.overlaps(&rect));
+
+        // Polygons
+        let diamond = Shape::Polygon(Polygon::new(
+            vec![Vec2::new(3.0, 0.0), Vec2::new(4.0, 1.0), Vec2::new(3.0, 2.0), Vec2::new(2.0, 1.0)]
+        ).unwrap());
+        assert_eq!(Polygon::new(vec![Vec2::zero(), Vec2::new(1.0, 1.0)]), None);
+        assert!(!rect.overlaps(&diamond));
+        assert!(diamond.overlaps(&circle));
+        assert!(diamond.overlaps(&rect.translate(Vec2::new(0.5, 0.0))));
+        assert!(!diamond.overlaps(&Shape::Circle(Circle { x: 2.0, y: 0.0, r: 0.5 })));
+
+        assert_eq!(diamond.bounding_box(), Rectangle { x: 2.0, y: 0.0, w: 2.0, h: 2.0 });
+        assert_eq!(capsule.bounding_box(), Rectangle { x: -2.5, y: 2.5, w: 7.0, h: 1.0 });
+    }
+
+    #[test]
+    fn pixel_masks() {
+        use phi::data::{PixelMask, Rectangle};
+
+        let at = |x: f64, y: f64, size: f64| Rectangle { x: x, y: y, w: size, h: size };
+
+        // A 3x3 mask with a single opaque pixel in its center
+        let dot = PixelMask::new(3, 3, vec![
+            false, false, false,
+            false, true,  false,
+            false, false, false,
+        ]);
+
+        assert!(dot.overlaps(at(0.0, 0.0, 3.0), &dot, at(0.0, 0.0, 3.0)));
+        assert!(!dot.overlaps(at(0.0, 0.0, 3.0), &dot, at(1.0, 0.0, 3.0)));
+        assert!(!dot.overlaps(at(0.0, 0.0, 3.0), &dot, at(3.0, 3.0, 3.0)));
+
+        let full = PixelMask::new(2, 2, vec![true; 4]);
+        assert!(dot.overlaps(at(0.0, 0.0, 3.0), &full, at(1.5, 1.5, 2.0)));
+        assert!(!dot.overlaps(at(0.0, 0.0, 3.0), &full, at(2.0, 0.0, 2.0)));
+
+        // Masks are stretched over their rectangle: the dot now covers the
+        // pixels from 2 to 4.
+        assert!(dot.overlaps(at(0.0, 0.0, 6.0), &full, at(3.5, 3.5, 1.0)));
+        assert!(!dot.overlaps(at(0.0, 0.0, 6.0), &full, at(4.0, 4.0, 1.0)));
+
+        let region = dot.region(Rectangle { x: 1.0, y: 1.0, w: 2.0, h: 2.0 });
+        assert!(region.is_solid(0, 0));
+        assert!(!region.is_solid(1, 1));
+        assert!(!region.is_solid(2, 0));
+
+        let flipped = region.flip_horizontal();
+        assert!(flipped.is_solid(1, 0));
+        assert!(!flipped.is_solid(0, 0));
+        assert_eq!(flipped.flip_horizontal(), region);
+    }
 }
@@ -0,0 +1,165 @@
+use phi::Phi;
+use phi::data::{Circle, Rectangle, Shape, Vec2};
+use phi::gfx::{self, Sprite};
+use phi::perf;
+use sdl2::pixels::Color;
+use sdl2::rect::Point;
+use std::collections::HashMap;
+use std::mem;
+
+const FONT: &str = "assets/belligerent.ttf";
+const FONT_SIZE: u16 = 12;
+
+/// Something to draw, in the coordinates of the world.
+#[derive(Clone, Debug, PartialEq)]
+enum Primitive {
+    Shape(Shape),
+    /// Straight lines joining the points, in order.
+    Lines(Vec<Vec2>),
+    /// Text whose top-left corner is at the point.
+    Label(Vec2, String),
+}
+
+/// Draws outlines and labels on top of the scene, to see what the game is
+/// doing.
+///
+/// Views describe what to show while they update, in the coordinates of the
+/// world; the main loop draws it through the camera once the view is
+/// rendered, then forgets it. Nothing is recorded while the layer is hidden,
+/// so views can submit primitives unconditionally, although they should skip
+/// expensive computations unless it is `enabled`.
+pub struct DebugDraw {
+    pub enabled: bool,
+    primitives: Vec<(Primitive, Color)>,
+    /// The text of the labels drawn during the last frame.
+    labels: HashMap<String, Sprite>,
+}
+
+impl DebugDraw {
+    pub fn new() -> DebugDraw {
+        DebugDraw {
+            enabled: false,
+            primitives: Vec::new(),
+            labels: HashMap::new(),
+        }
+    }
+
+    fn push(&mut self, primitive: Primitive, color: Color) {
+        if self.enabled {
+            self.primitives.push((primitive, color));
+        }
+    }
+
+    /// Outlines a shape, such as one of those of a hitbox.
+    pub fn shape(&mut self, shape: &Shape, color: Color) {
+        self.push(Primitive::Shape(shape.clone()), color);
+    }
+
+    pub fn rect(&mut self, rect: Rectangle, color: Color) {
+        self.push(Primitive::Shape(Shape::Rect(rect)), color);
+    }
+
+    pub fn circle(&mut self, center: Vec2, radius: f64, color: Color) {
+        self.push(Primitive::Shape(Shape::Circle(Circle { x: center.x, y: center.y, r: radius })), color);
+    }
+
+    pub fn line(&mut self, from: Vec2, to: Vec2, color: Color) {
+        self.push(Primitive::Lines(vec![from, to]), color);
+    }
+
+    /// Joins `points` with straight lines, to show a path.
+    pub fn path(&mut self, points: Vec<Vec2>, color: Color) {
+        if points.len() > 1 {
+            self.push(Primitive::Lines(points), color);
+        }
+    }
+
+    pub fn label(&mut self, pos: Vec2, text: &str, color: Color) {
+        self.push(Primitive::Label(pos, text.to_string()), color);
+    }
+
+    /// Forgets what was submitted, which is done once every frame.
+    pub fn clear(&mut self) {
+        self.primitives.clear();
+    }
+}
+
+/// Draws what was submitted during the frame on top of it, then forgets it.
+pub fn render(phi: &mut Phi) {
+    let primitives = mem::take(&mut phi.debug.primitives);
+    if !phi.debug.enabled {
+        phi.debug.labels.clear();
+        return;
+    }
+
+    // Labels are rendered in white, and tinted. Those which were not drawn
+    // this frame are dropped, so that the cache does not grow forever.
+    let mut cached = mem::take(&mut phi.debug.labels);
+    let mut labels = HashMap::new();
+
+    for (primitive, color) in primitives {
+        phi.renderer.set_draw_color(color);
+
+        match primitive {
+            Primitive::Shape(shape) =>
+                gfx::draw_shape(&mut phi.renderer, &phi.camera, &shape),
+
+            Primitive::Lines(points) => {
+                let points: Vec<Point> = points.into_iter()
+                    .map(|point| phi.camera.point_to_screen(point))
+                    .map(|point| Point::new(point.x as i32, point.y as i32))
+                    .collect();
+                perf::draw_call();
+                phi.renderer.draw_lines(&points).unwrap();
+            },
+
+            Primitive::Label(pos, text) => {
+                let sprite = match cached.remove(&text).or_else(|| labels.get(&text).cloned()) {
+                    Some(sprite) => sprite,
+                    None => match phi.ttf_str_sprite(&text, FONT, FONT_SIZE, Color::RGB(255, 255, 255)) {
+                        Some(sprite) => sprite,
+                        None => continue,
+                    },
+                };
+
+                let (w, h) = sprite.size();
+                let origin = phi.camera.point_to_screen(pos);
+                sprite.tinted(color).render(&mut phi.renderer, Rectangle { x: origin.x, y: origin.y, w: w, h: h });
+                labels.insert(text, sprite);
+            },
+        }
+    }
+
+    phi.debug.labels = labels;
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn debug_draw_queue() {
+        use phi::data::{Rectangle, Vec2};
+        use phi::debug::DebugDraw;
+        use sdl2::pixels::Color;
+
+        let white = Color::RGB(255, 255, 255);
+        let mut debug = DebugDraw::new();
+
+        // Nothing is recorded while the layer is hidden.
+        debug.rect(Rectangle { x: 0.0, y: 0.0, w: 10.0, h: 10.0 }, white);
+        debug.label(Vec2::zero(), "hidden", white);
+        assert_eq!(debug.primitives.len(), 0);
+
+        debug.enabled = true;
+        debug.circle(Vec2::new(5.0, 5.0), 3.0, white);
+        debug.line(Vec2::zero(), Vec2::new(1.0, 1.0), white);
+        debug.label(Vec2::zero(), "shown", white);
+
+        // A path needs two points at least.
+        debug.path(vec![Vec2::zero()], white);
+        debug.path(vec![Vec2::zero(), Vec2::new(1.0, 0.0), Vec2::new(1.0, 1.0)], white);
+        assert_eq!(debug.primitives.len(), 4);
+
+        debug.clear();
+        assert_eq!(debug.primitives.len(), 0);
+    }
+}
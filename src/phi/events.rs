@@ -4,12 +4,30 @@ macro_rules! struct_events {
         other: { $( $e_alias:ident : $e_sdl:pat ),* }
     ) => {
         use sdl2::EventPump;
+        use sdl2::GameControllerSubsystem;
+        use sdl2::controller::{Button, GameController};
+
+        /// The keys which are listened to, so that players' controls can
+        /// refer to them. Only some of them are bound to a player.
+        #[allow(dead_code)]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum Key {
+            $( $k_sdl, )*
+        }
 
         pub struct ImmediateEvents {
             /// "Debounced" keyboard events: will be Some(true/false) only if the key was just pressed
             $( pub $k_alias: Option<bool>, )*
             /// Other events
             $( pub $e_alias: bool, )*
+            /// The text typed since the last pump, which takes the keyboard
+            /// layout into account.
+            pub text: String,
+            /// Whether the mouse moved, and the left button was clicked.
+            pub mouse_moved: bool,
+            pub click: bool,
+            /// The buttons of the game controllers which were just pressed.
+            pub buttons: Vec<Button>,
         }
 
         impl ImmediateEvents {
@@ -17,33 +35,85 @@ macro_rules! struct_events {
                 ImmediateEvents {
                     $( $k_alias: None, )*
                     $( $e_alias: false, )*
+                    text: String::new(),
+                    mouse_moved: false,
+                    click: false,
+                    buttons: Vec::new(),
                 }
             }
+
+            /// Returns whether the player just pressed any of the keys which
+            /// are listened to, clicked, or pressed a controller button.
+            pub fn any(&self) -> bool {
+                false $( || self.$k_alias == Some(true) )* || self.click || !self.buttons.is_empty()
+            }
         }
 
 
         pub struct Events {
             pump: EventPump,
             pub now: ImmediateEvents,
+            /// The position of the mouse on the screen, in pixels.
+            pub mouse: (f64, f64),
+
+            /// Controllers must be kept open to receive their events. Only
+            /// the keyboard and the mouse are read without the subsystem.
+            controller_subsystem: Option<GameControllerSubsystem>,
+            controllers: Vec<GameController>,
 
             /// "Raw" keyboard events: these hold the pressed status of a key
             $( pub $k_alias: bool, )*
         }
 
         impl Events {
-            pub fn new(pump: EventPump) -> Events {
+            pub fn new(pump: EventPump, controller_subsystem: Option<GameControllerSubsystem>) -> Events {
                 Events {
                     pump: pump,
                     now: ImmediateEvents::new(),
+                    mouse: (0.0, 0.0),
+                    controller_subsystem: controller_subsystem,
+                    controllers: Vec::new(),
                     $( $k_alias: false, )*
                 }
             }
 
+            fn open_controller(&mut self, id: u32) {
+                let subsystem = match self.controller_subsystem {
+                    Some(ref subsystem) => subsystem,
+                    None => return,
+                };
+
+                if subsystem.is_game_controller(id) {
+                    match subsystem.open(id) {
+                        Ok(controller) => self.controllers.push(controller),
+                        Err(e) => println!("Could not open controller {}: {:?}", id, e),
+                    }
+                }
+            }
+
+            /// Returns whether `key` is currently held down.
+            pub fn is_down(&self, key: Key) -> bool {
+                match key {
+                    $( Key::$k_sdl => self.$k_alias, )*
+                }
+            }
+
+            /// Returns whether `key` was pressed since the last pump.
+            pub fn just_pressed(&self, key: Key) -> bool {
+                match key {
+                    $( Key::$k_sdl => self.now.$k_alias == Some(true), )*
+                }
+            }
+
             pub fn pump(&mut self) {
                 // These are created from scratch on each pass because they only
                 // hold the events of the current pass.
                 self.now = ImmediateEvents::new();
 
+                // SDL reports the controllers plugged in before the game
+                // started too. They are opened once the events were read.
+                let mut added = Vec::new();
+
                 for event in self.pump.poll_iter() {
                     use sdl2::event::Event::*;
                     use sdl2::keyboard::Keycode::*;
@@ -77,6 +147,28 @@ macro_rules! struct_events {
                             _ => {}
                         },
 
+                        TextInput { text, .. } => {
+                            self.now.text.push_str(&text);
+                        },
+
+                        MouseMotion { x, y, .. } => {
+                            self.mouse = (x as f64, y as f64);
+                            self.now.mouse_moved = true;
+                        },
+
+                        MouseButtonDown { mouse_btn: ::sdl2::mouse::Mouse::Left, x, y, .. } => {
+                            self.mouse = (x as f64, y as f64);
+                            self.now.click = true;
+                        },
+
+                        ControllerButtonDown { button, .. } => {
+                            self.now.buttons.push(button);
+                        },
+
+                        ControllerDeviceAdded { which, .. } => {
+                            added.push(which as u32);
+                        },
+
                         $(
                             $e_sdl => {
                                 self.now.$e_alias = true;
@@ -86,6 +178,10 @@ macro_rules! struct_events {
                         _ => {}
                     }
                 }
+
+                for id in added {
+                    self.open_controller(id);
+                }
             }
         }
     }
@@ -1,9 +1,23 @@
-use phi::data::Rectangle;
+use phi::camera::{Camera, MAX_SHAKE_OFFSET};
+use phi::data::{PixelMask, Rectangle, Shape, Vec2};
+use phi::perf;
 use std::cell::RefCell;
+use std::f64::consts::PI;
 use std::path::Path;
 use std::rc::Rc;
+use sdl2::pixels::Color;
+use sdl2::rect::Point;
 use sdl2::render::{Renderer, Texture};
-use sdl2_image::LoadTexture;
+use sdl2::surface::Surface;
+use sdl2_image::{LoadSurface, LoadTexture};
+
+/// Pixels whose alpha is below this value are considered transparent when
+/// building a `PixelMask`.
+const MASK_ALPHA_THRESHOLD: u8 = 128;
+
+/// The number of segments used to approximate a full circle when drawing
+/// outlines.
+const CIRCLE_SEGMENTS: usize = 24;
 
 
 #[derive(Clone)]
@@ -13,6 +27,13 @@ pub struct Sprite {
     // `render` method requires a mutable `Texture`.
     tex: Rc<RefCell<Texture>>,
     src: Rectangle,
+    // The opaque pixels of the whole texture, if they were requested, and
+    // those of the source region as it is rendered. Both are only built once,
+    // when the sprite is created.
+    tex_mask: Option<Rc<PixelMask>>,
+    mask: Option<Rc<PixelMask>>,
+    flipped: bool,
+    tint: Option<Color>,
 }
 
 impl Sprite {
@@ -26,14 +47,34 @@ impl Sprite {
                 h: tex_query.height as f64,
                 x: 0.0,
                 y: 0.0,
-            }
+            },
+            tex_mask: None,
+            mask: None,
+            flipped: false,
+            tint: None,
         }
     }
 
     /// Creates a new `Sprite` from an image file located at the given path.
-    /// Returns `Some` if the file could be read, and `None` otherwise.
-    pub fn load(renderer: &Renderer, path: &str) -> Sprite {
-        Sprite::new(renderer.load_texture(Path::new(path)).unwrap())
+    /// Returns an error if the file could not be read.
+    pub fn load(renderer: &Renderer, path: &str) -> Result<Sprite, String> {
+        renderer.load_texture(Path::new(path))
+            .map(Sprite::new)
+            .map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /// Same as `load`, but also keeps track of the image's opaque pixels, so
+    /// that `mask` can be used for pixel-perfect collision detection.
+    pub fn load_with_mask(renderer: &Renderer, path: &str) -> Result<Sprite, String> {
+        let surface = Surface::from_file(Path::new(path)).map_err(|e| format!("{}: {}", path, e))?;
+        let texture = renderer.create_texture_from_surface(&surface).map_err(|e| format!("{}: {}", path, e))?;
+        let mask = Rc::new(alpha_mask(&surface));
+
+        Ok(Sprite {
+            tex_mask: Some(mask.clone()),
+            mask: Some(mask),
+            .. Sprite::new(texture)
+        })
     }
 
     /// Returns a new `Sprite` representing a sub-region of the current one.
@@ -43,26 +84,123 @@ impl Sprite {
         if self.src.contains(rect) {
             Some(Sprite {
                 tex: self.tex.clone(),
-                src: rect
+                src: rect,
+                tex_mask: self.tex_mask.clone(),
+                mask: self.tex_mask.as_ref().map(|mask| {
+                    let mask = mask.region(rect);
+                    Rc::new(if self.flipped { mask.flip_horizontal() } else { mask })
+                }),
+                flipped: self.flipped,
+                tint: self.tint,
             })
         } else {
             None
         }
     }
 
+    /// Returns a copy of the `Sprite` which is mirrored horizontally when
+    /// rendered. Useful to make enemies face the player.
+    pub fn flipped(&self) -> Sprite {
+        Sprite {
+            flipped: !self.flipped,
+            mask: self.mask.as_ref().map(|mask| Rc::new(mask.flip_horizontal())),
+            .. self.clone()
+        }
+    }
+
+    /// Returns a copy of the `Sprite` whose colors are multiplied by `color`
+    /// when rendered.
+    pub fn tinted(&self, color: Color) -> Sprite {
+        Sprite {
+            tint: Some(color),
+            .. self.clone()
+        }
+    }
+
     /// Returns the dimensions of the source region (which may be smaller
     /// than those of the `Texture`!)
     pub fn size(&self) -> (f64, f64) {
         (self.src.w, self.src.h)
     }
 
+    /// Returns the opaque pixels of the source region, or `None` if the
+    /// `Sprite` was not loaded with `load_with_mask`.
+    pub fn mask(&self) -> Option<&PixelMask> {
+        self.mask.as_deref()
+    }
+
     /// Renders a `Sprite` to the `dest` region. Only the Sprite's sub-region will
     /// be rendered.
-    pub fn render(&self, mut renderer: &mut Renderer, dest: Rectangle) {
-        renderer.copy(&mut self.tex.borrow_mut(), Some(self.src.to_sdl()), Some(dest.to_sdl())).unwrap();
+    pub fn render(&self, renderer: &mut Renderer, dest: Rectangle) {
+        let mut tex = self.tex.borrow_mut();
+
+        // The color modulation is a property of the texture, which is shared
+        // with other sprites: it is reset once we're done.
+        if let Some(color) = self.tint {
+            let (r, g, b) = color.rgb();
+            tex.set_color_mod(r, g, b);
+        }
+
+        perf::draw_call();
+        if self.flipped {
+            renderer.copy_ex(&tex, Some(self.src.to_sdl()), Some(dest.to_sdl()), 0.0, None, true, false).unwrap();
+        } else {
+            renderer.copy(&tex, Some(self.src.to_sdl()), Some(dest.to_sdl())).unwrap();
+        }
+
+        if self.tint.is_some() {
+            tex.set_color_mod(255, 255, 255);
+        }
+    }
+
+    /// Renders a `Sprite` to the `dest` region of the game world, as seen
+    /// through `camera`. Elements of the HUD should use `render` instead, so
+    /// that they are neither moved nor shaken.
+    pub fn render_world(&self, renderer: &mut Renderer, camera: &Camera, dest: Rectangle) {
+        self.render(renderer, camera.to_screen(dest));
     }
 }
 
+/// Builds a `PixelMask` from the alpha channel of `surface`, as described by
+/// its pixel format. Images without one are opaque everywhere.
+fn alpha_mask(surface: &Surface) -> PixelMask {
+    let (w, h) = (surface.width() as usize, surface.height() as usize);
+    let pitch = surface.pitch() as usize;
+    let format = surface.pixel_format_enum();
+    let bytes_per_pixel = format.byte_size_per_pixel();
+    let amask = format.into_masks().map(|masks| masks.amask).unwrap_or(0);
+
+    if amask == 0 {
+        return PixelMask::new(w, h, vec![true; w * h]);
+    }
+
+    // Formats with an alpha channel are packed in 16 or 32 bits, in the
+    // native byte order.
+    let shift = amask.trailing_zeros();
+    let max = amask >> shift;
+
+    let solid = surface.with_lock(|pixels| {
+        let mut solid = Vec::with_capacity(w * h);
+
+        for y in 0..h {
+            for x in 0..w {
+                let offset = y * pitch + x * bytes_per_pixel;
+                let bytes = &pixels[offset..offset + bytes_per_pixel];
+                let pixel = match bytes_per_pixel {
+                    2 => u16::from_ne_bytes([bytes[0], bytes[1]]) as u32,
+                    _ => u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+                };
+                let alpha = ((pixel & amask) >> shift) * 255 / max;
+                solid.push(alpha >= MASK_ALPHA_THRESHOLD as u32);
+            }
+        }
+
+        solid
+    });
+
+    PixelMask::new(w, h, solid)
+}
+
 #[derive(Clone)]
 pub struct ParallaxSprite {
     pos: f64,
@@ -72,54 +210,147 @@ pub struct ParallaxSprite {
 }
 
 impl ParallaxSprite {
-    pub fn new(texture: Texture, vel: f64) -> ParallaxSprite {
+    pub fn new(sprite: Sprite, vel: f64) -> ParallaxSprite {
         ParallaxSprite {
             pos: 0.0,
             vel: vel,
-            sprite: Sprite::new(texture)
+            sprite: sprite,
         }
     }
 
-    pub fn load(renderer: &Renderer, path: &str, vel: f64) -> ParallaxSprite {
+    pub fn load(renderer: &Renderer, path: &str, vel: f64) -> Result<ParallaxSprite, String> {
+        Sprite::load(renderer, path).map(|sprite| ParallaxSprite::new(sprite, vel))
+    }
+
+    /// The number of pixels of the image scrolled so far, modulo its width.
+    pub fn scroll(&self) -> f64 {
+        self.pos
+    }
+
+    /// Scrolls to a position returned by `scroll`.
+    pub fn set_scroll(&mut self, scroll: f64) {
+        self.pos = scroll;
+    }
+
+    /// Returns the same `ParallaxSprite`, with its colors multiplied by
+    /// `color` when rendered.
+    pub fn tinted(self, color: Color) -> ParallaxSprite {
         ParallaxSprite {
-            pos: 0.0,
-            vel: vel,
-            sprite: Sprite::new(renderer.load_texture(Path::new(path)).unwrap())
+            sprite: self.sprite.tinted(color),
+            .. self
         }
     }
 
     /// Renders the `ParallaxSprite` to `dest` (`None` to use the full window). `elapsed` is the
     /// number of seconds that have passed since the last `render` call.
-    pub fn render(&mut self, mut renderer: &mut Renderer, dest: Option<Rectangle>, elapsed: f64) {
+    pub fn render(&mut self, renderer: &mut Renderer, dest: Option<Rectangle>, elapsed: f64) {
         // We define a logical position as depending solely on the time and the
         // dimensions of the image, not on the destination's size.
         let (w, h) = self.sprite.size();
         self.pos = (self.pos + self.vel * elapsed) % w;
 
-        let (rect_w, rect_h) = if dest.is_some() {
-            (dest.unwrap().w, dest.unwrap().h)
-        } else {
-            let (win_w, win_h) = renderer.output_size().unwrap();
-            (win_w as f64, win_h as f64)
-        };
+        let dest = dest.unwrap_or_else(|| {
+            let (win_w, win_h) = renderer.logical_size();
+            Rectangle { x: 0.0, y: 0.0, w: win_w as f64, h: win_h as f64 }
+        });
 
         // We determine the scale ratio of the rectangle to the sprite. Since we're
         // doing parallax, the ratio is solely determined by the height difference.
-        let scale = rect_h as f64 / h;
+        let scale = dest.h / h;
 
         // We render as many copies of the image as necessary to fill
         // the rectangle.
-        let mut physical_left = -self.pos * scale;
+        let mut physical_left = dest.x - self.pos * scale;
 
-        while physical_left < rect_w as f64 {
-            self.sprite.render(&mut renderer, Rectangle {
+        while physical_left < dest.x + dest.w {
+            self.sprite.render(renderer, Rectangle {
                 x: physical_left,
-                y: 0.0,
+                y: dest.y,
                 w: w * scale,
-                h: rect_h as f64,
+                h: dest.h,
             });
 
             physical_left += w * scale;
         }
     }
+
+    /// Renders the `ParallaxSprite` behind the whole visible world, so that it
+    /// follows the zoom and shake of the `camera`.
+    pub fn render_world(&mut self, renderer: &mut Renderer, camera: &Camera, elapsed: f64) {
+        // Cover a bit more than the visible region, so that no black borders
+        // appear while the screen is shaking.
+        let visible = camera.visible();
+        let margin = MAX_SHAKE_OFFSET / camera.zoom;
+        let dest = Rectangle {
+            x: visible.x - margin,
+            y: visible.y - margin,
+            w: visible.w + 2.0 * margin,
+            h: visible.h + 2.0 * margin,
+        };
+
+        self.render(renderer, Some(camera.to_screen(dest)), elapsed);
+    }
+}
+
+
+/// Draws the outline of `shape`, whose coordinates are in the world, with the
+/// current draw color of the renderer.
+pub fn draw_shape(renderer: &mut Renderer, camera: &Camera, shape: &Shape) {
+    let mut outline = match *shape {
+        Shape::Rect(r) => vec![
+            Vec2::new(r.x, r.y),
+            Vec2::new(r.x + r.w, r.y),
+            Vec2::new(r.x + r.w, r.y + r.h),
+            Vec2::new(r.x, r.y + r.h),
+        ],
+
+        Shape::Circle(c) =>
+            arc(Vec2::new(c.x, c.y), c.r, 0.0, 2.0 * PI),
+
+        Shape::Capsule(c) => {
+            // Half a circle around each end, joined by straight lines
+            let (p1, p2) = (Vec2::new(c.x1, c.y1), Vec2::new(c.x2, c.y2));
+            let angle = (p2 - p1).angle();
+            let mut points = arc(p2, c.r, angle - PI / 2.0, PI);
+            points.extend(arc(p1, c.r, angle + PI / 2.0, PI));
+            points
+        },
+
+        Shape::Polygon(ref p) => p.points.clone(),
+    };
+
+    if let Some(&first) = outline.first() {
+        outline.push(first);
+    }
+
+    let points: Vec<Point> = outline.into_iter()
+        .map(|point| camera.point_to_screen(point))
+        .map(|point| Point::new(point.x as i32, point.y as i32))
+        .collect();
+    perf::draw_call();
+    renderer.draw_lines(&points).unwrap();
+}
+
+/// Fills `rect`, in screen coordinates, with the current draw color of the
+/// renderer.
+pub fn fill_rect(renderer: &mut Renderer, rect: Rectangle) {
+    perf::draw_call();
+    renderer.fill_rect(rect.to_sdl()).unwrap();
+}
+
+/// Draws the outline of `rect`, in screen coordinates, with the current draw
+/// color of the renderer.
+pub fn draw_rect(renderer: &mut Renderer, rect: Rectangle) {
+    perf::draw_call();
+    renderer.draw_rect(rect.to_sdl()).unwrap();
+}
+
+/// Returns points along the arc of the circle of `center` and `radius`
+/// starting at angle `start` and spanning `length` radians.
+fn arc(center: Vec2, radius: f64, start: f64, length: f64) -> Vec<Vec2> {
+    let segments = ((CIRCLE_SEGMENTS as f64 * length / (2.0 * PI)).ceil() as usize).max(1);
+
+    (0..segments + 1)
+        .map(|i| center + Vec2::from_angle(start + length * i as f64 / segments as f64) * radius)
+        .collect()
 }
@@ -0,0 +1,92 @@
+use phi::{Events, Key};
+
+/// The keys a player uses to control their ship. Several players can share
+/// the keyboard, as long as their controls do not overlap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Controls {
+    pub up: Key,
+    pub down: Key,
+    pub left: Key,
+    pub right: Key,
+    pub fire: Key,
+    pub bomb: Key,
+    pub focus: Key,
+}
+
+impl Controls {
+    /// The arrows, with Space to fire, B for bombs and left Shift to focus.
+    pub fn player1() -> Controls {
+        Controls {
+            up: Key::Up,
+            down: Key::Down,
+            left: Key::Left,
+            right: Key::Right,
+            fire: Key::Space,
+            bomb: Key::B,
+            focus: Key::LShift,
+        }
+    }
+
+    /// W, A, S and D, with F to fire, G for bombs and Q to focus.
+    pub fn player2() -> Controls {
+        Controls {
+            up: Key::W,
+            down: Key::S,
+            left: Key::A,
+            right: Key::D,
+            fire: Key::F,
+            bomb: Key::G,
+            focus: Key::Q,
+        }
+    }
+
+    /// Returns the state of the controls during the current frame.
+    pub fn read(&self, events: &Events) -> Input {
+        Input {
+            up: events.is_down(self.up),
+            down: events.is_down(self.down),
+            left: events.is_down(self.left),
+            right: events.is_down(self.right),
+            fire: events.is_down(self.fire),
+            bomb: events.just_pressed(self.bomb),
+            focus: events.is_down(self.focus),
+        }
+    }
+}
+
+/// What a player is doing during a frame, independently of the keys they
+/// use to do it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Input {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub fire: bool,
+    /// Only set on the frame the key was pressed.
+    pub bomb: bool,
+    pub focus: bool,
+}
+
+impl Input {
+    /// Packs the input into a number, one bit per control, so that it can be
+    /// recorded.
+    pub fn to_bits(self) -> u32 {
+        [self.up, self.down, self.left, self.right, self.fire, self.bomb, self.focus].iter()
+            .enumerate()
+            .fold(0, |bits, (i, &on)| if on { bits | 1 << i } else { bits })
+    }
+
+    pub fn from_bits(bits: u32) -> Input {
+        let on = |i: u32| bits & 1 << i != 0;
+        Input {
+            up: on(0),
+            down: on(1),
+            left: on(2),
+            right: on(3),
+            fire: on(4),
+            bomb: on(5),
+            focus: on(6),
+        }
+    }
+}
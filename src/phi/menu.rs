@@ -0,0 +1,583 @@
+use phi::{Events, Phi, ViewAction};
+use phi::data::{Rectangle, Vec2};
+use phi::gfx::{self, Sprite};
+use phi::tween::{Easing, Tween};
+use sdl2::controller::Button;
+use sdl2::pixels::Color;
+
+/// The sizes of the text of the items, and of the title of the pages. The
+/// items are rendered at the size of the selected one, and scaled down while
+/// they are not.
+const ITEM_SIZE: u16 = 32;
+const SELECTED_SIZE: u16 = 38;
+const TITLE_SIZE: u16 = 48;
+
+/// Seconds taken by an item to grow and light up once it is selected, and to
+/// go back once it is not.
+const HOVER_DURATION: f64 = 0.15;
+
+/// Seconds taken by a page to slide in, and the distance it slides from.
+const PAGE_DURATION: f64 = 0.25;
+const PAGE_SLIDE: f64 = 80.0;
+
+/// The height of every row, relative to that of its text.
+const SPACING: f64 = 1.5;
+
+/// The dimensions of the bar drawn under sliders.
+const SLIDER_W: f64 = 200.0;
+const SLIDER_H: f64 = 6.0;
+
+/// What an item does when it is chosen or adjusted.
+enum Widget {
+    Action(Box<dyn Fn(&mut Phi) -> ViewAction>),
+    Submenu(Page),
+    /// Goes back to the previous page.
+    Back,
+    Toggle { value: bool, changed: Box<dyn Fn(bool)> },
+    Slider { value: f64, min: f64, max: f64, step: f64, changed: Box<dyn Fn(f64)> },
+    /// Goes through a list of options, back to the first one after the last.
+    Cycler { options: Vec<String>, selected: usize, changed: Box<dyn Fn(usize)> },
+}
+
+struct Item {
+    label: String,
+    widget: Widget,
+    /// Disabled items are shown greyed out, and cannot be selected.
+    enabled: bool,
+}
+
+impl Item {
+    /// The text of the item, including its current value.
+    fn text(&self) -> String {
+        match self.widget {
+            Widget::Toggle { value, .. } => format!("{}: {}", self.label, if value { "on" } else { "off" }),
+            Widget::Slider { value, min, max, .. } =>
+                format!("{}: {}%", self.label, ((value - min) / (max - min) * 100.0).round()),
+            Widget::Cycler { ref options, selected, .. } => format!("{}: < {} >", self.label, options[selected]),
+            _ => self.label.clone(),
+        }
+    }
+
+    /// Moves the value of the item `steps` times, backward if negative.
+    fn adjust(&mut self, steps: i32) {
+        match self.widget {
+            Widget::Toggle { ref mut value, ref changed } => {
+                *value = !*value;
+                changed(*value);
+            },
+            Widget::Slider { ref mut value, min, max, step, ref changed } => {
+                *value = (*value + step * steps as f64).max(min).min(max);
+                changed(*value);
+            },
+            Widget::Cycler { ref options, ref mut selected, ref changed } => {
+                let count = options.len() as i32;
+                *selected = ((*selected as i32 + steps) % count + count) as usize % options.len();
+                changed(*selected);
+            },
+            _ => {},
+        }
+    }
+}
+
+/// A list of items, built one after the other:
+///
+/// ```ignore
+/// Page::new("OPTIONS")
+///     .toggle("Music", true, |on| ...)
+///     .action("Continue", |phi| ...).enabled(false)
+///     .back("Back")
+/// ```
+pub struct Page {
+    title: Option<String>,
+    items: Vec<Item>,
+    /// The index of the focused item.
+    selected: usize,
+}
+
+impl Page {
+    pub fn new(title: &str) -> Page {
+        Page {
+            title: if title.is_empty() { None } else { Some(title.to_string()) },
+            items: Vec::new(),
+            selected: 0,
+        }
+    }
+
+    pub fn action<F>(self, label: &str, action: F) -> Page
+    where F: Fn(&mut Phi) -> ViewAction + 'static {
+        self.item(label, Widget::Action(Box::new(action)))
+    }
+
+    pub fn submenu(self, label: &str, page: Page) -> Page {
+        self.item(label, Widget::Submenu(page))
+    }
+
+    pub fn back(self, label: &str) -> Page {
+        self.item(label, Widget::Back)
+    }
+
+    /// `changed` is called with the new value whenever the player changes it.
+    pub fn toggle<F>(self, label: &str, value: bool, changed: F) -> Page
+    where F: Fn(bool) + 'static {
+        self.item(label, Widget::Toggle { value: value, changed: Box::new(changed) })
+    }
+
+    pub fn slider<F>(self, label: &str, value: f64, (min, max): (f64, f64), step: f64, changed: F) -> Page
+    where F: Fn(f64) + 'static {
+        self.item(label, Widget::Slider { value: value, min: min, max: max, step: step, changed: Box::new(changed) })
+    }
+
+    /// `options` must not be empty.
+    pub fn cycler<F>(self, label: &str, options: Vec<String>, selected: usize, changed: F) -> Page
+    where F: Fn(usize) + 'static {
+        assert!(!options.is_empty());
+        let selected = selected.min(options.len() - 1);
+        self.item(label, Widget::Cycler { options: options, selected: selected, changed: Box::new(changed) })
+    }
+
+    /// Enables or disables the last item added.
+    pub fn enabled(mut self, enabled: bool) -> Page {
+        if let Some(item) = self.items.last_mut() {
+            item.enabled = enabled;
+        }
+        self.first_enabled()
+    }
+
+    fn item(mut self, label: &str, widget: Widget) -> Page {
+        self.items.push(Item { label: label.to_string(), widget: widget, enabled: true });
+        self.first_enabled()
+    }
+
+    /// Focuses the first enabled item, if the focused one is disabled.
+    fn first_enabled(mut self) -> Page {
+        if !self.items.is_empty() && !self.items[self.selected].enabled {
+            self.selected = self.items.iter().position(|item| item.enabled).unwrap_or(0);
+        }
+        self
+    }
+
+    /// Focuses the next enabled item in the given direction, wrapping around.
+    fn step(&mut self, forward: bool) {
+        let count = self.items.len();
+        for i in 1..count {
+            let index = if forward { (self.selected + i) % count } else { (self.selected + count - i) % count };
+            if self.items[index].enabled {
+                self.selected = index;
+                return;
+            }
+        }
+    }
+}
+
+/// The ways the player navigates a menu, whatever the device.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Nav {
+    Up,
+    Down,
+    Left,
+    Right,
+    Accept,
+    Back,
+    /// The mouse points at, or clicks, a position of the screen.
+    Point(Vec2),
+    Click(Vec2),
+}
+
+impl Nav {
+    /// Reads what the player did with the keyboard, the mouse and the game
+    /// controllers since the last frame.
+    pub fn read(events: &Events) -> Vec<Nav> {
+        let mut navs = Vec::new();
+        let mouse = Vec2::new(events.mouse.0, events.mouse.1);
+        let now = &events.now;
+
+        if now.mouse_moved { navs.push(Nav::Point(mouse)); }
+        if now.click { navs.push(Nav::Click(mouse)); }
+        if now.key_up == Some(true) { navs.push(Nav::Up); }
+        if now.key_down == Some(true) { navs.push(Nav::Down); }
+        if now.key_left == Some(true) { navs.push(Nav::Left); }
+        if now.key_right == Some(true) { navs.push(Nav::Right); }
+        if now.key_space == Some(true) || now.key_return == Some(true) { navs.push(Nav::Accept); }
+        if now.key_escape == Some(true) || now.key_backspace == Some(true) { navs.push(Nav::Back); }
+
+        for &button in &now.buttons {
+            match button {
+                Button::DPadUp => navs.push(Nav::Up),
+                Button::DPadDown => navs.push(Nav::Down),
+                Button::DPadLeft => navs.push(Nav::Left),
+                Button::DPadRight => navs.push(Nav::Right),
+                Button::A | Button::Start => navs.push(Nav::Accept),
+                Button::B | Button::Back => navs.push(Nav::Back),
+                _ => {},
+            }
+        }
+
+        navs
+    }
+}
+
+/// What the owner of a menu should do after it was navigated.
+pub enum Response<'a> {
+    None,
+    /// An action was chosen, and should be called.
+    Action(&'a dyn Fn(&mut Phi) -> ViewAction),
+    /// The player went back from the first page.
+    Exit,
+}
+
+/// Nested pages of items, navigated with the keyboard, the mouse or a game
+/// controller, and laid out automatically in the middle of the screen. The
+/// menu is rendered on top of what is already on the screen, so that views
+/// can put it in front of their own background.
+pub struct Menu {
+    root: Page,
+    /// The indices of the submenus leading to the current page.
+    path: Vec<usize>,
+    font: &'static str,
+    /// Where the items of the current page were last rendered, for the mouse.
+    rects: Vec<Rectangle>,
+    /// The sprites of the title and of every item of the current page, kept
+    /// until their text changes.
+    sprites: Vec<Option<(String, Sprite)>>,
+    /// How far each item of the current page is into its hover animation,
+    /// from 0 (idle) to 1 (selected).
+    highlights: Vec<Tween<f64>>,
+    /// The horizontal offset of the current page, while it slides in.
+    entrance: Tween<f64>,
+}
+
+impl Menu {
+    pub fn new(root: Page, font: &'static str) -> Menu {
+        Menu {
+            root: root,
+            path: Vec::new(),
+            font: font,
+            rects: Vec::new(),
+            sprites: Vec::new(),
+            highlights: Vec::new(),
+            entrance: Tween::idle(0.0, PAGE_DURATION, Easing::CubicOut),
+        }
+    }
+
+    fn page(&self) -> &Page {
+        let mut page = &self.root;
+        for &i in &self.path {
+            page = match page.items[i].widget {
+                Widget::Submenu(ref submenu) => submenu,
+                _ => unreachable!(),
+            };
+        }
+        page
+    }
+
+    fn page_mut(&mut self) -> &mut Page {
+        let mut page = &mut self.root;
+        for &i in &self.path {
+            page = match page.items[i].widget {
+                Widget::Submenu(ref mut submenu) => submenu,
+                _ => unreachable!(),
+            };
+        }
+        page
+    }
+
+    /// Changes the current page, whose items are laid out again. Submenus
+    /// slide in from the right, and the pages they are left for from the
+    /// left.
+    fn go_to(&mut self, path: Vec<usize>) {
+        let from = if path.len() > self.path.len() { PAGE_SLIDE } else { -PAGE_SLIDE };
+        self.entrance = Tween::new(from, 0.0, PAGE_DURATION, Easing::CubicOut);
+        self.path = path;
+        self.rects.clear();
+        self.sprites.clear();
+        self.highlights.clear();
+    }
+
+    /// Returns the index of the enabled item at `point`, as last rendered.
+    fn item_at(&self, point: Vec2) -> Option<usize> {
+        let page = self.page();
+        self.rects.iter().position(|rect| rect.contains_point(point))
+            .and_then(|i| if page.items[i].enabled { Some(i) } else { None })
+    }
+
+    /// Applies a single navigation to the current page.
+    pub fn navigate(&mut self, nav: Nav) -> Response<'_> {
+        let pointed = match nav {
+            Nav::Point(point) | Nav::Click(point) => self.item_at(point),
+            _ => None,
+        };
+
+        match nav {
+            Nav::Up => self.page_mut().step(false),
+            Nav::Down => self.page_mut().step(true),
+            Nav::Left => self.adjust(-1),
+            Nav::Right => self.adjust(1),
+            Nav::Back => return self.back(),
+            Nav::Accept => return self.accept(),
+
+            Nav::Point(_) => if let Some(i) = pointed {
+                self.page_mut().selected = i;
+            },
+
+            // Clicking a slider sets it to the value under the mouse.
+            Nav::Click(point) => if let Some(i) = pointed {
+                self.page_mut().selected = i;
+                let rect = self.rects[i];
+                let item = &mut self.page_mut().items[i];
+                if let Widget::Slider { min, max, step, ref mut value, ref changed } = item.widget {
+                    let fraction = ((point.x - rect.x) / rect.w).clamp(0.0, 1.0);
+                    *value = (min + ((max - min) * fraction / step).round() * step).min(max);
+                    changed(*value);
+                    return Response::None;
+                }
+                return self.accept();
+            },
+        }
+
+        Response::None
+    }
+
+    fn adjust(&mut self, steps: i32) {
+        let page = self.page_mut();
+        if let Some(item) = page.items.get_mut(page.selected) {
+            if item.enabled {
+                item.adjust(steps);
+            }
+        }
+    }
+
+    fn back(&mut self) -> Response<'_> {
+        if self.path.is_empty() {
+            return Response::Exit;
+        }
+
+        let mut path = self.path.clone();
+        path.pop();
+        self.go_to(path);
+        Response::None
+    }
+
+    fn accept(&mut self) -> Response<'_> {
+        let selected = self.page().selected;
+        let (enabled, submenu, back) = match self.page().items.get(selected) {
+            Some(item) => match item.widget {
+                Widget::Submenu(_) => (item.enabled, true, false),
+                Widget::Back => (item.enabled, false, true),
+                _ => (item.enabled, false, false),
+            },
+            None => return Response::None,
+        };
+
+        if !enabled {
+            return Response::None;
+        }
+
+        if submenu {
+            let mut path = self.path.clone();
+            path.push(selected);
+            self.go_to(path);
+            return Response::None;
+        }
+
+        if back {
+            return self.back();
+        }
+
+        // Choosing a value goes to the next one.
+        self.adjust(1);
+
+        match self.page().items[selected].widget {
+            Widget::Action(ref action) => Response::Action(&**action),
+            _ => Response::None,
+        }
+    }
+
+    /// Handles the navigation of the current frame and renders the menu.
+    /// Returns the result of the chosen action, if any, or that of `exit` if
+    /// the player went back from the first page.
+    pub fn update<F>(&mut self, phi: &mut Phi, elapsed: f64, exit: F) -> ViewAction
+    where F: Fn(&mut Phi) -> ViewAction {
+        for nav in Nav::read(&phi.events) {
+            match self.navigate(nav) {
+                Response::None => {},
+                Response::Action(action) => return action(phi),
+                Response::Exit => return exit(phi),
+            }
+        }
+
+        self.animate(elapsed);
+        self.render(phi);
+        ViewAction::None
+    }
+
+    /// Moves the hover animation of every item toward its state.
+    fn animate(&mut self, elapsed: f64) {
+        let targets: Vec<f64> = {
+            let page = self.page();
+            page.items.iter().enumerate()
+                .map(|(i, item)| if item.enabled && i == page.selected { 1.0 } else { 0.0 })
+                .collect()
+        };
+
+        self.highlights.resize_with(targets.len(), || Tween::idle(0.0, HOVER_DURATION, Easing::QuadOut));
+        for (highlight, target) in self.highlights.iter_mut().zip(targets) {
+            if highlight.end() != target {
+                highlight.retarget(target);
+            }
+            highlight.update(elapsed);
+        }
+
+        self.entrance.update(elapsed);
+    }
+
+    fn render(&mut self, phi: &mut Phi) {
+        let (title, texts, enabled) = {
+            let page = self.page();
+            (page.title.clone(),
+             page.items.iter().map(Item::text).collect::<Vec<_>>(),
+             page.items.iter().map(|item| item.enabled).collect::<Vec<_>>())
+        };
+
+        // The title comes first, if any.
+        let has_title = title.is_some();
+        let rows: Vec<String> = title.into_iter().chain(texts).collect();
+
+        self.sprites.resize(rows.len(), None);
+        for (i, (cached, text)) in self.sprites.iter_mut().zip(rows.iter()).enumerate() {
+            if cached.as_ref().is_none_or(|(t, _)| t != text) {
+                let size = if has_title && i == 0 { TITLE_SIZE } else { SELECTED_SIZE };
+                *cached = phi.ttf_str_sprite(text, self.font, size, Color::RGB(255, 255, 255))
+                    .map(|sprite| (text.clone(), sprite));
+            }
+        }
+
+        // The rows are laid out with the idle size of the items, so that
+        // they do not move while the selected one grows.
+        let idle = ITEM_SIZE as f64 / SELECTED_SIZE as f64;
+        let offset = if has_title { 1 } else { 0 };
+        let sizes: Vec<(f64, f64)> = self.sprites.iter().enumerate().map(|(i, cached)| {
+            let (w, h) = cached.as_ref().map_or((0.0, 0.0), |(_, sprite)| sprite.size());
+            if i < offset { (w, h) } else { (w * idle, h * idle) }
+        }).collect();
+        let offset_x = self.entrance.value();
+        let rects: Vec<Rectangle> = layout(&sizes, phi.output_size()).into_iter()
+            .map(|rect| Rectangle { x: rect.x + offset_x, .. rect })
+            .collect();
+
+        if has_title {
+            if let Some((_, ref sprite)) = self.sprites[0] {
+                sprite.render(&mut phi.renderer, rects[0]);
+            }
+        }
+
+        for (i, &rect) in rects[offset..].iter().enumerate() {
+            if let Some((_, ref sprite)) = self.sprites[offset + i] {
+                let eased = self.highlights.get(i).map_or(0.0, Tween::value);
+                let shade = if enabled[i] { (220.0 + 35.0 * eased) as u8 } else { 100 };
+                let scale = (idle + (1.0 - idle) * eased) / idle;
+                sprite.tinted(Color::RGB(shade, shade, shade)).render(&mut phi.renderer, rect.scale(scale));
+            }
+        }
+
+        self.rects = rects[offset..].to_vec();
+
+        // Sliders show their value as a bar under their text.
+        for (item, rect) in self.page().items.iter().zip(self.rects.iter()) {
+            if let Widget::Slider { value, min, max, .. } = item.widget {
+                let bar = Rectangle { x: rect.center().x - SLIDER_W / 2.0, y: rect.y + rect.h, w: SLIDER_W, h: SLIDER_H };
+                let filled = Rectangle { w: bar.w * (value - min) / (max - min), .. bar };
+                phi.renderer.set_draw_color(Color::RGB(80, 80, 80));
+                gfx::fill_rect(&mut phi.renderer, bar);
+                phi.renderer.set_draw_color(Color::RGB(220, 220, 220));
+                gfx::fill_rect(&mut phi.renderer, filled);
+            }
+        }
+    }
+}
+
+/// Stacks rows of the given `sizes`, centered on the `screen`.
+pub fn layout(sizes: &[(f64, f64)], screen: (f64, f64)) -> Vec<Rectangle> {
+    let total: f64 = sizes.iter().map(|&(_, h)| h * SPACING).sum();
+    let mut y = (screen.1 - total) / 2.0;
+
+    sizes.iter().map(|&(w, h)| {
+        // Every text is centered in its row.
+        let rect = Rectangle { x: (screen.0 - w) / 2.0, y: y + h * (SPACING - 1.0) / 2.0, w: w, h: h };
+        y += h * SPACING;
+        rect
+    }).collect()
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn menu_navigation() {
+        use phi::data::{Rectangle, Vec2};
+        use phi::menu::{layout, Menu, Nav, Page, Response};
+        use phi::ViewAction;
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let volume = Rc::new(Cell::new(0.5));
+        let level = Rc::new(Cell::new(0));
+        let (v, l) = (volume.clone(), level.clone());
+
+        let options = Page::new("OPTIONS")
+            .slider("Volume", 0.5, (0.0, 1.0), 0.1, move |value| v.set(value))
+            .cycler("Level", vec!["a".to_string(), "b".to_string()], 0, move |i| l.set(i))
+            .back("Back");
+
+        let mut menu = Menu::new(Page::new("")
+            .action("Continue", |_| ViewAction::Quit).enabled(false)
+            .submenu("Options", options)
+            .action("Quit", |_| ViewAction::Quit), "font.ttf");
+
+        // Empty pages have nothing to focus.
+        Page::new("EMPTY").enabled(false);
+
+        // Disabled items are skipped, and the selection wraps around.
+        assert_eq!(menu.page().selected, 1);
+        menu.navigate(Nav::Down);
+        assert_eq!(menu.page().selected, 2);
+        menu.navigate(Nav::Down);
+        assert_eq!(menu.page().selected, 1);
+        menu.navigate(Nav::Up);
+        menu.navigate(Nav::Up);
+        assert_eq!(menu.page().selected, 1);
+
+        // Submenus are entered, adjusted and left.
+        menu.navigate(Nav::Accept);
+        assert_eq!(menu.page().title, Some("OPTIONS".to_string()));
+        menu.navigate(Nav::Right);
+        menu.navigate(Nav::Right);
+        assert!((volume.get() - 0.7).abs() < 1e-9);
+        for _ in 0..10 {
+            menu.navigate(Nav::Left);
+        }
+        assert_eq!(volume.get(), 0.0);
+
+        menu.navigate(Nav::Down);
+        menu.navigate(Nav::Left);
+        assert_eq!(level.get(), 1);
+        menu.navigate(Nav::Accept);
+        assert_eq!(level.get(), 0);
+
+        menu.navigate(Nav::Down);
+        assert!(matches!(menu.navigate(Nav::Accept), Response::None));
+        assert_eq!(menu.page().title, None);
+        assert!(matches!(menu.navigate(Nav::Back), Response::Exit));
+
+        // Actions are returned to be called, and the mouse selects what it
+        // points at, unless it is disabled.
+        menu.navigate(Nav::Down);
+        assert!(matches!(menu.navigate(Nav::Accept), Response::Action(_)));
+
+        menu.rects = layout(&[(100.0, 20.0); 3], (800.0, 600.0));
+        assert_eq!(menu.rects[1], Rectangle { x: 350.0, y: 290.0, w: 100.0, h: 20.0 });
+        menu.navigate(Nav::Point(Vec2::new(400.0, 300.0)));
+        assert_eq!(menu.page().selected, 1);
+        menu.navigate(Nav::Point(Vec2::new(400.0, 270.0)));
+        assert_eq!(menu.page().selected, 1);
+        assert!(matches!(menu.navigate(Nav::Click(Vec2::new(400.0, 330.0))), Response::Action(_)));
+    }
+}
@@ -1,9 +1,28 @@
 #[macro_use]
 mod events;
+pub mod camera;
+pub mod console;
 pub mod data;
+pub mod debug;
 pub mod gfx;
+pub mod input;
+pub mod menu;
+pub mod pacing;
+pub mod perf;
+pub mod rng;
+pub mod scheduler;
+pub mod snapshot;
+pub mod timescale;
+pub mod toml;
+pub mod tween;
 
+use self::camera::Camera;
+use self::console::Console;
+use self::debug::DebugDraw;
 use self::gfx::Sprite;
+use self::pacing::{Limiter, Pacing};
+use self::perf::Perf;
+use self::timescale::TimeScale;
 use sdl2::render::Renderer;
 use sdl2::pixels::Color;
 use std::collections::HashMap;
@@ -12,26 +31,63 @@ use std::path::Path;
 struct_events! {
     keyboard: {
         key_escape: Escape,
+        key_return: Return,
+        key_backspace: Backspace,
+        key_tab: Tab,
+        key_backquote: Backquote,
         key_up: Up,
         key_down: Down,
         key_left: Left,
         key_right: Right,
-        key_space: Space
+        key_space: Space,
+        key_b: B,
+        key_lshift: LShift,
+        key_w: W,
+        key_a: A,
+        key_s: S,
+        key_d: D,
+        key_f: F,
+        key_g: G,
+        key_q: Q,
+        key_f2: F2,
+        key_f3: F3,
+        key_f4: F4,
+        key_f5: F5,
+        key_f6: F6,
+        key_f7: F7,
+        key_f8: F8,
+        key_f9: F9
     },
     other: {
         quit: Quit { .. }
     }
 }
 
+/// The size of the screen the views draw on, in pixels. It is scaled to fill
+/// the window, whatever its size, with black bars if their shapes differ.
+pub const SCREEN_SIZE: (u32, u32) = (800, 600);
+
 pub enum ViewAction {
     None,
-    ChangeView { new_view: Box<View> },
+    ChangeView { new_view: Box<dyn View> },
     Quit,
 }
 
 pub struct Phi<'window> {
     pub events: Events,
     pub renderer: Renderer<'window>,
+    /// Transforms the coordinates of the game world to the screen. Views are
+    /// responsible for updating it every frame if they use it.
+    pub camera: Camera,
+    /// Times the frames, and shows how long they take on top of the views.
+    pub perf: Perf,
+    /// The debug console, opened with the backquote key.
+    pub console: Console,
+    /// Outlines and labels drawn on top of the views, shown with F2.
+    pub debug: DebugDraw,
+    /// Scales the time handed to the views, which can also be paused with F7
+    /// and advanced one frame at a time with F8.
+    pub time: TimeScale,
     ttf_context: ::sdl2_ttf::Sdl2TtfContext,
 
     cached_fonts: HashMap<(&'static str, u16), ::sdl2_ttf::Font<'window>>,
@@ -39,16 +95,25 @@ pub struct Phi<'window> {
 
 impl<'window> Phi<'window> {
     fn new(events: Events, renderer: Renderer, ttf_context: ::sdl2_ttf::Sdl2TtfContext) -> Phi {
+        let (w, h) = renderer.logical_size();
+
         Phi {
             events: events,
             renderer: renderer,
+            camera: Camera::new((w as f64, h as f64)),
+            perf: Perf::new(),
+            console: Console::new(),
+            debug: DebugDraw::new(),
+            time: TimeScale::new(),
             ttf_context: ttf_context,
             cached_fonts: HashMap::new(),
         }
     }
 
+    /// The size of the screen, which is `SCREEN_SIZE` whatever that of the
+    /// window.
     pub fn output_size(&self) -> (f64, f64) {
-        let (w, h) = self.renderer.output_size().unwrap();
+        let (w, h) = self.renderer.logical_size();
         (w as f64, h as f64)
     }
 
@@ -74,64 +139,114 @@ pub trait View {
     fn render(&mut self, context: &mut Phi, elapsed: f64) -> ViewAction;
 }
 
-pub fn spawn<F>(title: &str, init: F)
-where F: Fn(&mut Phi) -> Box<View> {
+/// Runs the game in a new window of `size` pixels, starting with the view
+/// built by `init`. Frames are paced according to `pacing`.
+pub fn spawn<F>(title: &str, size: (u32, u32), pacing: Pacing, init: F)
+where F: Fn(&mut Phi) -> Box<dyn View> {
     // Initialize SDL2
     let sdl_context = ::sdl2::init().unwrap();
     let video = sdl_context.video().unwrap();
-    let mut timer = sdl_context.timer().unwrap();
+    let timer = sdl_context.timer().unwrap();
     let _image_context = ::sdl2_image::init(::sdl2_image::INIT_PNG).unwrap();
 
     // Create the window
-    let window = video.window(title, 800, 600)
+    let window = video.window(title, size.0, size.1)
         .position_centered().opengl()
         .build().unwrap();
 
+    // The game can be played with the keyboard alone.
+    let controllers = match sdl_context.game_controller() {
+        Ok(subsystem) => Some(subsystem),
+        Err(e) => {
+            println!("Could not use game controllers: {}", e);
+            None
+        },
+    };
+
     // Create the context
+    let mut builder = window.renderer().accelerated();
+    if pacing == Pacing::Vsync {
+        builder = builder.present_vsync();
+    }
+    let mut renderer = builder.build().unwrap();
+    renderer.set_logical_size(SCREEN_SIZE.0, SCREEN_SIZE.1).unwrap();
     let mut context = Phi::new(
-        Events::new(sdl_context.event_pump().unwrap()),
-        window.renderer()
-            .accelerated()
-            .build().unwrap(),
+        Events::new(sdl_context.event_pump().unwrap(), controllers),
+        renderer,
         ::sdl2_ttf::init().unwrap());
 
     // Create the initial view
     let mut current_view = init(&mut context);
 
-    // Frame timing
-    let interval = 1_000 / 60;
-    let mut before = timer.ticks();
-    let mut last_second = timer.ticks();
-    let mut fps = 0;
+    // Frame timing, with the high-resolution performance counter
+    let frequency = timer.performance_frequency() as f64;
+    let clock = || timer.performance_counter() as f64 / frequency;
+    let mut limiter = Limiter::new(pacing);
+    let mut before = clock();
 
     loop {
-        let now = timer.ticks();
-        let dt = now - before;
-        let elapsed = dt as f64 / 1_000.0;
-
-        // Lock to the intended framerate
-        if dt < interval {
-            timer.delay(interval - dt);
-            continue;
+        // Wait for the next frame, if the frame rate is limited
+        let wait = limiter.wait(clock());
+        if wait > 0.0 {
+            pacing::sleep_until(clock, clock() + wait);
         }
 
+        let now = clock();
+        let elapsed = now - before;
         before = now;
-        fps += 1;
-
-        if now - last_second > 1_000 {
-            println!("FPS: {}", fps);
-            last_second = now;
-            fps = 0;
-        }
 
 
         // Logic & rendering
 
         context.events.pump();
 
-        match current_view.render(&mut context, elapsed) {
-            ViewAction::None =>
-                context.renderer.present(),
+        // While the console is open, what is typed goes to it.
+        if cfg!(debug_assertions) && context.events.now.key_backquote == Some(true) {
+            context.console.toggle();
+        }
+        context.console.capture(&mut context.events.now);
+
+        // The performance overlay is available in every view.
+        if context.events.now.key_f3 == Some(true) {
+            context.perf.visible = !context.perf.visible;
+        }
+        if context.events.now.key_f4 == Some(true) {
+            match context.perf.toggle_logging() {
+                Ok(()) if context.perf.is_logging() => println!("Logging frame timings to {}", perf::LOG_PATH),
+                Ok(()) => println!("Stopped logging frame timings"),
+                Err(e) => println!("Could not log frame timings: {}", e),
+            }
+        }
+
+        if cfg!(debug_assertions) {
+            if context.events.now.key_f2 == Some(true) {
+                context.debug.enabled = !context.debug.enabled;
+            }
+            if context.events.now.key_f7 == Some(true) {
+                context.time.toggle_pause();
+                let status = if context.time.is_paused() { "Paused, F8 advances one frame" } else { "Resumed" };
+                context.console.print(status);
+            }
+            if context.events.now.key_f8 == Some(true) {
+                context.time.step();
+            }
+        }
+
+        // The views only see scaled time; the rest of the frame is measured
+        // in real time.
+        let scaled = context.time.update(elapsed);
+
+        context.perf.begin();
+        context.debug.clear();
+        match current_view.render(&mut context, scaled) {
+            ViewAction::None => {
+                context.perf.rendered();
+                debug::render(&mut context);
+                console::render(&mut context, elapsed);
+                perf::render_overlay(&mut context);
+                context.renderer.present();
+                context.perf.end(elapsed);
+            },
 
             ViewAction::ChangeView { new_view } =>
                 current_view = new_view,
@@ -0,0 +1,120 @@
+use std::thread;
+use std::time::Duration;
+
+/// Sleeping is only accurate to a millisecond or two, so the last moments
+/// before a frame are spent checking the clock instead.
+const SPIN: f64 = 0.002;
+
+/// How the main loop paces the frames.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pacing {
+    /// Waits for the display to refresh before showing every frame.
+    Vsync,
+    /// Sleeps between the frames, to render that many of them every second.
+    Limited(u32),
+    /// Renders frames as fast as possible.
+    Uncapped,
+}
+
+impl Pacing {
+    /// The modes offered in the options.
+    pub fn all() -> Vec<Pacing> {
+        vec![Pacing::Vsync, Pacing::Limited(30), Pacing::Limited(60), Pacing::Limited(120),
+             Pacing::Limited(144), Pacing::Uncapped]
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            Pacing::Vsync => "VSync".to_string(),
+            Pacing::Limited(fps) => format!("{} FPS", fps),
+            Pacing::Uncapped => "Uncapped".to_string(),
+        }
+    }
+
+    /// The seconds between two frames, for limited modes.
+    pub fn period(self) -> Option<f64> {
+        match self {
+            Pacing::Limited(fps) if fps > 0 => Some(1.0 / fps as f64),
+            _ => None,
+        }
+    }
+}
+
+/// Decides when frames start, so that they are evenly spaced.
+pub struct Limiter {
+    period: Option<f64>,
+    /// When the next frame should start.
+    next: Option<f64>,
+}
+
+impl Limiter {
+    pub fn new(pacing: Pacing) -> Limiter {
+        Limiter {
+            period: pacing.period(),
+            next: None,
+        }
+    }
+
+    /// Returns the seconds to wait, from `now`, before starting the next
+    /// frame. Frames are spaced from when they were due rather than from
+    /// when they started, so that the frame rate does not drift; a loop
+    /// which fell more than a frame behind starts again from `now` instead
+    /// of rushing frames to catch up.
+    pub fn wait(&mut self, now: f64) -> f64 {
+        let period = match self.period {
+            Some(period) => period,
+            None => return 0.0,
+        };
+
+        let mut due = self.next.unwrap_or(now);
+        if now - due > period {
+            due = now;
+        }
+
+        self.next = Some(due + period);
+        (due - now).max(0.0)
+    }
+}
+
+/// Waits until `clock` reaches `deadline`, both being expressed in seconds.
+/// `clock` should be precise, such as SDL's performance counter.
+pub fn sleep_until<C: Fn() -> f64>(clock: C, deadline: f64) {
+    let left = deadline - clock() - SPIN;
+    if left > 0.0 {
+        thread::sleep(Duration::new(left as u64, (left.fract() * 1e9) as u32));
+    }
+
+    while clock() < deadline {
+        thread::yield_now();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn frame_pacing() {
+        use phi::pacing::{Limiter, Pacing};
+
+        for pacing in Pacing::all() {
+            assert!(!pacing.label().is_empty());
+        }
+        assert_eq!(Pacing::Limited(50).period(), Some(0.02));
+        assert_eq!(Pacing::Vsync.period(), None);
+        assert_eq!(Pacing::Limited(0).period(), None);
+
+        let mut uncapped = Limiter::new(Pacing::Uncapped);
+        assert_eq!(uncapped.wait(1.0), 0.0);
+
+        let mut limiter = Limiter::new(Pacing::Limited(100));
+        assert_eq!(limiter.wait(0.0), 0.0);
+        assert!((limiter.wait(0.004) - 0.006).abs() < 1e-9);
+
+        // Slightly late frames are followed by slightly shorter waits...
+        assert_eq!(limiter.wait(0.021), 0.0);
+        assert!((limiter.wait(0.025) - 0.005).abs() < 1e-9);
+
+        // ...but the limiter gives up on frames which are too late.
+        assert_eq!(limiter.wait(0.1), 0.0);
+        assert!((limiter.wait(0.1) - 0.01).abs() < 1e-9);
+    }
+}
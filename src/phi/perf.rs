@@ -0,0 +1,305 @@
+use phi::Phi;
+use phi::data::Rectangle;
+use phi::gfx::{self, Sprite};
+use sdl2::pixels::Color;
+use sdl2::render::BlendMode;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+/// Where the timings of every frame are written while logging, relative to
+/// the working directory.
+pub const LOG_PATH: &str = "perf.csv";
+
+const FONT: &str = "assets/belligerent.ttf";
+
+/// The number of frames shown by the graph.
+const HISTORY: usize = 120;
+
+/// Seconds between two refreshes of the text of the overlay, which is slow
+/// to render.
+const TEXT_INTERVAL: f64 = 0.25;
+
+/// The duration of a frame at 60 FPS, marked on the graph.
+const TARGET_FRAME: f64 = 1.0 / 60.0;
+
+/// The dimensions of the graph, and the height of a bar lasting
+/// `TARGET_FRAME`.
+const GRAPH_W: f64 = 2.0 * HISTORY as f64;
+const GRAPH_H: f64 = 80.0;
+const TARGET_H: f64 = 40.0;
+
+/// The draw calls made since the start of the frame. It is global so that
+/// `gfx` can count them without being handed the context.
+static DRAW_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts a call to the renderer. Everything drawn through `gfx` is counted.
+pub fn draw_call() {
+    DRAW_CALLS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// The timings of a frame, in seconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Frame {
+    /// The time since the previous frame.
+    pub elapsed: f64,
+    /// The time spent by the view on its logic, then on drawing the scene.
+    pub update: f64,
+    pub render: f64,
+    /// The time spent showing the frame, which includes waiting for vsync.
+    pub present: f64,
+    pub draw_calls: usize,
+    /// The number of entities reported by the view.
+    pub entities: usize,
+}
+
+impl Frame {
+    /// The line of the frame in the CSV log, whose durations are in
+    /// milliseconds.
+    pub fn csv(&self, index: u64) -> String {
+        format!("{},{:.3},{:.3},{:.3},{:.3},{},{}", index,
+                self.elapsed * 1000.0, self.update * 1000.0, self.render * 1000.0, self.present * 1000.0,
+                self.draw_calls, self.entities)
+    }
+}
+
+/// Measures the performance of the game, and shows it on top of the views.
+///
+/// The main loop times every frame. Views only have to call `split` once
+/// their logic is done and they start drawing, and report how many entities
+/// they handle with `count`; otherwise, all of their time counts as
+/// rendering.
+pub struct Perf {
+    pub visible: bool,
+    history: VecDeque<Frame>,
+    /// The entities counted by the view during the current frame.
+    counts: Vec<(&'static str, usize)>,
+    start: Option<Instant>,
+    split: Option<Instant>,
+    rendered: Option<Instant>,
+    /// The number of draw calls once the view was rendered, which excludes
+    /// those of the overlay.
+    draw_calls: usize,
+    log: Option<BufWriter<File>>,
+    frames: u64,
+    /// The text of the overlay, and the seconds since it was refreshed.
+    text: Vec<Sprite>,
+    text_age: f64,
+}
+
+impl Perf {
+    pub fn new() -> Perf {
+        Perf {
+            visible: false,
+            history: VecDeque::with_capacity(HISTORY),
+            counts: Vec::new(),
+            start: None,
+            split: None,
+            rendered: None,
+            draw_calls: 0,
+            log: None,
+            frames: 0,
+            text: Vec::new(),
+            text_age: TEXT_INTERVAL,
+        }
+    }
+
+    /// Marks the end of the logic of the view, and the start of its drawing.
+    pub fn split(&mut self) {
+        self.split = Some(Instant::now());
+    }
+
+    /// Reports the number of entities of a kind (enemies, bullets, ...)
+    /// during the current frame.
+    pub fn count(&mut self, name: &'static str, n: usize) {
+        self.counts.push((name, n));
+    }
+
+    /// Called by the main loop before the view is rendered.
+    pub fn begin(&mut self) {
+        self.start = Some(Instant::now());
+        self.split = None;
+        self.rendered = None;
+        self.counts.clear();
+        DRAW_CALLS.store(0, Ordering::Relaxed);
+    }
+
+    /// Called by the main loop once the view was rendered.
+    pub fn rendered(&mut self) {
+        self.rendered = Some(Instant::now());
+        self.draw_calls = DRAW_CALLS.load(Ordering::Relaxed);
+    }
+
+    /// Called by the main loop once the frame is presented, `elapsed` seconds
+    /// after the previous one.
+    pub fn end(&mut self, elapsed: f64) {
+        let (start, rendered) = match (self.start, self.rendered) {
+            (Some(start), Some(rendered)) => (start, rendered),
+            _ => return,
+        };
+        let split = self.split.unwrap_or(start);
+        let seconds = |from: Instant, to: Instant| {
+            let d = to.duration_since(from);
+            d.as_secs() as f64 + d.subsec_nanos() as f64 / 1e9
+        };
+
+        self.record(Frame {
+            elapsed: elapsed,
+            update: seconds(start, split),
+            render: seconds(split, rendered),
+            present: seconds(rendered, Instant::now()),
+            draw_calls: self.draw_calls,
+            entities: self.counts.iter().map(|&(_, n)| n).sum(),
+        });
+    }
+
+    fn record(&mut self, frame: Frame) {
+        if self.history.len() == HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(frame);
+        self.text_age += frame.elapsed;
+
+        let failed = match self.log {
+            Some(ref mut log) => writeln!(log, "{}", frame.csv(self.frames)).is_err(),
+            None => false,
+        };
+        if failed {
+            println!("Could not write to {}, stopped logging", LOG_PATH);
+            self.log = None;
+        }
+        self.frames += 1;
+    }
+
+    /// The average of `f` over the frames of the graph.
+    fn average<F: Fn(&Frame) -> f64>(&self, f: F) -> f64 {
+        if self.history.is_empty() {
+            return 0.0;
+        }
+        self.history.iter().map(f).sum::<f64>() / self.history.len() as f64
+    }
+
+    /// The average number of frames per second, over the frames of the graph.
+    pub fn fps(&self) -> f64 {
+        let elapsed = self.average(|frame| frame.elapsed);
+        if elapsed > 0.0 { 1.0 / elapsed } else { 0.0 }
+    }
+
+    pub fn is_logging(&self) -> bool {
+        self.log.is_some()
+    }
+
+    /// Starts writing the timings of every frame to `LOG_PATH`, or stops.
+    pub fn toggle_logging(&mut self) -> Result<(), String> {
+        if let Some(mut log) = self.log.take() {
+            return log.flush().map_err(|e| e.to_string());
+        }
+
+        let mut log = BufWriter::new(File::create(LOG_PATH).map_err(|e| e.to_string())?);
+        writeln!(log, "frame,elapsed_ms,update_ms,render_ms,present_ms,draw_calls,entities")
+            .map_err(|e| e.to_string())?;
+        self.log = Some(log);
+        self.frames = 0;
+        Ok(())
+    }
+
+    /// The lines of text of the overlay.
+    fn lines(&self) -> Vec<String> {
+        let last = self.history.back().cloned().unwrap_or_default();
+        let mut lines = vec![
+            format!("FPS {:.0}  FRAME {:.1} MS", self.fps(), self.average(|frame| frame.elapsed) * 1000.0),
+            format!("UPDATE {:.2} MS  RENDER {:.2} MS", self.average(|frame| frame.update) * 1000.0,
+                    self.average(|frame| frame.render) * 1000.0),
+            format!("DRAW CALLS {}", last.draw_calls),
+        ];
+        lines.extend(self.counts.iter().map(|&(name, n)| format!("{} {}", name.to_uppercase(), n)));
+        if self.is_logging() {
+            lines.push(format!("LOGGING TO {}", LOG_PATH));
+        }
+        lines
+    }
+}
+
+/// Renders the overlay on top of the current frame, if it is visible.
+pub fn render_overlay(phi: &mut Phi) {
+    if !phi.perf.visible {
+        return;
+    }
+
+    if phi.perf.text_age >= TEXT_INTERVAL {
+        let lines = phi.perf.lines();
+        phi.perf.text = lines.iter()
+            .filter_map(|line| phi.ttf_str_sprite(line, FONT, 14, Color::RGB(255, 255, 255)))
+            .collect();
+        phi.perf.text_age = 0.0;
+    }
+
+    let margin = 8.0;
+    let (_, win_h) = phi.output_size();
+    let graph = Rectangle { x: margin, y: win_h - margin - GRAPH_H, w: GRAPH_W, h: GRAPH_H };
+    let line_h = phi.perf.text.first().map_or(0.0, |sprite| sprite.size().1);
+    let text_h = line_h * phi.perf.text.len() as f64;
+
+    phi.renderer.set_blend_mode(BlendMode::Blend);
+    phi.renderer.set_draw_color(Color::RGBA(0, 0, 0, 160));
+    gfx::fill_rect(&mut phi.renderer, Rectangle {
+        x: 0.0, y: graph.y - text_h - 2.0 * margin, w: graph.w + 2.0 * margin, h: win_h - graph.y + text_h + 2.0 * margin
+    });
+
+    let mut y = graph.y - text_h - margin;
+    for sprite in &phi.perf.text {
+        let (w, h) = sprite.size();
+        sprite.render(&mut phi.renderer, Rectangle { x: margin, y: y, w: w, h: h });
+        y += h;
+    }
+
+    // Every frame is a bar, split between the update (blue), the rendering
+    // (green) and the rest (grey).
+    let scale = TARGET_H / TARGET_FRAME;
+    for (i, frame) in phi.perf.history.iter().enumerate() {
+        let x = graph.x + (i * 2) as f64;
+        let mut bottom = graph.y + graph.h;
+        for &(seconds, color) in &[(frame.update, Color::RGB(80, 160, 255)),
+                                   (frame.render, Color::RGB(80, 220, 80)),
+                                   ((frame.elapsed - frame.update - frame.render).max(0.0), Color::RGB(140, 140, 140))] {
+            let h = (seconds * scale).min(bottom - graph.y);
+            phi.renderer.set_draw_color(color);
+            gfx::fill_rect(&mut phi.renderer, Rectangle { x: x, y: bottom - h, w: 2.0, h: h });
+            bottom -= h;
+        }
+    }
+
+    phi.renderer.set_draw_color(Color::RGB(255, 80, 80));
+    gfx::fill_rect(&mut phi.renderer, Rectangle { x: graph.x, y: graph.y + graph.h - TARGET_H, w: graph.w, h: 1.0 });
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn perf_frames() {
+        use phi::perf::{Frame, Perf, HISTORY};
+
+        let mut perf = Perf::new();
+        assert_eq!(perf.fps(), 0.0);
+
+        for i in 0..HISTORY + 10 {
+            let elapsed = if i < 10 { 1.0 } else { 0.02 };
+            perf.record(Frame { elapsed: elapsed, ..Frame::default() });
+        }
+
+        // Only the frames of the graph are averaged.
+        assert_eq!(perf.history.len(), HISTORY);
+        assert!((perf.fps() - 50.0).abs() < 1e-6);
+
+        let frame = Frame { elapsed: 0.0167, update: 0.002, render: 0.0051234, present: 0.0, draw_calls: 12, entities: 40 };
+        assert_eq!(frame.csv(7), "7,16.700,2.000,5.123,0.000,12,40");
+
+        perf.count("enemies", 3);
+        perf.count("bullets", 25);
+        let lines = perf.lines();
+        assert_eq!(lines[0], "FPS 50  FRAME 20.0 MS");
+        assert_eq!(&lines[3..], &["ENEMIES 3".to_string(), "BULLETS 25".to_string()]);
+    }
+}
@@ -0,0 +1,58 @@
+use phi::snapshot::Snapshot;
+use phi::toml::Value;
+
+/// A small and fast pseudo-random number generator (xorshift64*). Its whole
+/// state is a single `u64`, so every system which needs randomness can own
+/// its own generator without affecting the sequence seen by the others.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a generator from any `seed`, including zero.
+    pub fn new(seed: u64) -> Rng {
+        // Scramble the seed (splitmix64) so that close seeds do not produce
+        // similar sequences, and so that the state is never zero.
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+
+        Rng {
+            state: if z == 0 { 0x9E37_79B9_7F4A_7C15 } else { z },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a number in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        // Keep the 53 most significant bits, which is what fits in the
+        // mantissa of an `f64`.
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a number in `[min, max)`.
+    pub fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next_f64()
+    }
+}
+
+impl Snapshot for Rng {
+    fn snapshot(&self) -> Value {
+        self.state.snapshot()
+    }
+
+    fn restore(value: &Value) -> Result<Rng, String> {
+        match u64::restore(value)? {
+            0 => Err("the state of a generator cannot be zero".to_string()),
+            state => Ok(Rng { state: state }),
+        }
+    }
+}
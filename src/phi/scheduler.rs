@@ -0,0 +1,198 @@
+use phi::snapshot::{field, table, Snapshot};
+use phi::toml::Value;
+
+/// Identifies a timer of a `Scheduler`, to cancel it or to know whether it is
+/// still pending.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimerId(u64);
+
+impl Snapshot for TimerId {
+    fn snapshot(&self) -> Value {
+        self.0.snapshot()
+    }
+
+    fn restore(value: &Value) -> Result<TimerId, String> {
+        u64::restore(value).map(TimerId)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Timer<E> {
+    id: TimerId,
+    /// Seconds left before the timer fires.
+    left: f64,
+    /// Set for repeating timers, which fire again every `interval` seconds.
+    interval: Option<f64>,
+    event: E,
+}
+
+/// Fires events after a delay, once or repeatedly.
+///
+/// Views own a scheduler, advance it with the `elapsed` value they receive
+/// and handle the events it returns, so that timers stop whenever the view
+/// does: "do X in 2 seconds" becomes `after(2.0, Event::X)`. Cooldowns are
+/// one-shot timers whose only purpose is to be pending.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scheduler<E> {
+    timers: Vec<Timer<E>>,
+    next_id: u64,
+    paused: bool,
+}
+
+impl<E: Clone> Scheduler<E> {
+    pub fn new() -> Scheduler<E> {
+        Scheduler {
+            timers: Vec::new(),
+            next_id: 0,
+            paused: false,
+        }
+    }
+
+    /// Fires `event` once, in `delay` seconds.
+    pub fn after(&mut self, delay: f64, event: E) -> TimerId {
+        self.add(delay, None, event)
+    }
+
+    /// Fires `event` every `interval` seconds, starting in `interval`
+    /// seconds, until the timer is cancelled.
+    pub fn every(&mut self, interval: f64, event: E) -> TimerId {
+        assert!(interval > 0.0, "repeating timers need a positive interval");
+        self.add(interval, Some(interval), event)
+    }
+
+    fn add(&mut self, delay: f64, interval: Option<f64>, event: E) -> TimerId {
+        let id = TimerId(self.next_id);
+        self.next_id += 1;
+        self.timers.push(Timer { id: id, left: delay.max(0.0), interval: interval, event: event });
+        id
+    }
+
+    /// Stops a timer before it fires (again). Returns whether it was pending.
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        let before = self.timers.len();
+        self.timers.retain(|timer| timer.id != id);
+        self.timers.len() != before
+    }
+
+    pub fn is_pending(&self, id: TimerId) -> bool {
+        self.timers.iter().any(|timer| timer.id == id)
+    }
+
+    /// Seconds left before the timer `id` fires, if it is pending.
+    pub fn remaining(&self, id: TimerId) -> Option<f64> {
+        self.timers.iter().find(|timer| timer.id == id).map(|timer| timer.left)
+    }
+
+    /// Stops every timer until `resume` is called.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Advances the timers by `elapsed` seconds, and returns the events which
+    /// fired meanwhile, in the order they were due. A repeating timer fires
+    /// as many times as its interval fits in `elapsed`, so that it never
+    /// drifts, whatever the frame rate.
+    pub fn update(&mut self, elapsed: f64) -> Vec<E> {
+        if self.paused {
+            return Vec::new();
+        }
+
+        // When during the frame every event fired.
+        let mut fired: Vec<(f64, E)> = Vec::new();
+        for timer in &mut self.timers {
+            while timer.left <= elapsed {
+                fired.push((timer.left, timer.event.clone()));
+                match timer.interval {
+                    Some(interval) => timer.left += interval,
+                    None => break,
+                }
+            }
+            timer.left -= elapsed;
+        }
+
+        // One-shot timers which fired have no time left.
+        self.timers.retain(|timer| timer.interval.is_some() || timer.left > 0.0);
+
+        // Stable, so that timers due at the same time fire in the order they
+        // were added.
+        fired.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        fired.into_iter().map(|(_, event)| event).collect()
+    }
+}
+
+impl<E: Snapshot + Clone> Snapshot for Scheduler<E> {
+    fn snapshot(&self) -> Value {
+        let timers = self.timers.iter().map(|timer| table(vec![
+            ("id", timer.id.0.snapshot()),
+            ("left", timer.left.snapshot()),
+            ("interval", timer.interval.snapshot()),
+            ("event", timer.event.snapshot()),
+        ])).collect();
+
+        table(vec![
+            ("timers", Value::Array(timers)),
+            ("next_id", self.next_id.snapshot()),
+            ("paused", self.paused.snapshot()),
+        ])
+    }
+
+    fn restore(value: &Value) -> Result<Scheduler<E>, String> {
+        let timers = field::<Vec<Value>>(value, "timers")?.iter().map(|timer| Ok(Timer {
+            id: TimerId(field(timer, "id")?),
+            left: field(timer, "left")?,
+            interval: field(timer, "interval")?,
+            event: field(timer, "event")?,
+        })).collect::<Result<_, String>>()?;
+
+        Ok(Scheduler {
+            timers: timers,
+            next_id: field(value, "next_id")?,
+            paused: field(value, "paused")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn scheduler_timers() {
+        use phi::scheduler::Scheduler;
+        use phi::snapshot::Snapshot;
+
+        let mut scheduler = Scheduler::new();
+        let once = scheduler.after(1.0, "once");
+        let every = scheduler.every(0.4, "every");
+        let cancelled = scheduler.after(0.5, "cancelled");
+
+        assert!(scheduler.cancel(cancelled));
+        assert!(!scheduler.cancel(cancelled));
+        assert_eq!(scheduler.update(0.3), Vec::<&str>::new());
+        assert!((scheduler.remaining(once).unwrap() - 0.7).abs() < 1e-9);
+
+        // Repeating timers catch up on long frames, and events fire in the
+        // order they were due.
+        assert_eq!(scheduler.update(0.8), vec!["every", "every", "once"]);
+        assert!(!scheduler.is_pending(once));
+        assert!(scheduler.is_pending(every));
+
+        // Paused schedulers do not move.
+        scheduler.pause();
+        assert_eq!(scheduler.update(10.0), Vec::<&str>::new());
+        scheduler.resume();
+        assert!((scheduler.remaining(every).unwrap() - 0.1).abs() < 1e-9);
+
+        // Timers due at the same time fire in the order they were added.
+        let mut scheduler = Scheduler::new();
+        scheduler.after(1.0, 1.0);
+        scheduler.after(0.5, 2.0);
+        scheduler.after(1.0, 3.0);
+        let restored: Scheduler<f64> = Snapshot::restore(&scheduler.snapshot()).unwrap();
+        assert_eq!(restored, scheduler);
+        scheduler.update(0.25);
+        assert_eq!(scheduler.update(1.0), vec![2.0, 1.0, 3.0]);
+    }
+}
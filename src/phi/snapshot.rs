@@ -0,0 +1,204 @@
+//! Snapshots of the state of the game, which can be written to TOML and
+//! restored exactly: numbers are written with enough digits to be read back
+//! as the same `f64`, and integers which do not fit in one as hexadecimal
+//! strings.
+
+use phi::data::{Rectangle, Vec2};
+use phi::toml::{self, Table, Value};
+
+/// A type whose state can be saved in a snapshot and restored from it.
+/// Types which depend on assets (sprites, enemy kinds, ...) are restored in
+/// place instead, by an inherent `restore` method.
+pub trait Snapshot: Sized {
+    fn snapshot(&self) -> Value;
+    fn restore(value: &Value) -> Result<Self, String>;
+}
+
+/// Builds the snapshot of a struct from those of its fields.
+pub fn table(fields: Vec<(&str, Value)>) -> Value {
+    Value::Table(fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+}
+
+/// Builds the snapshot of a variant of an enum, whose name is kept in the
+/// `type` field.
+pub fn variant(name: &str, mut fields: Vec<(&str, Value)>) -> Value {
+    fields.push(("type", Value::String(name.to_string())));
+    table(fields)
+}
+
+/// Restores the field `key` of the snapshot of a struct.
+pub fn field<T: Snapshot>(value: &Value, key: &str) -> Result<T, String> {
+    let table: &Table = value.as_table().ok_or("expected a table")?;
+    let field = table.get(key).ok_or(format!("missing `{}`", key))?;
+    T::restore(field).map_err(|e| format!("{}: {}", key, e))
+}
+
+/// Returns the name of the variant saved with `variant`.
+pub fn variant_name(value: &Value) -> Result<String, String> {
+    field(value, "type")
+}
+
+/// Values restored later, for instance once the assets they need are loaded.
+impl Snapshot for Value {
+    fn snapshot(&self) -> Value {
+        self.clone()
+    }
+
+    fn restore(value: &Value) -> Result<Value, String> {
+        Ok(value.clone())
+    }
+}
+
+impl Snapshot for f64 {
+    fn snapshot(&self) -> Value {
+        Value::Float(*self)
+    }
+
+    fn restore(value: &Value) -> Result<f64, String> {
+        toml::number(value).ok_or("expected a number".to_string())
+    }
+}
+
+impl Snapshot for bool {
+    fn snapshot(&self) -> Value {
+        Value::Boolean(*self)
+    }
+
+    fn restore(value: &Value) -> Result<bool, String> {
+        value.as_bool().ok_or("expected a boolean".to_string())
+    }
+}
+
+impl Snapshot for u32 {
+    fn snapshot(&self) -> Value {
+        Value::Integer(*self as i64)
+    }
+
+    fn restore(value: &Value) -> Result<u32, String> {
+        toml::number(value).map(|n| n as u32).ok_or("expected a number".to_string())
+    }
+}
+
+impl Snapshot for usize {
+    fn snapshot(&self) -> Value {
+        Value::Integer(*self as i64)
+    }
+
+    fn restore(value: &Value) -> Result<usize, String> {
+        toml::number(value).map(|n| n as usize).ok_or("expected a number".to_string())
+    }
+}
+
+impl Snapshot for u64 {
+    fn snapshot(&self) -> Value {
+        Value::String(format!("{:016X}", self))
+    }
+
+    fn restore(value: &Value) -> Result<u64, String> {
+        value.as_str()
+            .and_then(|s| u64::from_str_radix(s, 16).ok())
+            .ok_or("expected a hexadecimal string".to_string())
+    }
+}
+
+impl Snapshot for String {
+    fn snapshot(&self) -> Value {
+        Value::String(self.clone())
+    }
+
+    fn restore(value: &Value) -> Result<String, String> {
+        value.as_str().map(|s| s.to_string()).ok_or("expected a string".to_string())
+    }
+}
+
+/// Saved as an array of zero or one element, since TOML has no null.
+impl<T: Snapshot> Snapshot for Option<T> {
+    fn snapshot(&self) -> Value {
+        Value::Array(self.iter().map(T::snapshot).collect())
+    }
+
+    fn restore(value: &Value) -> Result<Option<T>, String> {
+        match value.as_array() {
+            Some(items) if items.is_empty() => Ok(None),
+            Some(items) if items.len() == 1 => T::restore(&items[0]).map(Some),
+            _ => Err("expected an array of at most one element".to_string()),
+        }
+    }
+}
+
+impl<T: Snapshot> Snapshot for Vec<T> {
+    fn snapshot(&self) -> Value {
+        Value::Array(self.iter().map(T::snapshot).collect())
+    }
+
+    fn restore(value: &Value) -> Result<Vec<T>, String> {
+        value.as_array().ok_or("expected an array".to_string())?
+            .iter().enumerate()
+            .map(|(i, item)| T::restore(item).map_err(|e| format!("[{}]: {}", i, e)))
+            .collect()
+    }
+}
+
+impl Snapshot for Vec2 {
+    fn snapshot(&self) -> Value {
+        Value::Array(vec![Value::Float(self.x), Value::Float(self.y)])
+    }
+
+    fn restore(value: &Value) -> Result<Vec2, String> {
+        match Vec::<f64>::restore(value) {
+            Ok(ref xy) if xy.len() == 2 => Ok(Vec2::new(xy[0], xy[1])),
+            _ => Err("expected [x, y]".to_string()),
+        }
+    }
+}
+
+impl Snapshot for Rectangle {
+    fn snapshot(&self) -> Value {
+        vec![self.x, self.y, self.w, self.h].snapshot()
+    }
+
+    fn restore(value: &Value) -> Result<Rectangle, String> {
+        match Vec::<f64>::restore(value) {
+            Ok(ref r) if r.len() == 4 => Ok(Rectangle { x: r[0], y: r[1], w: r[2], h: r[3] }),
+            _ => Err("expected [x, y, w, h]".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn snapshot_values() {
+        use phi::data::{Rectangle, Vec2};
+        use phi::rng::Rng;
+        use phi::snapshot::{field, table, Snapshot};
+        use phi::toml;
+
+        // Written to a document and read back exactly
+        let mut rng = Rng::new(7);
+        rng.next_u64();
+        let value = table(vec![
+            ("third", (1.0f64 / 3.0).snapshot()),
+            ("tiny", 1e-300f64.snapshot()),
+            ("big", u64::MAX.snapshot()),
+            ("none", None::<Vec2>.snapshot()),
+            ("some", Some(Vec2::new(-0.1, 2.5)).snapshot()),
+            ("rect", Rectangle { x: 1.0, y: 2.0, w: 3.0, h: 4.0 }.snapshot()),
+            ("rng", rng.snapshot()),
+        ]);
+        let text = toml::to_string(value.as_table().unwrap()).unwrap();
+        let read = toml::Value::Table(toml::parse(&text).unwrap());
+        assert_eq!(read, value);
+
+        assert_eq!(field::<f64>(&read, "third"), Ok(1.0 / 3.0));
+        assert_eq!(field::<u64>(&read, "big"), Ok(u64::MAX));
+        assert_eq!(field::<Option<Vec2>>(&read, "none"), Ok(None));
+        assert_eq!(field::<Option<Vec2>>(&read, "some"), Ok(Some(Vec2::new(-0.1, 2.5))));
+        assert!(field::<Vec2>(&read, "rect").is_err());
+        assert_eq!(field::<f64>(&read, "missing"), Err("missing `missing`".to_string()));
+
+        // The generator goes on with the same sequence.
+        let mut restored: Rng = field(&read, "rng").unwrap();
+        assert_eq!(restored.next_u64(), rng.next_u64());
+    }
+}
@@ -0,0 +1,140 @@
+use phi::tween::{Easing, Tween};
+
+/// The seconds by which the game advances for every step, while it is paused.
+pub const STEP: f64 = 1.0 / 60.0;
+
+/// The bounds of the scale set from the console.
+pub const MIN_SCALE: f64 = 0.01;
+pub const MAX_SCALE: f64 = 10.0;
+
+/// Controls how fast time goes by for the views.
+///
+/// The main loop scales the `elapsed` value of every frame before handing it
+/// to the view. The scale is the product of the one set for debugging and
+/// of the slow motion requested by the game, which wears off by itself. A
+/// paused game does not move at all, unless it is advanced one step at a
+/// time.
+///
+/// The console and the performance overlay keep measuring real time.
+pub struct TimeScale {
+    scale: f64,
+    /// The factor of the slow motion, which eases back to 1.
+    slow_motion: Option<Tween<f64>>,
+    paused: bool,
+    /// The steps requested since the last frame.
+    steps: u32,
+    /// Whether the views stand still during the current frame.
+    frozen: bool,
+}
+
+impl TimeScale {
+    pub fn new() -> TimeScale {
+        TimeScale {
+            scale: 1.0,
+            slow_motion: None,
+            paused: false,
+            steps: 0,
+            frozen: false,
+        }
+    }
+
+    pub fn set_scale(&mut self, scale: f64) -> Result<(), String> {
+        if !(MIN_SCALE..=MAX_SCALE).contains(&scale) {
+            return Err(format!("must be between {} and {}", MIN_SCALE, MAX_SCALE));
+        }
+        self.scale = scale;
+        Ok(())
+    }
+
+    /// Slows the game down by `factor`, which goes back to normal over
+    /// `duration` real seconds. The slowest request wins while several
+    /// overlap.
+    pub fn slow_motion(&mut self, factor: f64, duration: f64) {
+        let slower = self.slow_motion.as_ref().is_none_or(|current| factor <= current.value());
+        if slower {
+            self.slow_motion = Some(Tween::new(factor, 1.0, duration, Easing::QuadIn));
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Whether the game is paused, and was not stepped during this frame.
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// Pauses the game, or resumes it.
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.steps = 0;
+    }
+
+    /// Advances a paused game by `STEP` seconds during the next frame.
+    pub fn step(&mut self) {
+        if self.paused {
+            self.steps += 1;
+        }
+    }
+
+    /// Returns the seconds by which the views should advance, for a frame
+    /// which lasted `elapsed` real seconds.
+    pub fn update(&mut self, elapsed: f64) -> f64 {
+        self.frozen = self.paused && self.steps == 0;
+        if self.paused {
+            let steps = self.steps;
+            self.steps = 0;
+            return steps as f64 * STEP * self.scale;
+        }
+
+        let factor = match self.slow_motion {
+            Some(ref mut slow_motion) => slow_motion.update(elapsed),
+            None => 1.0,
+        };
+        if self.slow_motion.as_ref().is_some_and(|slow_motion| slow_motion.progress() >= 1.0) {
+            self.slow_motion = None;
+        }
+
+        elapsed * self.scale * factor
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn time_scale() {
+        use phi::timescale::{TimeScale, STEP};
+
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        let mut time = TimeScale::new();
+        assert!(close(time.update(0.1), 0.1));
+
+        assert!(time.set_scale(0.0).is_err());
+        assert!(time.set_scale(0.5).is_ok());
+        assert!(close(time.update(0.1), 0.05));
+
+        // Paused games only move when they are stepped.
+        time.toggle_pause();
+        assert_eq!(time.update(0.1), 0.0);
+        assert!(time.is_frozen());
+        time.step();
+        time.step();
+        assert!(close(time.update(0.1), 2.0 * STEP * 0.5));
+        assert!(!time.is_frozen());
+        assert_eq!(time.update(0.1), 0.0);
+        time.toggle_pause();
+        time.step();
+        assert!(close(time.update(0.1), 0.05));
+        assert!(!time.is_frozen());
+
+        // Slow motion wears off, and faster requests do not cut it short.
+        time.set_scale(1.0).unwrap();
+        time.slow_motion(0.2, 1.0);
+        time.slow_motion(0.5, 2.0);
+        assert!(close(time.update(0.0), 0.0));
+        assert!(time.update(0.5) < 0.5 * 0.5);
+        assert!(close(time.update(0.5), 0.5));
+        assert!(close(time.update(0.1), 0.1));
+    }
+}
@@ -0,0 +1,131 @@
+//! Reading and writing of the game's data files, on top of the `toml` crate.
+//!
+//! The files are handled as tables of values, which the game walks itself
+//! so that it can point at the key which is wrong. Both integers and floats
+//! are accepted wherever a number is expected, see `number`.
+
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+
+pub use toml::{Table, Value};
+
+/// Reads and parses the file at `path`.
+pub fn load(path: &str) -> Result<Table, String> {
+    let mut text = String::new();
+    File::open(Path::new(path))
+        .and_then(|mut file| file.read_to_string(&mut text))
+        .map_err(|e| format!("{}: {}", path, e))?;
+
+    parse(&text).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Parses a TOML document. Errors read `line N: message`.
+pub fn parse(text: &str) -> Result<Table, String> {
+    text.parse::<Table>().map_err(|e| {
+        let message = e.message().trim_end().to_string();
+        match e.span() {
+            Some(span) => format!("line {}: {}", text[..span.start].matches('\n').count() + 1, message),
+            None => message,
+        }
+    })
+}
+
+/// Writes `table` to the file at `path`, replacing its previous content.
+/// The document is first written next to it, then moved in place, so that
+/// the file is never left half-written if the game stops in the meantime.
+pub fn save(path: &str, table: &Table) -> Result<(), String> {
+    let temporary = format!("{}.tmp", path);
+    let text = to_string(table).map_err(|e| format!("{}: {}", path, e))?;
+
+    File::create(Path::new(&temporary))
+        .and_then(|mut file| {
+            file.write_all(text.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temporary, path))
+        .map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Returns the TOML document describing `table`, which `parse` reads back
+/// as the same table.
+pub fn to_string(table: &Table) -> Result<String, String> {
+    ::toml::to_string(table).map_err(|e| e.to_string())
+}
+
+/// Returns the value as a number, whether it was written as an integer or
+/// as a float.
+pub fn number(value: &Value) -> Option<f64> {
+    match *value {
+        Value::Integer(n) => Some(n as f64),
+        Value::Float(n) => Some(n),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn read_documents() {
+        use phi::toml::{number, parse};
+
+        let doc = parse(r#"
+            # A comment
+            name = "First \"level\""  # Another one
+            duration = 90.5
+            lives = -3
+            hard = false
+
+            [enemies.grunt]
+            tint = [255, 0, 0]
+
+            [[waves]]
+            at = 1.0
+        "#).unwrap();
+
+        assert_eq!(doc["name"].as_str(), Some("First \"level\""));
+        assert_eq!(number(&doc["duration"]), Some(90.5));
+        assert_eq!(number(&doc["lives"]), Some(-3.0));
+        assert_eq!(number(&doc["hard"]), None);
+
+        let grunt = doc["enemies"].as_table().unwrap()["grunt"].as_table().unwrap();
+        assert_eq!(grunt["tint"].as_array().unwrap().iter().filter_map(number).collect::<Vec<_>>(), vec![255.0, 0.0, 0.0]);
+        assert_eq!(doc["waves"].as_array().unwrap().len(), 1);
+
+        assert_eq!(parse("a = 1\n\na = 2\n").map_err(|e| e.starts_with("line 3: ")), Err(true));
+        assert!(parse("a = [1, 2").is_err());
+    }
+
+    #[test]
+    fn write_documents() {
+        use phi::toml::{parse, to_string, Table, Value};
+
+        let doc = parse(r#"
+            name = "Tricky \"quotes\"\n"
+            "spaced key" = 1.5
+            empty = []
+            points = [[0, 0], [-100, 50]]
+            inline = [{ x = 1 }, 2]
+
+            [settings.audio]
+            volume = 0.8
+            muted = false
+
+            [[scores]]
+            score = 1200
+
+            [scores.details]
+            stage = 3
+
+            [[scores]]
+            score = 800
+        "#).unwrap();
+
+        assert_eq!(parse(&to_string(&doc).unwrap()), Ok(doc));
+        assert_eq!(to_string(&Table::new()), Ok(String::new()));
+
+        let mut table = Table::new();
+        table.insert("a".to_string(), Value::Float(3.0));
+        assert_eq!(parse(&to_string(&table).unwrap()).unwrap()["a"], Value::Float(3.0));
+    }
+}
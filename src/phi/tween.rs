@@ -0,0 +1,486 @@
+// The easings and animations are a toolbox for the views: not all of them
+// are used by the game yet.
+#![allow(dead_code)]
+
+use phi::data::{Rectangle, Vec2};
+use sdl2::pixels::Color;
+use std::cell::Cell;
+use std::f64::consts::PI;
+use std::rc::Rc;
+
+/// How the progress of an animation, from 0 to 1, is distributed over its
+/// duration. `In` easings start slowly, `Out` easings stop slowly, and
+/// `InOut` easings do both.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineInOut,
+    /// Goes slightly past the end before settling on it.
+    BackOut,
+    /// Overshoots and wobbles around the end, like a spring.
+    ElasticOut,
+    /// Bounces on the end, like a dropped ball.
+    BounceOut,
+}
+
+impl Easing {
+    /// Returns the eased progress for `t`, which is clamped between 0 and 1.
+    /// The result is 0 for `t == 0` and 1 for `t == 1`, but some easings go
+    /// past these values in between.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut =>
+                if t < 0.5 { 2.0 * t * t } else { 1.0 - 2.0 * (1.0 - t) * (1.0 - t) },
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut =>
+                if t < 0.5 { 4.0 * t * t * t } else { 1.0 - 4.0 * (1.0 - t).powi(3) },
+            Easing::SineInOut => (1.0 - (PI * t).cos()) / 2.0,
+            Easing::BackOut => {
+                let s = 1.70158;
+                let u = t - 1.0;
+                1.0 + u * u * ((s + 1.0) * u + s)
+            },
+            Easing::ElasticOut =>
+                if t == 0.0 || t == 1.0 { t }
+                else { 1.0 + 2f64.powf(-10.0 * t) * ((t * 10.0 - 0.75) * 2.0 * PI / 3.0).sin() },
+            Easing::BounceOut => {
+                let (n, d) = (7.5625, 2.75);
+                if t < 1.0 / d {
+                    n * t * t
+                } else if t < 2.0 / d {
+                    let t = t - 1.5 / d;
+                    n * t * t + 0.75
+                } else if t < 2.5 / d {
+                    let t = t - 2.25 / d;
+                    n * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / d;
+                    n * t * t + 0.984375
+                }
+            },
+        }
+    }
+}
+
+/// Values which can be animated by a `Tween`.
+pub trait Lerp: Copy {
+    /// Returns `self` for `t == 0`, `other` for `t == 1`, and the values in
+    /// between (or beyond, for easings which overshoot) otherwise.
+    fn lerp(self, other: Self, t: f64) -> Self;
+}
+
+impl Lerp for f64 {
+    fn lerp(self, other: f64, t: f64) -> f64 {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(self, other: Vec2, t: f64) -> Vec2 {
+        Vec2::lerp(self, other, t)
+    }
+}
+
+impl Lerp for Color {
+    /// Every channel, including the alpha, is interpolated separately and
+    /// clamped to its range.
+    fn lerp(self, other: Color, t: f64) -> Color {
+        let (r1, g1, b1, a1) = self.rgba();
+        let (r2, g2, b2, a2) = other.rgba();
+        let channel = |a: u8, b: u8| (a as f64).lerp(b as f64, t).round().clamp(0.0, 255.0) as u8;
+        Color::RGBA(channel(r1, r2), channel(g1, g2), channel(b1, b2), channel(a1, a2))
+    }
+}
+
+impl Lerp for Rectangle {
+    fn lerp(self, other: Rectangle, t: f64) -> Rectangle {
+        Rectangle {
+            x: self.x.lerp(other.x, t),
+            y: self.y.lerp(other.y, t),
+            w: self.w.lerp(other.w, t),
+            h: self.h.lerp(other.h, t),
+        }
+    }
+}
+
+/// Something which plays over time, driven by the `elapsed` value given to
+/// views. Tweens, delays and groups of them all are animations, so that
+/// groups can be nested.
+pub trait Animation {
+    /// Moves the animation `elapsed` seconds forward. Returns the seconds
+    /// which were not needed to finish it, so that sequences can give them
+    /// to the next animation.
+    fn advance(&mut self, elapsed: f64) -> f64;
+
+    fn is_finished(&self) -> bool;
+}
+
+/// Animates a value from `from` to `to` in `duration` seconds.
+///
+/// The current value is read with `value`. A tween can also be `bound` to a
+/// shared cell, which it updates as it plays: this is how the values of the
+/// tweens inside of groups are read.
+pub struct Tween<T: Lerp> {
+    from: T,
+    to: T,
+    duration: f64,
+    easing: Easing,
+    time: f64,
+    target: Option<Rc<Cell<T>>>,
+    /// Called once, when the tween finishes.
+    done: Option<Box<dyn FnMut()>>,
+    /// Whether the tween finished since it started moving toward its end.
+    notified: bool,
+}
+
+impl<T: Lerp> Tween<T> {
+    pub fn new(from: T, to: T, duration: f64, easing: Easing) -> Tween<T> {
+        Tween {
+            from: from,
+            to: to,
+            duration: duration,
+            easing: easing,
+            time: 0.0,
+            target: None,
+            done: None,
+            notified: false,
+        }
+    }
+
+    /// A finished tween, which stays at `value` until it is `retarget`ed.
+    pub fn idle(value: T, duration: f64, easing: Easing) -> Tween<T> {
+        let mut tween = Tween::new(value, value, duration, easing);
+        tween.time = duration;
+        tween.notified = true;
+        tween
+    }
+
+    /// Returns the same tween, which writes its value to `target` every time
+    /// it is updated.
+    pub fn bound(mut self, target: &Rc<Cell<T>>) -> Tween<T> {
+        target.set(self.value());
+        self.target = Some(target.clone());
+        self
+    }
+
+    /// Returns the same tween, which calls `done` once it finishes, during
+    /// the first `advance` for tweens which take no time.
+    pub fn on_complete<F: FnMut() + 'static>(mut self, done: F) -> Tween<T> {
+        self.done = Some(Box::new(done));
+        self
+    }
+
+    /// The progress of the tween, from 0 to 1, before it is eased.
+    pub fn progress(&self) -> f64 {
+        if self.duration <= 0.0 { 1.0 } else { (self.time / self.duration).min(1.0) }
+    }
+
+    pub fn value(&self) -> T {
+        self.from.lerp(self.to, self.easing.apply(self.progress()))
+    }
+
+    /// The value the tween ends at.
+    pub fn end(&self) -> T {
+        self.to
+    }
+
+    /// Starts moving from the current value to `to` instead, taking the full
+    /// duration again. Useful for values which change direction midway, such
+    /// as the highlight of the selected item of a menu.
+    pub fn retarget(&mut self, to: T) {
+        self.from = self.value();
+        self.to = to;
+        self.time = 0.0;
+        self.notified = false;
+    }
+
+    /// Advances the tween, then returns its value.
+    pub fn update(&mut self, elapsed: f64) -> T {
+        self.advance(elapsed);
+        self.value()
+    }
+}
+
+impl<T: Lerp> Animation for Tween<T> {
+    fn advance(&mut self, elapsed: f64) -> f64 {
+        let was_finished = self.is_finished();
+        let left = (self.time + elapsed - self.duration).max(0.0);
+        self.time = (self.time + elapsed).min(self.duration.max(0.0));
+
+        if let Some(ref target) = self.target {
+            target.set(self.value());
+        }
+
+        if !self.notified && self.is_finished() {
+            self.notified = true;
+            if let Some(ref mut done) = self.done {
+                done();
+            }
+        }
+
+        if was_finished { elapsed } else { left }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.time >= self.duration
+    }
+}
+
+/// Does nothing for `duration` seconds, to space out the animations of a
+/// sequence.
+pub struct Delay {
+    duration: f64,
+    time: f64,
+}
+
+impl Delay {
+    pub fn new(duration: f64) -> Delay {
+        Delay {
+            duration: duration,
+            time: 0.0,
+        }
+    }
+}
+
+impl Animation for Delay {
+    fn advance(&mut self, elapsed: f64) -> f64 {
+        let left = (self.time + elapsed - self.duration).max(0.0);
+        self.time = (self.time + elapsed).min(self.duration);
+        left
+    }
+
+    fn is_finished(&self) -> bool {
+        self.time >= self.duration
+    }
+}
+
+/// Animations played one after the other (a sequence), or all at once
+/// (a parallel group). The group finishes with its last animation.
+pub struct Group {
+    animations: Vec<Box<dyn Animation>>,
+    parallel: bool,
+    /// The animation being played, in sequences.
+    current: usize,
+    done: Option<Box<dyn FnMut()>>,
+    /// Whether `done` was already called.
+    notified: bool,
+}
+
+impl Group {
+    pub fn sequence() -> Group {
+        Group::new(false)
+    }
+
+    pub fn parallel() -> Group {
+        Group::new(true)
+    }
+
+    fn new(parallel: bool) -> Group {
+        Group {
+            animations: Vec::new(),
+            parallel: parallel,
+            current: 0,
+            done: None,
+            notified: false,
+        }
+    }
+
+    /// Returns the same group, with `animation` added after (or along with)
+    /// the others.
+    pub fn then<A: Animation + 'static>(mut self, animation: A) -> Group {
+        self.animations.push(Box::new(animation));
+        self
+    }
+
+    /// Returns the same group, which calls `done` once it finishes, during
+    /// the first `advance` for groups which take no time.
+    pub fn on_complete<F: FnMut() + 'static>(mut self, done: F) -> Group {
+        self.done = Some(Box::new(done));
+        self
+    }
+}
+
+impl Animation for Group {
+    fn advance(&mut self, elapsed: f64) -> f64 {
+        let left = if self.parallel {
+            self.animations.iter_mut()
+                .map(|animation| animation.advance(elapsed))
+                .fold(elapsed, f64::min)
+        } else {
+            let mut left = elapsed;
+            while let Some(animation) = self.animations.get_mut(self.current) {
+                left = animation.advance(left);
+                if !animation.is_finished() {
+                    break;
+                }
+                self.current += 1;
+            }
+            left
+        };
+
+        if !self.notified && self.is_finished() {
+            self.notified = true;
+            if let Some(ref mut done) = self.done {
+                done();
+            }
+        }
+
+        left
+    }
+
+    fn is_finished(&self) -> bool {
+        self.animations.iter().all(|animation| animation.is_finished())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn easings() {
+        use phi::tween::Easing;
+
+        let all = [Easing::Linear, Easing::QuadIn, Easing::QuadOut, Easing::QuadInOut,
+                   Easing::CubicIn, Easing::CubicOut, Easing::CubicInOut, Easing::SineInOut,
+                   Easing::BackOut, Easing::ElasticOut, Easing::BounceOut];
+
+        for &easing in &all {
+            assert!(easing.apply(0.0).abs() < 1e-9, "{:?}", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-9, "{:?}", easing);
+            assert_eq!(easing.apply(-1.0), easing.apply(0.0));
+            assert_eq!(easing.apply(2.0), easing.apply(1.0));
+        }
+
+        assert_eq!(Easing::Linear.apply(0.25), 0.25);
+        assert_eq!(Easing::QuadIn.apply(0.5), 0.25);
+        assert!(Easing::QuadOut.apply(0.5) > 0.5);
+        assert!(Easing::CubicIn.apply(0.5) < Easing::QuadIn.apply(0.5));
+        assert!(Easing::CubicOut.apply(0.5) > Easing::QuadOut.apply(0.5));
+        assert!(Easing::BackOut.apply(0.7) > 1.0);
+
+        // `InOut` easings are symmetric around the middle.
+        for &easing in &[Easing::QuadInOut, Easing::CubicInOut, Easing::SineInOut] {
+            assert!((easing.apply(0.5) - 0.5).abs() < 1e-9, "{:?}", easing);
+            assert!(easing.apply(0.25) < 0.25, "{:?}", easing);
+            assert!((easing.apply(0.25) + easing.apply(0.75) - 1.0).abs() < 1e-9, "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn tweens() {
+        use phi::data::{Rectangle, Vec2};
+        use phi::tween::{Animation, Easing, Lerp, Tween};
+        use sdl2::pixels::Color;
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        assert_eq!(Vec2::new(0.0, 10.0).lerp(Vec2::new(10.0, 0.0), 0.5), Vec2::new(5.0, 5.0));
+        assert_eq!(Color::RGB(0, 100, 255).lerp(Color::RGB(255, 200, 255), 0.5).rgba(), (128, 150, 255, 255));
+        assert_eq!(Color::RGB(0, 0, 0).lerp(Color::RGB(255, 255, 255), 2.0).rgba(), (255, 255, 255, 255));
+        let rect = Rectangle { x: 0.0, y: 0.0, w: 10.0, h: 10.0 };
+        assert_eq!(rect.lerp(Rectangle { x: 10.0, .. rect }, 0.3).x, 3.0);
+
+        let finished = Rc::new(Cell::new(0));
+        let target = Rc::new(Cell::new(0.0));
+        let mut tween = {
+            let finished = finished.clone();
+            Tween::new(0.0, 10.0, 2.0, Easing::QuadIn)
+                .bound(&target)
+                .on_complete(move || finished.set(finished.get() + 1))
+        };
+
+        assert_eq!(tween.update(0.5), 0.625);
+        assert_eq!(target.get(), 0.625);
+        assert!(!tween.is_finished());
+
+        // The time which was not needed is given back, and the callback is
+        // only called once.
+        assert_eq!(tween.advance(2.0), 0.5);
+        assert_eq!(tween.value(), 10.0);
+        assert!(tween.is_finished());
+        assert_eq!(tween.advance(1.0), 1.0);
+        assert_eq!(finished.get(), 1);
+
+        // Retargeting starts from where the tween is, and finishes again.
+        tween.retarget(0.0);
+        assert_eq!(tween.update(1.0), 7.5);
+        assert_eq!(finished.get(), 1);
+        tween.advance(1.0);
+        assert_eq!(finished.get(), 2);
+
+        // Tweens which take no time finish as soon as they are advanced.
+        let instant = Rc::new(Cell::new(0));
+        let mut tween = {
+            let instant = instant.clone();
+            Tween::new(0.0, 1.0, 0.0, Easing::QuadOut).on_complete(move || instant.set(instant.get() + 1))
+        };
+        assert_eq!(tween.value(), 1.0);
+        assert_eq!(tween.advance(0.25), 0.25);
+        tween.advance(0.25);
+        assert_eq!(instant.get(), 1);
+
+        let mut idle = Tween::idle(1.0, 0.5, Easing::QuadOut);
+        assert!(idle.is_finished());
+        assert_eq!(idle.update(1.0), 1.0);
+    }
+
+    #[test]
+    fn tween_groups() {
+        use phi::tween::{Animation, Delay, Easing, Group, Tween};
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let x = Rc::new(Cell::new(0.0));
+        let y = Rc::new(Cell::new(0.0));
+        let finished = Rc::new(Cell::new(0));
+
+        let mut sequence = {
+            let finished = finished.clone();
+            Group::sequence()
+                .then(Tween::new(0.0, 1.0, 1.0, Easing::Linear).bound(&x))
+                .then(Delay::new(0.5))
+                .then(Group::parallel()
+                    .then(Tween::new(1.0, 0.0, 1.0, Easing::Linear).bound(&x))
+                    .then(Tween::new(0.0, 4.0, 2.0, Easing::Linear).bound(&y)))
+                .on_complete(move || finished.set(finished.get() + 1))
+        };
+
+        // The leftover time of an animation goes to the next one.
+        sequence.advance(1.25);
+        assert_eq!((x.get(), y.get()), (1.0, 0.0));
+        sequence.advance(0.75);
+        assert_eq!((x.get(), y.get()), (0.5, 1.0));
+
+        // Parallel groups last as long as their longest animation, and the
+        // callback is only called once.
+        sequence.advance(1.0);
+        assert_eq!((x.get(), y.get()), (0.0, 3.0));
+        assert!(!sequence.is_finished());
+        assert_eq!(finished.get(), 0);
+        assert_eq!(sequence.advance(1.0), 0.5);
+        assert_eq!(y.get(), 4.0);
+        assert!(sequence.is_finished());
+        sequence.advance(1.0);
+        assert_eq!(finished.get(), 1);
+
+        // Empty groups finish as soon as they are advanced.
+        let empty = Rc::new(Cell::new(false));
+        let mut group = {
+            let empty = empty.clone();
+            Group::parallel().on_complete(move || empty.set(true))
+        };
+        assert_eq!(group.advance(0.25), 0.25);
+        assert!(empty.get());
+    }
+}
@@ -0,0 +1,417 @@
+use phi::camera::Camera;
+use phi::data::{Hitbox, Rectangle, Shape, Vec2};
+use phi::gfx::{self, Sprite};
+use phi::snapshot::{field, table, variant, variant_name, Snapshot};
+use phi::toml::Value;
+use sdl2::pixels::Color;
+use sdl2::render::Renderer;
+use std::rc::Rc;
+use views::game::bullet::{BulletPool, Owner};
+use views::game::difficulty::Scaling;
+use views::game::emitter::Emitter;
+use views::game::enemy::EnemyKind;
+use views::game::movement::{Movement, Pattern};
+use views::game::state::StateMachine;
+
+/// Seconds during which a boss pauses, invulnerable, between two phases.
+const PHASE_TRANSITION: f64 = 1.0;
+
+/// Seconds between the killing blow and the end of the level.
+const DEFEAT_DURATION: f64 = 3.0;
+
+/// Seconds between two explosions of a dying boss.
+const EXPLOSION_INTERVAL: f64 = 0.25;
+
+/// Pixels per second gained every second by a boss leaving the screen.
+const LEAVE_ACCELERATION: f64 = 400.0;
+
+/// Seconds taken by a boss to leave the screen once its time is over.
+const LEAVE_DURATION: f64 = 3.0;
+
+/// A stage of the fight against a boss, with its own movement and weapons.
+#[derive(Clone)]
+pub struct Phase {
+    /// The phase starts once the boss's health falls to this fraction of its
+    /// maximum.
+    pub threshold: f64,
+    /// Positions are relative to where the boss is when the phase starts.
+    pub pattern: Pattern,
+    pub speed: f64,
+    pub weapons: Vec<Emitter>,
+}
+
+/// A part of a boss which takes more damage than the rest of its hull.
+#[derive(Clone)]
+pub struct WeakPoint {
+    /// Relative to the top-left corner of the boss.
+    pub shape: Shape,
+    /// Multiplies the damage of the bullets hitting this point.
+    pub multiplier: f64,
+}
+
+/// A boss, as described in a level file.
+pub struct BossKind {
+    /// The size, hull and health of the boss.
+    pub enemy: Rc<EnemyKind>,
+    /// Where the center of the boss appears.
+    pub position: Vec2,
+    /// Where the boss goes during its entrance, before the fight starts.
+    pub arena: Vec2,
+    /// Seconds taken by the entrance.
+    pub entrance: f64,
+    /// Seconds of fighting after which the boss leaves, if any.
+    pub timeout: Option<f64>,
+    /// Multiplies the damage of the bullets hitting the hull.
+    pub armor: f64,
+    /// At least one, sorted by decreasing threshold.
+    pub phases: Vec<Phase>,
+    pub weak_points: Vec<WeakPoint>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BossState {
+    /// Flying to the arena. Cannot be damaged.
+    Entering,
+    /// Pausing before the given phase. Cannot be damaged.
+    Transition(usize),
+    Fighting(usize),
+    /// Flying away once the time is over.
+    Leaving,
+    /// The defeat sequence, which shakes the screen with explosions.
+    Dying,
+    Defeated,
+    /// The boss left the screen without being defeated.
+    Escaped,
+}
+
+impl Snapshot for BossState {
+    fn snapshot(&self) -> Value {
+        match *self {
+            BossState::Entering => variant("entering", vec![]),
+            BossState::Transition(phase) => variant("transition", vec![("phase", phase.snapshot())]),
+            BossState::Fighting(phase) => variant("fighting", vec![("phase", phase.snapshot())]),
+            BossState::Leaving => variant("leaving", vec![]),
+            BossState::Dying => variant("dying", vec![]),
+            BossState::Defeated => variant("defeated", vec![]),
+            BossState::Escaped => variant("escaped", vec![]),
+        }
+    }
+
+    fn restore(value: &Value) -> Result<BossState, String> {
+        Ok(match &variant_name(value)?[..] {
+            "entering" => BossState::Entering,
+            "transition" => BossState::Transition(field(value, "phase")?),
+            "fighting" => BossState::Fighting(field(value, "phase")?),
+            "leaving" => BossState::Leaving,
+            "dying" => BossState::Dying,
+            "defeated" => BossState::Defeated,
+            "escaped" => BossState::Escaped,
+            other => return Err(format!("unknown boss state `{}`", other)),
+        })
+    }
+}
+
+pub struct Boss {
+    pub kind: Rc<BossKind>,
+    pub pos: Rectangle,
+    pub health: f64,
+    pub max_health: f64,
+    /// The index of the last player who damaged the boss, who gets the points
+    /// if it is defeated.
+    pub last_hit: Option<usize>,
+    machine: StateMachine<BossState>,
+    scaling: Option<Scaling>,
+    movement: Option<Movement>,
+    weapons: Vec<Emitter>,
+    /// Seconds spent fighting, over every phase.
+    fight_time: f64,
+    /// Pixels traveled every second while leaving or dying.
+    velocity: Vec2,
+}
+
+impl Boss {
+    pub fn new(kind: Rc<BossKind>) -> Boss {
+        let (w, h) = kind.enemy.size;
+
+        Boss {
+            pos: Rectangle::from_center(kind.position, w, h),
+            health: kind.enemy.health,
+            max_health: kind.enemy.health,
+            last_hit: None,
+            kind: kind,
+            machine: StateMachine::new(BossState::Entering),
+            scaling: None,
+            movement: None,
+            weapons: Vec::new(),
+            fight_time: 0.0,
+            velocity: Vec2::zero(),
+        }
+    }
+
+    /// The kind of the boss is not saved, since it comes with the level.
+    pub fn snapshot(&self) -> Value {
+        table(vec![
+            ("pos", self.pos.snapshot()),
+            ("health", self.health.snapshot()),
+            ("max_health", self.max_health.snapshot()),
+            ("last_hit", self.last_hit.snapshot()),
+            ("machine", self.machine.snapshot()),
+            ("scaling", self.scaling.snapshot()),
+            ("movement", self.movement.snapshot()),
+            ("weapons", self.weapons.snapshot()),
+            ("fight_time", self.fight_time.snapshot()),
+            ("velocity", self.velocity.snapshot()),
+        ])
+    }
+
+    pub fn restore(value: &Value, kind: Rc<BossKind>) -> Result<Boss, String> {
+        let boss = Boss {
+            kind: kind,
+            pos: field(value, "pos")?,
+            health: field(value, "health")?,
+            max_health: field(value, "max_health")?,
+            last_hit: field(value, "last_hit")?,
+            machine: field(value, "machine")?,
+            scaling: field(value, "scaling")?,
+            movement: field(value, "movement")?,
+            weapons: field(value, "weapons")?,
+            fight_time: field(value, "fight_time")?,
+            velocity: field(value, "velocity")?,
+        };
+
+        match boss.state() {
+            BossState::Transition(phase) | BossState::Fighting(phase) if phase >= boss.kind.phases.len() =>
+                Err(format!("the boss has no phase {}", phase)),
+            _ => Ok(boss),
+        }
+    }
+
+    /// Adapts the boss to the difficulty. Must be called once, when it
+    /// spawns.
+    pub fn scale(&mut self, scaling: Scaling) {
+        self.max_health *= scaling.health;
+        self.health = self.max_health;
+        self.scaling = Some(scaling);
+    }
+
+    pub fn state(&self) -> BossState {
+        self.machine.state()
+    }
+
+    /// Whether the boss can still be hit, or ram into the player.
+    pub fn is_alive(&self) -> bool {
+        !matches!(self.state(), BossState::Dying | BossState::Defeated | BossState::Escaped)
+    }
+
+    /// Whether the boss takes damage from the bullets hitting it.
+    pub fn is_vulnerable(&self) -> bool {
+        matches!(self.state(), BossState::Fighting(_))
+    }
+
+    /// Whether the encounter is over, one way or the other.
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state(), BossState::Defeated | BossState::Escaped)
+    }
+
+    /// `player` is the center of the player's ship. The camera is shaken by
+    /// the changes of phase and the defeat sequence.
+    pub fn update(&mut self, elapsed: f64, player: Vec2, bullets: &mut BulletPool, camera: &mut Camera) {
+        self.machine.update(elapsed);
+        let kind = self.kind.clone();
+        let mut center = self.pos.center();
+
+        match self.machine.state() {
+            BossState::Entering => {
+                let progress = self.entrance_progress();
+                center = kind.position.lerp(kind.arena, progress);
+
+                if progress >= 1.0 {
+                    self.machine.set(BossState::Fighting(0));
+                }
+            },
+
+            BossState::Transition(phase) => {
+                if self.machine.entered() {
+                    bullets.clear(Owner::Enemy);
+                    camera.add_trauma(0.4);
+                }
+
+                if self.machine.time() >= PHASE_TRANSITION {
+                    self.machine.set(BossState::Fighting(phase));
+                }
+            },
+
+            BossState::Fighting(phase) => {
+                let current = &kind.phases[phase];
+
+                if self.machine.entered() {
+                    self.movement = Some(Movement::new(current.pattern.clone(), current.speed, center));
+                    self.weapons = current.weapons.clone();
+
+                    if let Some(scaling) = self.scaling {
+                        for weapon in &mut self.weapons {
+                            weapon.speed *= scaling.bullet_speed;
+                            weapon.interval /= scaling.spawn_rate;
+                        }
+                    }
+                }
+
+                self.fight_time += elapsed;
+                if let Some(ref mut movement) = self.movement {
+                    center = movement.update(elapsed, player);
+                }
+
+                for weapon in &mut self.weapons {
+                    weapon.update(elapsed, center, player, bullets);
+                }
+
+                let next = kind.phases.get(phase + 1);
+                if kind.timeout.is_some_and(|timeout| self.fight_time >= timeout) {
+                    self.machine.set(BossState::Leaving);
+                } else if next.is_some_and(|next| self.health <= next.threshold * self.max_health) {
+                    self.machine.set(BossState::Transition(phase + 1));
+                }
+            },
+
+            BossState::Leaving => {
+                self.velocity.x += LEAVE_ACCELERATION * elapsed;
+                center += self.velocity * elapsed;
+
+                if self.machine.time() >= LEAVE_DURATION {
+                    self.machine.set(BossState::Escaped);
+                }
+            },
+
+            BossState::Dying => {
+                if self.machine.entered() {
+                    bullets.clear(Owner::Enemy);
+                    self.velocity = Vec2::new(0.0, 20.0);
+                }
+
+                // An explosion every once in a while, and a big one at the end
+                let time = self.machine.time();
+                let explosions = |t: f64| (t / EXPLOSION_INTERVAL) as u32;
+                if explosions(time) != explosions(time - elapsed) {
+                    camera.add_trauma(0.3);
+                }
+
+                self.velocity.y += 20.0 * elapsed;
+                center += self.velocity * elapsed;
+
+                if time >= DEFEAT_DURATION {
+                    camera.add_trauma(1.0);
+                    self.machine.set(BossState::Defeated);
+                }
+            },
+
+            BossState::Defeated | BossState::Escaped => {},
+        }
+
+        self.pos = Rectangle::from_center(center, self.pos.w, self.pos.h);
+    }
+
+    /// The hull of the boss at its current position.
+    pub fn hitbox(&self) -> Hitbox {
+        self.kind.enemy.hitbox.at(self.pos)
+    }
+
+    /// Applies the damage of a bullet whose hitbox is `shape`. Returns
+    /// whether it hit the boss, and should therefore be destroyed.
+    pub fn hit(&mut self, shape: &Shape, damage: f64) -> bool {
+        if !self.is_alive() {
+            return false;
+        }
+
+        let offset = self.pos.origin();
+        let weak_point = self.kind.weak_points.iter()
+            .filter(|point| point.shape.translate(offset).overlaps(shape))
+            .map(|point| point.multiplier)
+            .fold(None, |max: Option<f64>, m| Some(max.map_or(m, |max| max.max(m))));
+
+        let multiplier = match weak_point {
+            Some(multiplier) => multiplier,
+            None if self.hitbox().overlaps_shape(shape) => self.kind.armor,
+            None => return false,
+        };
+
+        self.damage(damage * multiplier);
+        true
+    }
+
+    /// Takes `amount` of damage, if the boss is vulnerable at the moment.
+    pub fn damage(&mut self, amount: f64) {
+        if self.is_vulnerable() {
+            self.health = (self.health - amount).max(0.0);
+
+            if self.health == 0.0 {
+                self.machine.set(BossState::Dying);
+            }
+        }
+    }
+
+    /// How far along the entrance the boss is, between 0 and 1.
+    fn entrance_progress(&self) -> f64 {
+        if self.state() != BossState::Entering {
+            return 1.0;
+        }
+
+        let t = if self.kind.entrance > 0.0 { (self.machine.time() / self.kind.entrance).min(1.0) } else { 1.0 };
+        // Slow down when arriving
+        t * (2.0 - t)
+    }
+
+    /// Renders the boss with `sprite`, the one of its enemy kind.
+    pub fn render(&self, sprite: &Sprite, renderer: &mut Renderer, camera: &Camera) {
+        if self.is_finished() {
+            return;
+        }
+
+        // Blink while dying or changing phases
+        let blinking = match self.state() {
+            BossState::Dying | BossState::Transition(_) => (self.machine.time() * 12.0) as u32 % 2 == 1,
+            _ => false,
+        };
+
+        if !blinking {
+            sprite.render_world(renderer, camera, self.pos);
+        }
+
+        // The weak points glow while they can be damaged.
+        if self.is_vulnerable() {
+            let glow = ((self.fight_time * 4.0).sin() + 1.0) / 2.0;
+            renderer.set_draw_color(Color::RGB(255, (80.0 + 120.0 * glow) as u8, 60));
+
+            for point in &self.kind.weak_points {
+                let bounds = point.shape.translate(self.pos.origin()).bounding_box().scale(0.5);
+                gfx::fill_rect(renderer, camera.to_screen(bounds));
+            }
+        }
+    }
+
+    /// Renders the health bar at the top of the screen, whose size is
+    /// `screen`. It fills up during the entrance, and shows where the
+    /// phases change.
+    pub fn render_health_bar(&self, renderer: &mut Renderer, screen: (f64, f64)) {
+        if self.is_finished() {
+            return;
+        }
+
+        let (w, _) = screen;
+        let bar = Rectangle { x: w * 0.25, y: 48.0, w: w * 0.5, h: 10.0 };
+        let fill = (self.health / self.max_health) * self.entrance_progress();
+
+        renderer.set_draw_color(Color::RGB(60, 60, 60));
+        gfx::fill_rect(renderer, bar);
+
+        renderer.set_draw_color(Color::RGB(220, 40, 40));
+        gfx::fill_rect(renderer, Rectangle { w: bar.w * fill, ..bar });
+
+        renderer.set_draw_color(Color::RGB(255, 255, 255));
+        for phase in self.kind.phases.iter().skip(1) {
+            let x = bar.x + bar.w * phase.threshold;
+            gfx::fill_rect(renderer, Rectangle { x: x - 1.0, y: bar.y - 2.0, w: 2.0, h: bar.h + 4.0 });
+        }
+
+        gfx::draw_rect(renderer, bar);
+    }
+}
@@ -0,0 +1,224 @@
+use phi::camera::Camera;
+use phi::data::{Circle, Rectangle, Shape, Vec2};
+use phi::gfx;
+use phi::snapshot::{field, table, variant, variant_name, Snapshot};
+use phi::toml::Value;
+use sdl2::pixels::Color;
+use sdl2::render::Renderer;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Owner {
+    /// The index of the player who fired the bullet.
+    Player(usize),
+    Enemy,
+}
+
+impl Snapshot for Owner {
+    fn snapshot(&self) -> Value {
+        match *self {
+            Owner::Player(player) => variant("player", vec![("player", player.snapshot())]),
+            Owner::Enemy => variant("enemy", vec![]),
+        }
+    }
+
+    fn restore(value: &Value) -> Result<Owner, String> {
+        match &variant_name(value)?[..] {
+            "player" => Ok(Owner::Player(field(value, "player")?)),
+            "enemy" => Ok(Owner::Enemy),
+            other => Err(format!("unknown owner `{}`", other)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bullet {
+    /// The center of the bullet.
+    pub pos: Vec2,
+    /// Pixels traveled every second, in each direction.
+    pub vel: Vec2,
+    pub radius: f64,
+    pub damage: f64,
+    pub owner: Owner,
+}
+
+impl Bullet {
+    pub fn hitbox(&self) -> Shape {
+        Shape::Circle(Circle { x: self.pos.x, y: self.pos.y, r: self.radius })
+    }
+
+    fn render(&self, renderer: &mut Renderer, camera: &Camera) {
+        let (outer, inner) = match self.owner {
+            Owner::Player(0) => (Color::RGB(230, 230, 30), Color::RGB(255, 255, 200)),
+            Owner::Player(_) => (Color::RGB(60, 220, 120), Color::RGB(210, 255, 220)),
+            Owner::Enemy => (Color::RGB(255, 60, 140), Color::RGB(255, 220, 240)),
+        };
+
+        let rect = Rectangle::from_center(self.pos, self.radius * 2.0, self.radius * 2.0);
+        renderer.set_draw_color(outer);
+        gfx::fill_rect(renderer, camera.to_screen(rect));
+        renderer.set_draw_color(inner);
+        gfx::fill_rect(renderer, camera.to_screen(rect.scale(0.5)));
+    }
+}
+
+impl Snapshot for Bullet {
+    fn snapshot(&self) -> Value {
+        table(vec![
+            ("pos", self.pos.snapshot()),
+            ("vel", self.vel.snapshot()),
+            ("radius", self.radius.snapshot()),
+            ("damage", self.damage.snapshot()),
+            ("owner", self.owner.snapshot()),
+        ])
+    }
+
+    fn restore(value: &Value) -> Result<Bullet, String> {
+        Ok(Bullet {
+            pos: field(value, "pos")?,
+            vel: field(value, "vel")?,
+            radius: field(value, "radius")?,
+            damage: field(value, "damage")?,
+            owner: field(value, "owner")?,
+        })
+    }
+}
+
+/// Holds every bullet of the game, whoever shot it. The storage is allocated
+/// once, and the slots of destroyed bullets are reused by new ones, so that
+/// bullet-heavy patterns do not allocate on every frame.
+pub struct BulletPool {
+    slots: Vec<Option<Bullet>>,
+    /// The indices of the empty slots.
+    free: Vec<usize>,
+}
+
+impl BulletPool {
+    pub fn new(capacity: usize) -> BulletPool {
+        BulletPool {
+            slots: vec![None; capacity],
+            free: (0..capacity).rev().collect(),
+        }
+    }
+
+    /// Adds a bullet to the pool. Returns `false` if it is full, in which
+    /// case the bullet is simply not fired.
+    pub fn spawn(&mut self, bullet: Bullet) -> bool {
+        match self.free.pop() {
+            Some(i) => {
+                self.slots[i] = Some(bullet);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// The number of bullets currently alive.
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    /// Moves every bullet, and destroys those which left `bounds`.
+    pub fn update(&mut self, elapsed: f64, bounds: Rectangle) {
+        for i in 0..self.slots.len() {
+            if let Some(ref mut bullet) = self.slots[i] {
+                bullet.pos += bullet.vel * elapsed;
+            }
+
+            let outside = self.slots[i].is_some_and(|bullet| {
+                let p = bullet.pos;
+                p.x < bounds.x || p.y < bounds.y || p.x > bounds.x + bounds.w || p.y > bounds.y + bounds.h
+            });
+
+            if outside {
+                self.remove(i);
+            }
+        }
+    }
+
+    /// Calls `hit` on every bullet; those for which it returns `true` are
+    /// destroyed.
+    pub fn collide<F>(&mut self, mut hit: F)
+    where F: FnMut(&Bullet) -> bool {
+        for i in 0..self.slots.len() {
+            if self.slots[i].as_ref().is_some_and(&mut hit) {
+                self.remove(i);
+            }
+        }
+    }
+
+    /// Destroys every bullet fired by `owner`.
+    pub fn clear(&mut self, owner: Owner) {
+        self.collide(|bullet| bullet.owner == owner);
+    }
+
+    /// Iterates over the bullets currently alive.
+    pub fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Bullet> + 'a> {
+        Box::new(self.slots.iter().filter_map(|slot| slot.as_ref()))
+    }
+
+    pub fn render(&self, renderer: &mut Renderer, camera: &Camera) {
+        for bullet in self.iter() {
+            bullet.render(renderer, camera);
+        }
+    }
+
+    fn remove(&mut self, i: usize) {
+        if self.slots[i].take().is_some() {
+            self.free.push(i);
+        }
+    }
+}
+
+/// Only the slots in use are saved, along with the order in which the free
+/// ones are reused.
+impl Snapshot for BulletPool {
+    fn snapshot(&self) -> Value {
+        let bullets = self.slots.iter().enumerate()
+            .filter_map(|(slot, bullet)| bullet.map(|bullet| table(vec![
+                ("slot", slot.snapshot()),
+                ("bullet", bullet.snapshot()),
+            ])))
+            .collect();
+
+        table(vec![
+            ("capacity", self.slots.len().snapshot()),
+            ("bullets", Value::Array(bullets)),
+            ("free", self.free.snapshot()),
+        ])
+    }
+
+    fn restore(value: &Value) -> Result<BulletPool, String> {
+        let capacity: usize = field(value, "capacity")?;
+        let mut pool = BulletPool {
+            slots: vec![None; capacity],
+            free: field(value, "free")?,
+        };
+
+        let bullets: Vec<Value> = field(value, "bullets")?;
+        for bullet in &bullets {
+            let slot: usize = field(bullet, "slot")?;
+            if slot >= capacity {
+                return Err(format!("bullet slot {} out of bounds", slot));
+            }
+            if pool.slots[slot].is_some() {
+                return Err(format!("bullet slot {} used twice", slot));
+            }
+            pool.slots[slot] = Some(field(bullet, "bullet")?);
+        }
+
+        // Every empty slot must be free exactly once, or the next bullets
+        // would either share a slot or never use some of them.
+        let mut listed = vec![false; capacity];
+        for &slot in &pool.free {
+            if slot >= capacity || pool.slots[slot].is_some() || listed[slot] {
+                return Err("inconsistent free slots".to_string());
+            }
+            listed[slot] = true;
+        }
+        if pool.free.len() + bullets.len() != capacity {
+            return Err("inconsistent free slots".to_string());
+        }
+
+        Ok(pool)
+    }
+}
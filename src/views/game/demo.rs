@@ -0,0 +1,185 @@
+use phi::input::Input;
+use phi::snapshot::{field, Snapshot};
+use phi::toml::{self, Table, Value};
+use views::settings::Settings;
+
+/// The demo played by the attract mode of the main menu.
+pub const DEMO_PATH: &str = "assets/demo.toml";
+
+/// Where the debug hotkey saves recordings, relative to the working
+/// directory. They become the demo once moved to `DEMO_PATH`.
+pub const RECORDING_PATH: &str = "demo.toml";
+
+/// The inputs of the players during a game, frame by frame, along with what
+/// is needed to play it again exactly: the seed of the game, its settings
+/// and the time elapsed during every frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recording {
+    pub seed: u64,
+    pub settings: Settings,
+    pub players: usize,
+    /// Runs of identical frames: the seconds elapsed during each of them,
+    /// their number, and the input of every player.
+    frames: Vec<(f64, u32, Vec<Input>)>,
+}
+
+impl Recording {
+    pub fn new(seed: u64, settings: Settings, players: usize) -> Recording {
+        Recording {
+            seed: seed,
+            settings: settings,
+            players: players,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, elapsed: f64, inputs: &[Input]) {
+        if let Some(&mut (last_elapsed, ref mut count, ref last_inputs)) = self.frames.last_mut() {
+            if last_elapsed == elapsed && &last_inputs[..] == inputs {
+                *count += 1;
+                return;
+            }
+        }
+
+        self.frames.push((elapsed, 1, inputs.to_vec()));
+    }
+
+    /// The number of seconds recorded.
+    pub fn duration(&self) -> f64 {
+        self.frames.iter().map(|&(elapsed, count, _)| elapsed * count as f64).sum()
+    }
+
+    pub fn load(path: &str) -> Result<Recording, String> {
+        toml::load(path).and_then(|table| Recording::from_table(&table)).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        toml::save(path, &self.to_table())
+    }
+
+    /// Every frame is written as `[elapsed, count, inputs...]`, the inputs
+    /// being packed with `Input::to_bits`.
+    pub fn from_table(table: &Table) -> Result<Recording, String> {
+        let value = Value::Table(table.clone());
+        let players: usize = field(&value, "players")?;
+
+        let mut frames = Vec::new();
+        for frame in field::<Vec<Vec<f64>>>(&value, "frames")? {
+            if frame.len() != 2 + players {
+                return Err(format!("every frame must have the inputs of {} players", players));
+            }
+            let inputs = frame[2..].iter().map(|&bits| Input::from_bits(bits as u32)).collect();
+            frames.push((frame[0], frame[1] as u32, inputs));
+        }
+
+        Ok(Recording {
+            seed: field(&value, "seed")?,
+            settings: field(&value, "settings")?,
+            players: players,
+            frames: frames,
+        })
+    }
+
+    pub fn to_table(&self) -> Table {
+        let frames: Vec<Vec<f64>> = self.frames.iter().map(|&(elapsed, count, ref inputs)| {
+            let mut frame = vec![elapsed, count as f64];
+            frame.extend(inputs.iter().map(|input| input.to_bits() as f64));
+            frame
+        }).collect();
+
+        let mut table = Table::new();
+        table.insert("seed".to_string(), self.seed.snapshot());
+        table.insert("settings".to_string(), self.settings.snapshot());
+        table.insert("players".to_string(), self.players.snapshot());
+        table.insert("frames".to_string(), frames.snapshot());
+        table
+    }
+}
+
+/// Goes through the frames of a `Recording`.
+pub struct Playback {
+    recording: Recording,
+    /// The current run of frames, and the number of frames already played
+    /// in it.
+    run: usize,
+    played: u32,
+}
+
+impl Playback {
+    pub fn new(recording: Recording) -> Playback {
+        Playback {
+            recording: recording,
+            run: 0,
+            played: 0,
+        }
+    }
+
+    /// Returns the seconds elapsed during the next frame and the inputs of
+    /// the players, or `None` once the recording is over.
+    pub fn next(&mut self) -> Option<(f64, Vec<Input>)> {
+        while let Some(&(elapsed, count, ref inputs)) = self.recording.frames.get(self.run) {
+            if self.played < count {
+                self.played += 1;
+                return Some((elapsed, inputs.clone()));
+            }
+
+            self.run += 1;
+            self.played = 0;
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn demo_recordings() {
+        use phi::input::Input;
+        use phi::toml;
+        use views::game::demo::{Playback, Recording};
+        use views::settings::Settings;
+
+        let idle = Input::default();
+        let firing = Input { fire: true, up: true, ..Input::default() };
+        assert_eq!(Input::from_bits(firing.to_bits()), firing);
+        assert_eq!(idle.to_bits(), 0);
+
+        let mut recording = Recording::new(0xDEADBEEF12345678, Settings::default(), 1);
+        let frames = vec![(0.016, idle), (0.016, idle), (0.017, idle), (0.017, firing), (0.017, firing)];
+        for &(elapsed, input) in &frames {
+            recording.record(elapsed, &[input]);
+        }
+        assert_eq!(recording.frames.len(), 3);
+        assert!((recording.duration() - 0.083).abs() < 1e-9);
+
+        // Played back exactly as recorded, once read from a document
+        let text = toml::to_string(&recording.to_table()).unwrap();
+        let read = Recording::from_table(&toml::parse(&text).unwrap()).unwrap();
+        assert_eq!(read, recording);
+
+        let mut playback = Playback::new(read);
+        for &(elapsed, input) in &frames {
+            assert_eq!(playback.next(), Some((elapsed, vec![input])));
+        }
+        assert_eq!(playback.next(), None);
+
+        let wrong = "seed = \"1\"\nplayers = 2\nframes = [[0.016, 1, 0]]\n[settings]\n";
+        assert!(Recording::from_table(&toml::parse(wrong).unwrap()).is_err());
+    }
+    #[test]
+    fn shipped_demo() {
+        use views::game::demo::{Playback, Recording, DEMO_PATH};
+
+        // The attract mode plays the first stage for a while.
+        let demo = Recording::load(DEMO_PATH).unwrap();
+        assert_eq!(demo.players, 1);
+        assert!(demo.duration() > 20.0);
+
+        let mut playback = Playback::new(demo.clone());
+        while let Some((elapsed, inputs)) = playback.next() {
+            assert!(elapsed > 0.0);
+            assert_eq!(inputs.len(), demo.players);
+        }
+    }
+}
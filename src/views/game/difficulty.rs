@@ -0,0 +1,189 @@
+use phi::snapshot::{field, table, Snapshot};
+use phi::toml::Value;
+
+/// How hard the game is, as chosen in the main menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn all() -> [Difficulty; 3] {
+        [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard]
+    }
+
+    /// The name used in the settings and high score files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        Difficulty::all().iter().cloned().find(|difficulty| difficulty.name() == name)
+    }
+
+    pub fn scaling(self) -> Scaling {
+        match self {
+            Difficulty::Easy => Scaling { health: 0.7, bullet_speed: 0.75, spawn_rate: 0.8, lives: 5 },
+            Difficulty::Normal => Scaling { health: 1.0, bullet_speed: 1.0, spawn_rate: 1.0, lives: 3 },
+            Difficulty::Hard => Scaling { health: 1.4, bullet_speed: 1.25, spawn_rate: 1.3, lives: 2 },
+        }
+    }
+}
+
+impl Snapshot for Difficulty {
+    fn snapshot(&self) -> Value {
+        Value::String(self.name().to_string())
+    }
+
+    fn restore(value: &Value) -> Result<Difficulty, String> {
+        value.as_str().and_then(Difficulty::from_name).ok_or("unknown difficulty".to_string())
+    }
+}
+
+/// Multipliers applied to the values of the level files.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Scaling {
+    /// Multiplies the health of the enemies.
+    pub health: f64,
+    /// Multiplies the speed of the enemies' bullets.
+    pub bullet_speed: f64,
+    /// Multiplies how often enemies appear and shoot.
+    pub spawn_rate: f64,
+    /// The number of lives the players start with.
+    pub lives: u32,
+}
+
+impl Scaling {
+    /// Returns the scaling for the given `intensity` (see `Adaptive`), which
+    /// does not affect the number of lives.
+    pub fn with_intensity(self, intensity: f64) -> Scaling {
+        Scaling {
+            health: self.health * intensity,
+            bullet_speed: self.bullet_speed * intensity,
+            spawn_rate: self.spawn_rate * intensity,
+            lives: self.lives,
+        }
+    }
+}
+
+impl Snapshot for Scaling {
+    fn snapshot(&self) -> Value {
+        table(vec![
+            ("health", self.health.snapshot()),
+            ("bullet_speed", self.bullet_speed.snapshot()),
+            ("spawn_rate", self.spawn_rate.snapshot()),
+            ("lives", self.lives.snapshot()),
+        ])
+    }
+
+    fn restore(value: &Value) -> Result<Scaling, String> {
+        Ok(Scaling {
+            health: field(value, "health")?,
+            bullet_speed: field(value, "bullet_speed")?,
+            spawn_rate: field(value, "spawn_rate")?,
+            lives: field(value, "lives")?,
+        })
+    }
+}
+
+/// The lowest and highest intensities reached by the adaptive difficulty.
+const MIN_INTENSITY: f64 = 0.7;
+const MAX_INTENSITY: f64 = 1.3;
+
+/// Intensity gained every second by perfectly accurate players.
+const INTENSITY_RAMP: f64 = 0.01;
+
+/// Intensity lost whenever a player loses a life.
+const INTENSITY_PER_DEATH: f64 = 0.15;
+
+/// Adjusts the intensity of the game to how well the players are doing: it
+/// slowly rises while they survive, faster if they are accurate, and drops
+/// every time they lose a life.
+#[derive(Clone, Debug)]
+pub struct Adaptive {
+    intensity: f64,
+}
+
+impl Adaptive {
+    pub fn new() -> Adaptive {
+        Adaptive {
+            intensity: 1.0,
+        }
+    }
+
+    /// Multiplies the scaling of the difficulty, between `MIN_INTENSITY` and
+    /// `MAX_INTENSITY`.
+    pub fn intensity(&self) -> f64 {
+        self.intensity
+    }
+
+    /// `accuracy` is the fraction of the players' shots which hit something.
+    pub fn update(&mut self, elapsed: f64, accuracy: f64) {
+        self.adjust(INTENSITY_RAMP * (0.5 + accuracy) * elapsed);
+    }
+
+    pub fn on_death(&mut self) {
+        self.adjust(-INTENSITY_PER_DEATH);
+    }
+
+    fn adjust(&mut self, amount: f64) {
+        self.intensity = (self.intensity + amount).clamp(MIN_INTENSITY, MAX_INTENSITY);
+    }
+}
+
+impl Snapshot for Adaptive {
+    fn snapshot(&self) -> Value {
+        self.intensity.snapshot()
+    }
+
+    fn restore(value: &Value) -> Result<Adaptive, String> {
+        Ok(Adaptive { intensity: f64::restore(value)? })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn difficulty_scaling() {
+        use views::game::difficulty::{Adaptive, Difficulty};
+
+        for &difficulty in Difficulty::all().iter() {
+            assert_eq!(Difficulty::from_name(difficulty.name()), Some(difficulty));
+        }
+        assert_eq!(Difficulty::from_name("nightmare"), None);
+
+        let easy = Difficulty::Easy.scaling();
+        let hard = Difficulty::Hard.scaling();
+        assert!(easy.health < hard.health && easy.bullet_speed < hard.bullet_speed);
+        assert!(easy.lives > hard.lives);
+
+        let intense = hard.with_intensity(1.2);
+        assert_eq!(intense.lives, hard.lives);
+        assert!((intense.health - hard.health * 1.2).abs() < 1e-9);
+
+        // Accurate players see the intensity rise faster, and deaths bring
+        // it down, within bounds.
+        let mut accurate = Adaptive::new();
+        let mut sloppy = Adaptive::new();
+        accurate.update(10.0, 1.0);
+        sloppy.update(10.0, 0.0);
+        assert!(accurate.intensity() > sloppy.intensity());
+        assert!(sloppy.intensity() > 1.0);
+
+        accurate.on_death();
+        assert!(accurate.intensity() < 1.0);
+        for _ in 0..10 {
+            accurate.on_death();
+        }
+        assert_eq!(accurate.intensity(), 0.7);
+
+        accurate.update(1000.0, 1.0);
+        assert_eq!(accurate.intensity(), 1.3);
+    }
+}
@@ -0,0 +1,330 @@
+use phi::data::Vec2;
+use phi::snapshot::{field, table, variant, variant_name, Snapshot};
+use phi::toml::Value;
+use views::game::bullet::{Bullet, BulletPool, Owner};
+
+/// The shape of a volley of bullets. Angles are in degrees, with 0 pointing
+/// to the right and positive angles going clockwise (on the screen).
+#[derive(Clone, Debug, PartialEq)]
+pub enum BulletPattern {
+    /// `count` bullets fanned over `spread` degrees, centered on the player.
+    Aimed { count: u32, spread: f64 },
+
+    /// `count` bullets fanned over `spread` degrees, centered on `angle`.
+    Spread { count: u32, spread: f64, angle: f64 },
+
+    /// `count` bullets evenly distributed around the emitter.
+    Radial { count: u32 },
+
+    /// `arms` bullets evenly distributed around the emitter, whose directions
+    /// are turned by `step` degrees after every volley.
+    Spiral { arms: u32, step: f64 },
+
+    /// A spread whose direction turns continuously, by `rotation_speed`
+    /// degrees every second.
+    Rotating { count: u32, spread: f64, rotation_speed: f64 },
+}
+
+impl Snapshot for BulletPattern {
+    fn snapshot(&self) -> Value {
+        match *self {
+            BulletPattern::Aimed { count, spread } =>
+                variant("aimed", vec![("count", count.snapshot()), ("spread", spread.snapshot())]),
+            BulletPattern::Spread { count, spread, angle } =>
+                variant("spread", vec![("count", count.snapshot()), ("spread", spread.snapshot()),
+                                       ("angle", angle.snapshot())]),
+            BulletPattern::Radial { count } =>
+                variant("radial", vec![("count", count.snapshot())]),
+            BulletPattern::Spiral { arms, step } =>
+                variant("spiral", vec![("arms", arms.snapshot()), ("step", step.snapshot())]),
+            BulletPattern::Rotating { count, spread, rotation_speed } =>
+                variant("rotating", vec![("count", count.snapshot()), ("spread", spread.snapshot()),
+                                         ("rotation_speed", rotation_speed.snapshot())]),
+        }
+    }
+
+    fn restore(value: &Value) -> Result<BulletPattern, String> {
+        Ok(match &variant_name(value)?[..] {
+            "aimed" => BulletPattern::Aimed { count: field(value, "count")?, spread: field(value, "spread")? },
+            "spread" => BulletPattern::Spread {
+                count: field(value, "count")?,
+                spread: field(value, "spread")?,
+                angle: field(value, "angle")?,
+            },
+            "radial" => BulletPattern::Radial { count: field(value, "count")? },
+            "spiral" => BulletPattern::Spiral { arms: field(value, "arms")?, step: field(value, "step")? },
+            "rotating" => BulletPattern::Rotating {
+                count: field(value, "count")?,
+                spread: field(value, "spread")?,
+                rotation_speed: field(value, "rotation_speed")?,
+            },
+            other => return Err(format!("unknown pattern `{}`", other)),
+        })
+    }
+}
+
+/// Fires volleys of bullets following a `BulletPattern` at a regular
+/// interval.
+#[derive(Clone, Debug)]
+pub struct Emitter {
+    pub pattern: BulletPattern,
+    /// Pixels traveled by the bullets every second.
+    pub speed: f64,
+    /// Seconds between two volleys.
+    pub interval: f64,
+    pub radius: f64,
+    pub damage: f64,
+    pub owner: Owner,
+
+    /// Seconds until the next volley.
+    cooldown: f64,
+    /// The angle, in degrees, by which spirals and rotating patterns have
+    /// turned.
+    turned: f64,
+}
+
+impl Emitter {
+    /// Creates an emitter for enemies, whose first volley is fired after
+    /// `delay` seconds.
+    pub fn new(pattern: BulletPattern, speed: f64, interval: f64, delay: f64) -> Emitter {
+        Emitter {
+            pattern: pattern,
+            speed: speed,
+            interval: interval,
+            radius: 5.0,
+            damage: 1.0,
+            owner: Owner::Enemy,
+            cooldown: delay,
+            turned: 0.0,
+        }
+    }
+
+    /// Waits for `elapsed` seconds, and fires from `origin` into `pool` if
+    /// it is time to. `target` is the position aimed at by `Aimed` patterns.
+    /// Returns the number of volleys fired.
+    pub fn update(&mut self, elapsed: f64, origin: Vec2, target: Vec2, pool: &mut BulletPool) -> u32 {
+        if let BulletPattern::Rotating { rotation_speed, .. } = self.pattern {
+            self.turned = (self.turned + rotation_speed * elapsed) % 360.0;
+        }
+
+        self.cooldown -= elapsed;
+        let mut volleys = 0;
+
+        while self.cooldown <= 0.0 {
+            self.fire(origin, target, pool);
+            self.cooldown += self.interval.max(0.01);
+            volleys += 1;
+        }
+
+        volleys
+    }
+
+    /// Fires a single volley right away.
+    pub fn fire(&mut self, origin: Vec2, target: Vec2, pool: &mut BulletPool) {
+        for angle in self.angles(origin, target) {
+            pool.spawn(Bullet {
+                pos: origin,
+                vel: Vec2::from_angle(angle.to_radians()) * self.speed,
+                radius: self.radius,
+                damage: self.damage,
+                owner: self.owner,
+            });
+        }
+
+        if let BulletPattern::Spiral { step, .. } = self.pattern {
+            self.turned = (self.turned + step) % 360.0;
+        }
+    }
+
+    /// Returns the direction of every bullet of the next volley, in degrees.
+    fn angles(&self, origin: Vec2, target: Vec2) -> Vec<f64> {
+        match self.pattern {
+            BulletPattern::Aimed { count, spread } =>
+                fan(count, spread, (target - origin).angle().to_degrees()),
+
+            BulletPattern::Spread { count, spread, angle } =>
+                fan(count, spread, angle),
+
+            BulletPattern::Radial { count } =>
+                around(count, 0.0),
+
+            BulletPattern::Spiral { arms, .. } =>
+                around(arms, self.turned),
+
+            BulletPattern::Rotating { count, spread, .. } =>
+                fan(count, spread, self.turned),
+        }
+    }
+}
+
+impl Snapshot for Emitter {
+    fn snapshot(&self) -> Value {
+        table(vec![
+            ("pattern", self.pattern.snapshot()),
+            ("speed", self.speed.snapshot()),
+            ("interval", self.interval.snapshot()),
+            ("radius", self.radius.snapshot()),
+            ("damage", self.damage.snapshot()),
+            ("owner", self.owner.snapshot()),
+            ("cooldown", self.cooldown.snapshot()),
+            ("turned", self.turned.snapshot()),
+        ])
+    }
+
+    fn restore(value: &Value) -> Result<Emitter, String> {
+        Ok(Emitter {
+            pattern: field(value, "pattern")?,
+            speed: field(value, "speed")?,
+            interval: field(value, "interval")?,
+            radius: field(value, "radius")?,
+            damage: field(value, "damage")?,
+            owner: field(value, "owner")?,
+            cooldown: field(value, "cooldown")?,
+            turned: field(value, "turned")?,
+        })
+    }
+}
+
+/// `count` angles evenly spread over `spread` degrees, centered on `center`.
+fn fan(count: u32, spread: f64, center: f64) -> Vec<f64> {
+    match count {
+        0 => return Vec::new(),
+        1 => return vec![center],
+        _ => {},
+    }
+
+    let step = spread / (count - 1) as f64;
+    (0..count).map(|i| center - spread / 2.0 + step * i as f64).collect()
+}
+
+/// `count` angles evenly spread over a full turn, starting at `start`.
+fn around(count: u32, start: f64) -> Vec<f64> {
+    (0..count).map(|i| start + 360.0 * i as f64 / count as f64).collect()
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn emitter_patterns() {
+        use phi::data::{Rectangle, Vec2};
+        use views::game::bullet::{BulletPool, Owner};
+        use views::game::emitter::{BulletPattern, Emitter};
+
+        let origin = Vec2::new(100.0, 100.0);
+        let below = Vec2::new(100.0, 200.0);
+        let bounds = Rectangle { x: 0.0, y: 0.0, w: 500.0, h: 500.0 };
+
+        // Collects the directions of the bullets in the pool, in degrees.
+        let directions = |pool: &mut BulletPool| {
+            let mut angles = Vec::new();
+            pool.collide(|bullet| {
+                angles.push(bullet.vel.angle().to_degrees().round());
+                true
+            });
+            angles.sort_by(|a: &f64, b| a.partial_cmp(b).unwrap());
+            angles
+        };
+
+        let mut pool = BulletPool::new(16);
+
+        // Nothing is fired before the delay is over, then volleys are fired
+        // at the given interval.
+        let mut aimed = Emitter::new(BulletPattern::Aimed { count: 3, spread: 90.0 }, 100.0, 0.5, 1.0);
+        assert_eq!(aimed.update(0.9, origin, below, &mut pool), 0);
+        assert_eq!(aimed.update(0.2, origin, below, &mut pool), 1);
+        assert_eq!(pool.len(), 3);
+        assert_eq!(directions(&mut pool), vec![45.0, 90.0, 135.0]);
+        assert_eq!(aimed.update(1.0, origin, below, &mut pool), 2);
+        assert_eq!(pool.len(), 6);
+        pool.clear(Owner::Enemy);
+        assert_eq!(pool.len(), 0);
+
+        let mut radial = Emitter::new(BulletPattern::Radial { count: 4 }, 100.0, 1.0, 0.0);
+        radial.update(0.0, origin, below, &mut pool);
+        assert_eq!(directions(&mut pool), vec![-90.0, 0.0, 90.0, 180.0]);
+
+        let mut spiral = Emitter::new(BulletPattern::Spiral { arms: 2, step: 30.0 }, 100.0, 1.0, 0.0);
+        spiral.update(0.0, origin, below, &mut pool);
+        assert_eq!(directions(&mut pool), vec![0.0, 180.0]);
+        spiral.update(1.0, origin, below, &mut pool);
+        assert_eq!(directions(&mut pool), vec![-150.0, 30.0]);
+
+        let mut rotating = Emitter::new(BulletPattern::Rotating {
+            count: 2, spread: 20.0, rotation_speed: 90.0
+        }, 100.0, 1.0, 1.0);
+        rotating.update(1.0, origin, below, &mut pool);
+        assert_eq!(directions(&mut pool), vec![80.0, 100.0]);
+
+        // Empty volleys fire nothing.
+        let mut empty = Emitter::new(BulletPattern::Spread { count: 0, spread: 90.0, angle: 0.0 }, 100.0, 1.0, 0.0);
+        empty.update(0.0, origin, below, &mut pool);
+        assert_eq!(pool.len(), 0);
+
+        // The pool drops bullets once it is full, and reuses freed slots.
+        let mut burst = Emitter::new(BulletPattern::Spread { count: 10, spread: 90.0, angle: 0.0 }, 1000.0, 1.0, 0.0);
+        burst.update(0.0, origin, below, &mut pool);
+        burst.update(1.0, origin, below, &mut pool);
+        assert_eq!(pool.len(), 16);
+        pool.update(1.0, bounds);
+        assert_eq!(pool.len(), 0);
+        burst.update(1.0, origin, below, &mut pool);
+        assert_eq!(pool.len(), 10);
+    }
+
+    #[test]
+    fn emitter_snapshots() {
+        use phi::data::{Rectangle, Vec2};
+        use phi::snapshot::{field, table, Snapshot};
+        use phi::toml::{self, Value};
+        use views::game::bullet::{BulletPool, Owner};
+        use views::game::emitter::{BulletPattern, Emitter};
+
+        let origin = Vec2::new(100.0, 100.0);
+        let below = Vec2::new(100.0, 200.0);
+        let bounds = Rectangle { x: 0.0, y: 0.0, w: 500.0, h: 500.0 };
+
+        let mut pool = BulletPool::new(32);
+        let mut emitter = Emitter::new(BulletPattern::Rotating {
+            count: 3, spread: 40.0, rotation_speed: 37.0
+        }, 120.0, 0.3, 0.1);
+        emitter.update(1.0, origin, below, &mut pool);
+        pool.update(0.7, bounds);
+        pool.clear(Owner::Enemy);
+        emitter.update(0.45, origin, below, &mut pool);
+
+        // Written to a document, then started again from it
+        let snapshot = table(vec![("emitter", emitter.snapshot()), ("pool", pool.snapshot())]);
+        let text = toml::to_string(snapshot.as_table().unwrap()).unwrap();
+        let read = Value::Table(toml::parse(&text).unwrap());
+        let mut restored_emitter: Emitter = field(&read, "emitter").unwrap();
+        let mut restored_pool: BulletPool = field(&read, "pool").unwrap();
+
+        // Both go on exactly the same way.
+        for _ in 0..20 {
+            assert_eq!(emitter.update(0.13, origin, below, &mut pool),
+                       restored_emitter.update(0.13, origin, below, &mut restored_pool));
+            pool.update(0.13, bounds);
+            restored_pool.update(0.13, bounds);
+        }
+        assert!(pool.len() > 0);
+        assert_eq!(pool.snapshot(), restored_pool.snapshot());
+        assert_eq!(emitter.snapshot(), restored_emitter.snapshot());
+
+        // Slots which are both free and in use, free twice or used twice,
+        // and empty slots which are not free cannot be restored.
+        let restore = |free: &str, slots: &[usize]| {
+            let mut text = format!("capacity = 3\nfree = {}\n", free);
+            for slot in slots {
+                text += &format!("[[bullets]]\nslot = {}\n[bullets.bullet]\n\
+                                  pos = [0, 0]\nvel = [1, 0]\nradius = 1\ndamage = 1\n\
+                                  [bullets.bullet.owner]\ntype = \"enemy\"\n", slot);
+            }
+            BulletPool::restore(&Value::Table(toml::parse(&text).unwrap()))
+        };
+        assert!(restore("[2, 1]", &[0]).is_ok());
+        assert!(restore("[2, 0]", &[0]).is_err());
+        assert!(restore("[2, 2]", &[0]).is_err());
+        assert!(restore("[2]", &[0, 0]).is_err());
+        assert!(restore("[2]", &[0]).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use phi::data::Vec2;
+use phi::rng::Rng;
+use std::time::{SystemTime, UNIX_EPOCH};
+use views::game::movement::Pattern;
+
+/// The level providing the enemy kinds of the endless mode.
+pub const ENDLESS_PATH: &str = "assets/levels/endless.toml";
+
+/// Seconds between the first waves, which shrinks as the run goes on.
+const FIRST_GAP: f64 = 4.0;
+const MIN_GAP: f64 = 1.5;
+const GAP_STEP: f64 = 0.1;
+
+/// The health of the enemies grows by this fraction with every wave.
+const HEALTH_STEP: f64 = 0.08;
+
+/// Their speed too, up to `MAX_SPEED` times that of their kind.
+const SPEED_STEP: f64 = 0.02;
+const MAX_SPEED: f64 = 1.5;
+
+/// The number of waves after which a new kind of enemy may appear.
+const WAVES_PER_KIND: u32 = 4;
+
+const MIN_COUNT: usize = 3;
+const MAX_COUNT: usize = 10;
+
+/// Seeds are kept short so that players can easily share them.
+pub fn random_seed() -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() ^ d.subsec_nanos() as u64)
+        .unwrap_or(0);
+    Rng::new(now).next_u64() & 0xFFFF_FFFF
+}
+
+/// Formats a seed as 8 hexadecimal digits, as shown to the players.
+pub fn format_seed(seed: u64) -> String {
+    format!("{:08X}", seed)
+}
+
+/// Reads a seed typed by a player, ignoring the case of the digits.
+pub fn parse_seed(text: &str) -> Option<u64> {
+    let text = text.trim();
+    if text.is_empty() || text.len() > 8 {
+        return None;
+    }
+
+    u64::from_str_radix(text, 16).ok()
+}
+
+/// A wave decided by the `Generator`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WavePlan {
+    /// Seconds since the start of the run.
+    pub at: f64,
+    /// The index of the enemy kind, from the weakest to the strongest.
+    pub kind: usize,
+    pub count: usize,
+    /// Seconds between two enemies.
+    pub interval: f64,
+    pub position: Vec2,
+    pub pattern: Pattern,
+    /// Multiplies the speed of the enemy kind.
+    pub speed: f64,
+    /// Multiplies the health of the enemy kind.
+    pub health: f64,
+}
+
+/// Makes up the waves of the endless mode, harder and closer to each other
+/// as the run goes on. The same seed always gives the same waves.
+pub struct Generator {
+    rng: Rng,
+    /// The number of enemy kinds to choose from.
+    kinds: usize,
+    /// The number of waves generated so far.
+    waves: u32,
+    next_at: f64,
+}
+
+impl Generator {
+    pub fn new(seed: u64, kinds: usize) -> Generator {
+        Generator {
+            rng: Rng::new(seed),
+            kinds: kinds,
+            waves: 0,
+            next_at: 1.0,
+        }
+    }
+
+    /// The number of waves generated so far.
+    pub fn waves(&self) -> u32 {
+        self.waves
+    }
+
+    /// When the next wave starts.
+    pub fn next_at(&self) -> f64 {
+        self.next_at
+    }
+
+    pub fn next(&mut self) -> WavePlan {
+        let n = self.waves;
+        let stronger = (n / WAVES_PER_KIND) as usize;
+        let kind = self.pick(self.kinds.min(1 + stronger));
+
+        // Stronger kinds come in smaller groups.
+        let count = (MIN_COUNT + n as usize / 3).min(MAX_COUNT) / (1 + kind);
+        let y = self.rng.range(120.0, 480.0);
+
+        let pattern = match self.pick(5) {
+            0 => Pattern::default(),
+            1 => Pattern::Sine {
+                direction: Vec2::new(-1.0, 0.0),
+                amplitude: self.rng.range(40.0, 100.0),
+                frequency: self.rng.range(0.5, 1.2),
+            },
+            2 => Pattern::Dive {
+                delay: self.rng.range(1.0, 2.0),
+                dive_speed: self.rng.range(260.0, 360.0),
+            },
+            3 => Pattern::HoldAndRetreat {
+                target: Vec2::new(-self.rng.range(200.0, 320.0), 0.0),
+                hold: self.rng.range(2.0, 4.0),
+                retreat: Vec2::new(1.0, 0.0),
+            },
+            _ => {
+                // Swoops toward the other half of the screen.
+                let swoop = if y < 300.0 { 1.0 } else { -1.0 } * self.rng.range(150.0, 300.0);
+                Pattern::Bezier {
+                    controls: [Vec2::new(-700.0, 0.0), Vec2::new(-700.0, swoop), Vec2::new(0.0, swoop)],
+                }
+            },
+        };
+
+        let plan = WavePlan {
+            at: self.next_at,
+            kind: kind,
+            count: count.max(1),
+            interval: self.rng.range(0.3, 0.7),
+            position: Vec2::new(850.0, y),
+            pattern: pattern,
+            speed: (1.0 + SPEED_STEP * n as f64).min(MAX_SPEED),
+            health: 1.0 + HEALTH_STEP * n as f64,
+        };
+
+        self.waves += 1;
+        self.next_at += (FIRST_GAP - GAP_STEP * n as f64).max(MIN_GAP);
+        plan
+    }
+
+    /// Returns an index below `count`.
+    fn pick(&mut self, count: usize) -> usize {
+        ((self.rng.next_f64() * count as f64) as usize).min(count - 1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn endless_waves() {
+        use views::game::endless::{format_seed, parse_seed, Generator};
+
+        assert_eq!(format_seed(0x1A2B), "00001A2B");
+        assert_eq!(parse_seed("00001a2b"), Some(0x1A2B));
+        assert_eq!(parse_seed(" 1A2B "), Some(0x1A2B));
+        assert_eq!(parse_seed(""), None);
+        assert_eq!(parse_seed("XYZ"), None);
+        assert_eq!(parse_seed("123456789"), None);
+
+        // A seed always gives the same run.
+        let run = |seed| {
+            let mut generator = Generator::new(seed, 3);
+            (0..40).map(|_| generator.next()).collect::<Vec<_>>()
+        };
+        assert_eq!(run(42), run(42));
+        assert!(run(42) != run(43));
+
+        // The waves get harder, closer to each other, and use stronger kinds
+        // of enemies as the run goes on.
+        let waves = run(42);
+        assert!(waves[..4].iter().all(|wave| wave.kind == 0));
+        assert!(waves.iter().all(|wave| wave.kind < 3 && wave.count >= 1));
+        assert!(waves.windows(2).all(|pair| pair[1].health > pair[0].health && pair[1].at > pair[0].at));
+        assert!(waves[39].at - waves[38].at < waves[1].at - waves[0].at);
+    }
+}
@@ -0,0 +1,162 @@
+use phi::camera::Camera;
+use phi::data::{Capsule, Hitbox, PixelMask, Rectangle, Shape, Vec2};
+use phi::gfx::Sprite;
+use phi::snapshot::{field, table, Snapshot};
+use phi::toml::Value;
+use phi::tween::{Easing, Tween};
+use sdl2::render::Renderer;
+use std::rc::Rc;
+use views::game::bullet::BulletPool;
+use views::game::difficulty::Scaling;
+use views::game::emitter::Emitter;
+use views::game::movement::Movement;
+
+/// Seconds taken by enemies to grow to their full size once they spawn.
+const ENTRANCE_DURATION: f64 = 0.4;
+
+/// Seconds between two points of a predicted path.
+const PATH_STEP: f64 = 0.1;
+
+/// The properties shared by every enemy of a given type, as described in the
+/// level files. Its sprite is kept apart, by the `LevelAssets`.
+pub struct EnemyKind {
+    pub name: String,
+    /// The dimensions of the enemy on the screen.
+    pub size: (f64, f64),
+    pub hitbox: Hitbox,
+    /// The opaque pixels of the sprite, if they are known.
+    pub mask: Option<PixelMask>,
+    pub health: f64,
+    /// Pixels traveled every second.
+    pub speed: f64,
+    pub score: u32,
+    /// The weapon every enemy of this kind is equipped with, if any.
+    pub weapon: Option<Emitter>,
+    /// The probability, between 0 and 1, of dropping a pickup when destroyed.
+    pub drop: f64,
+}
+
+impl EnemyKind {
+    /// Creates a kind whose sprite is `size` pixels large, and is rendered
+    /// `scale` times larger.
+    pub fn new(name: &str, size: (f64, f64), scale: f64, health: f64, speed: f64, score: u32,
+               weapon: Option<Emitter>) -> EnemyKind {
+        let (w, h) = (size.0 * scale, size.1 * scale);
+
+        EnemyKind {
+            name: name.to_string(),
+            size: (w, h),
+            hitbox: Hitbox::new(vec![
+                Shape::Capsule(Capsule { x1: w * 0.15, y1: h * 0.5, x2: w * 0.85, y2: h * 0.5, r: h * 0.25 }),
+            ]),
+            mask: None,
+            health: health,
+            speed: speed,
+            score: score,
+            weapon: weapon,
+            drop: 0.0,
+        }
+    }
+}
+
+pub struct Enemy {
+    pub kind: Rc<EnemyKind>,
+    pub pos: Rectangle,
+    pub health: f64,
+    /// The index of the last player who damaged this enemy, who gets the
+    /// points if it is destroyed.
+    pub last_hit: Option<usize>,
+    movement: Movement,
+    weapon: Option<Emitter>,
+    /// The scale at which the enemy is rendered, while it enters.
+    entrance: Tween<f64>,
+}
+
+impl Enemy {
+    /// Creates an enemy whose center starts at the origin of `movement`.
+    pub fn new(kind: Rc<EnemyKind>, movement: Movement) -> Enemy {
+        let (w, h) = kind.size;
+
+        Enemy {
+            pos: Rectangle::from_center(movement.pos(), w, h),
+            health: kind.health,
+            last_hit: None,
+            weapon: kind.weapon.clone(),
+            kind: kind,
+            movement: movement,
+            entrance: Tween::new(0.0, 1.0, ENTRANCE_DURATION, Easing::BackOut),
+        }
+    }
+
+    /// `player` is the center of the player's ship, which some movement
+    /// and bullet patterns aim for. Enemies only shoot once they are inside
+    /// of the `visible` region.
+    pub fn update(&mut self, elapsed: f64, player: Vec2, visible: Rectangle, bullets: &mut BulletPool) {
+        let center = self.movement.update(elapsed, player);
+        self.pos = Rectangle::from_center(center, self.pos.w, self.pos.h);
+        self.entrance.update(elapsed);
+
+        if let Some(ref mut weapon) = self.weapon {
+            if visible.contains(self.pos) {
+                weapon.update(elapsed, center, player, bullets);
+            }
+        }
+    }
+
+    /// Only the name of the kind is saved, to be looked up in the level when
+    /// the enemy is restored.
+    pub fn snapshot(&self) -> Value {
+        table(vec![
+            ("kind", self.kind.name.snapshot()),
+            ("pos", self.pos.snapshot()),
+            ("health", self.health.snapshot()),
+            ("last_hit", self.last_hit.snapshot()),
+            ("movement", self.movement.snapshot()),
+            ("weapon", self.weapon.snapshot()),
+        ])
+    }
+
+    pub fn restore(value: &Value, kind: Rc<EnemyKind>) -> Result<Enemy, String> {
+        Ok(Enemy {
+            kind: kind,
+            pos: field(value, "pos")?,
+            health: field(value, "health")?,
+            last_hit: field(value, "last_hit")?,
+            movement: field(value, "movement")?,
+            weapon: field(value, "weapon")?,
+            entrance: Tween::idle(1.0, ENTRANCE_DURATION, Easing::BackOut),
+        })
+    }
+
+    /// Adapts the enemy to the difficulty. Must be called once, when it
+    /// spawns.
+    pub fn scale(&mut self, scaling: Scaling) {
+        self.health *= scaling.health;
+
+        if let Some(ref mut weapon) = self.weapon {
+            weapon.speed *= scaling.bullet_speed;
+            weapon.interval /= scaling.spawn_rate;
+        }
+    }
+
+    pub fn hitbox(&self) -> Hitbox {
+        self.kind.hitbox.at(self.pos)
+    }
+
+    /// Where the center of the enemy will be during the next `duration`
+    /// seconds, if `player` stays where it is.
+    pub fn path(&self, duration: f64, player: Vec2) -> Vec<Vec2> {
+        self.movement.predict(duration, PATH_STEP, player)
+    }
+
+    /// The velocity of the enemy, in pixels per second, if `player` stays
+    /// where it is.
+    pub fn velocity(&self, player: Vec2) -> Vec2 {
+        self.movement.velocity(player)
+    }
+
+    /// Renders the enemy with `sprite`, the one of its kind.
+    pub fn render(&self, sprite: &Sprite, renderer: &mut Renderer, camera: &Camera) {
+        sprite.render_world(renderer, camera, self.pos.scale(self.entrance.value()));
+    }
+}
@@ -0,0 +1,85 @@
+use phi::Phi;
+use phi::data::Rectangle;
+use phi::gfx::Sprite;
+use phi::tween::{Animation, Easing, Tween};
+use sdl2::pixels::Color;
+use std::collections::HashMap;
+
+const FONT: &str = "assets/belligerent.ttf";
+
+/// How much bigger elements get when they pop, and the seconds they take to
+/// shrink back.
+const POP_SCALE: f64 = 1.4;
+const POP_DURATION: f64 = 0.35;
+
+/// Where an element of the HUD is anchored on the screen.
+#[derive(Clone, Copy)]
+pub enum Anchor {
+    TopLeft,
+    /// Centered horizontally.
+    Top,
+    TopRight,
+    Center,
+}
+
+/// The heads-up display: text rendered on top of the scene, unaffected by the
+/// camera. Rendering text is slow, so the sprite of every element is kept
+/// until its text changes.
+pub struct Hud {
+    cache: HashMap<String, (String, Sprite)>,
+    /// The scale of the elements which are popping.
+    pops: HashMap<String, Tween<f64>>,
+}
+
+impl Hud {
+    pub fn new() -> Hud {
+        Hud {
+            cache: HashMap::new(),
+            pops: HashMap::new(),
+        }
+    }
+
+    /// Advances the animations of the elements.
+    pub fn update(&mut self, elapsed: f64) {
+        for pop in self.pops.values_mut() {
+            pop.advance(elapsed);
+        }
+        self.pops.retain(|_, pop| !pop.is_finished());
+    }
+
+    /// Returns whether the element `id` was already rendered, with another
+    /// text than `text`.
+    pub fn changed(&self, id: &str, text: &str) -> bool {
+        self.cache.get(id).is_some_and(|(cached, _)| cached != text)
+    }
+
+    /// Briefly enlarges the element `id`, to draw attention to it.
+    pub fn pop(&mut self, id: &str) {
+        self.pops.insert(id.to_string(), Tween::new(POP_SCALE, 1.0, POP_DURATION, Easing::BackOut));
+    }
+
+    /// Renders `text` at the `anchor`, `line` lines away from it. `id` must
+    /// uniquely identify the element, so that its sprite can be reused.
+    pub fn text(&mut self, phi: &mut Phi, id: &str, text: &str, size: u16, anchor: Anchor, line: f64) {
+        let stale = self.cache.get(id).is_none_or(|(cached, _)| cached != text);
+        if stale {
+            let sprite = phi.ttf_str_sprite(text, FONT, size, Color::RGB(255, 255, 255)).unwrap();
+            self.cache.insert(id.to_string(), (text.to_string(), sprite));
+        }
+
+        let sprite = &self.cache[id].1;
+        let (w, h) = sprite.size();
+        let (win_w, win_h) = phi.output_size();
+        let margin = 12.0;
+
+        let (x, y) = match anchor {
+            Anchor::TopLeft => (margin, margin),
+            Anchor::Top => ((win_w - w) / 2.0, margin),
+            Anchor::TopRight => (win_w - w - margin, margin),
+            Anchor::Center => ((win_w - w) / 2.0, (win_h - h) / 2.0),
+        };
+
+        let scale = self.pops.get(id).map_or(1.0, Tween::value);
+        sprite.render(&mut phi.renderer, Rectangle { x: x, y: y + line * h * 1.2, w: w, h: h }.scale(scale));
+    }
+}
@@ -0,0 +1,1033 @@
+use phi::Phi;
+use phi::data::{Capsule, Circle, Hitbox, PixelMask, Polygon, Rectangle, Shape, Vec2};
+use phi::gfx::{ParallaxSprite, Sprite};
+use phi::snapshot::{field, table, Snapshot};
+use phi::toml::{self, Table, Value};
+use sdl2::pixels::Color;
+use std::collections::HashMap;
+use std::rc::Rc;
+use views::game::boss::{BossKind, BossState, Phase, WeakPoint};
+use views::game::emitter::{BulletPattern, Emitter};
+use views::game::endless::{Generator, WavePlan};
+use views::game::enemy::{Enemy, EnemyKind};
+use views::game::movement::{Movement, Pattern};
+
+/// A group of enemies of the same kind, spawned one after the other and
+/// sharing the same movement pattern.
+struct Wave {
+    /// Seconds since the start of the level.
+    at: f64,
+    /// The index of the enemy kind in the level.
+    kind: usize,
+    /// Seconds between two spawns.
+    interval: f64,
+    position: Vec2,
+    /// Where each enemy appears, relative to `position`.
+    slots: Vec<Vec2>,
+    pattern: Pattern,
+    /// Overrides the speed of the enemy kind.
+    speed: Option<f64>,
+    /// Multiplies the health of the enemy kind.
+    health: f64,
+}
+
+enum BossTrigger {
+    /// Seconds since the start of the level.
+    At(f64),
+    /// Once every wave was spawned and destroyed (or has left the screen).
+    AfterWaves,
+}
+
+/// A boss, as described by the level file.
+struct BossSpawn {
+    trigger: BossTrigger,
+    /// The index of the enemy kind giving the boss its sprite and hull.
+    kind: usize,
+    position: Vec2,
+    arena: Vec2,
+    entrance: f64,
+    timeout: Option<f64>,
+    armor: f64,
+    phases: Vec<Phase>,
+    /// Relative to the center of the boss.
+    weak_points: Vec<WeakPoint>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EndCondition {
+    /// Every wave (and the boss, if any) was spawned, and no enemy is left.
+    WavesCleared,
+    /// The player survived for the given number of seconds.
+    Time(f64),
+    /// The boss was destroyed.
+    BossDefeated,
+    /// Never, as in the endless mode.
+    Never,
+}
+
+/// An enemy kind, as described by the level file.
+struct Kind {
+    name: String,
+    sprite: String,
+    /// The region of the sprite to use, if not all of it.
+    frame: Option<Rectangle>,
+    flip: bool,
+    tint: Option<Color>,
+    scale: f64,
+    health: f64,
+    speed: f64,
+    score: u32,
+    weapon: Option<Emitter>,
+    drop: f64,
+    /// Replaces the default hitbox, already scaled.
+    hitbox: Option<Hitbox>,
+}
+
+/// A layer of the background, as described by the level file.
+struct Layer {
+    sprite: String,
+    speed: f64,
+    tint: Option<Color>,
+}
+
+/// A level, as described by a data file in `assets/levels`. See `1.toml` for
+/// the documentation of the format.
+///
+/// Nothing is loaded besides the file itself; the sprites are loaded by
+/// `LevelAssets`.
+pub struct Level {
+    pub name: String,
+    pub end: EndCondition,
+    /// The back, middle and front layers of the background.
+    layers: [Layer; 3],
+    /// Every enemy kind of the level, from the one worth the least points
+    /// to the one worth the most.
+    kinds: Vec<Kind>,
+    waves: Vec<Wave>,
+    boss: Option<BossSpawn>,
+}
+
+impl Level {
+    pub fn load(path: &str) -> Result<Level, String> {
+        Level::from_table(&toml::load(path)?)
+            .map_err(|e| format!("{}: {}", path, e))
+    }
+
+    fn from_table(doc: &Table) -> Result<Level, String> {
+        let mut kinds = Vec::new();
+        if let Some(enemies) = doc.get("enemies") {
+            let enemies = enemies.as_table().ok_or("`enemies` must be a table")?;
+            for (name, kind) in enemies {
+                let kind = kind.as_table().ok_or(format!("`enemies.{}` must be a table", name))?;
+                kinds.push(enemy_kind(name, kind).map_err(|e| format!("enemies.{}: {}", name, e))?);
+            }
+        }
+        kinds.sort_by_key(|kind| kind.score);
+
+        let find_kind = |table: &Table| -> Result<usize, String> {
+            let name = string(table, "enemy")?;
+            kinds.iter().position(|kind| kind.name == name).ok_or(format!("unknown enemy `{}`", name))
+        };
+
+        let mut waves = Vec::new();
+        if let Some(list) = doc.get("waves") {
+            let list = list.as_array().ok_or("`waves` must be an array of tables")?;
+            for (i, wave) in list.iter().enumerate() {
+                let wave = wave.as_table().ok_or("`waves` must be an array of tables")?;
+                let parse_wave = || -> Result<Wave, String> {
+                    // Either an explicit formation, or enemies lined up
+                    let slots = match wave.get("formation") {
+                        Some(_) => points(wave, "formation")?,
+                        None => {
+                            let count = number(wave, "count", Some(1.0))? as usize;
+                            let offset = vec2(wave, "offset", Some(Vec2::zero()))?;
+                            (0..count).map(|i| offset * i as f64).collect()
+                        },
+                    };
+
+                    let (pattern, speed) = movement(wave)?;
+
+                    Ok(Wave {
+                        at: number(wave, "at", None)?,
+                        kind: find_kind(wave)?,
+                        interval: number(wave, "interval", Some(0.0))?,
+                        position: vec2(wave, "position", None)?,
+                        slots: slots,
+                        pattern: pattern,
+                        speed: speed,
+                        health: 1.0,
+                    })
+                };
+                waves.push(parse_wave().map_err(|e| format!("waves[{}]: {}", i, e))?);
+            }
+        }
+
+        let boss = match doc.get("boss") {
+            None => None,
+            Some(boss) => {
+                let boss = boss.as_table().ok_or("`boss` must be a table")?;
+                let parse_boss = || -> Result<BossSpawn, String> {
+                    let kind = find_kind(boss)?;
+                    boss_spawn(kind, &kinds[kind], boss)
+                };
+                Some(parse_boss().map_err(|e| format!("boss: {}", e))?)
+            },
+        };
+
+        let end = if doc.contains_key("end") { string(doc, "end")? } else { "waves".to_string() };
+        let end = match &end[..] {
+            "waves" => EndCondition::WavesCleared,
+            "time" => EndCondition::Time(number(doc, "duration", None)?),
+            // The level would never end if the boss escaped.
+            "boss" if boss.as_ref().is_some_and(|boss| boss.timeout.is_some()) =>
+                return Err("a boss with a `timeout` may escape, so `end` must be \"waves\"".to_string()),
+            "boss" if boss.is_some() => EndCondition::BossDefeated,
+            "boss" => return Err("`end = \"boss\"` requires a `[boss]` section".to_string()),
+            "endless" => EndCondition::Never,
+            other => return Err(format!("unknown end condition `{}`", other)),
+        };
+
+        let background = match doc.get("background") {
+            None => Table::new(),
+            Some(background) => background.as_table().ok_or("`background` must be a table")?.clone(),
+        };
+        let layers = [
+            layer(&background, "back", "assets/starBG.png", 20.0)?,
+            layer(&background, "middle", "assets/starMG.png", 40.0)?,
+            layer(&background, "front", "assets/starFG.png", 80.0)?,
+        ];
+
+        Ok(Level {
+            name: string(doc, "name")?,
+            end: end,
+            layers: layers,
+            kinds: kinds,
+            waves: waves,
+            boss: boss,
+        })
+    }
+}
+
+/// The layers of stars scrolling behind the action, and in front of it.
+pub struct Background {
+    pub back: ParallaxSprite,
+    pub middle: ParallaxSprite,
+    pub front: ParallaxSprite,
+}
+
+impl Background {
+    /// How far each layer scrolled, from the back to the front.
+    pub fn scrolls(&self) -> Vec<f64> {
+        vec![self.back.scroll(), self.middle.scroll(), self.front.scroll()]
+    }
+
+    pub fn set_scrolls(&mut self, scrolls: &[f64]) -> Result<(), String> {
+        if scrolls.len() != 3 {
+            return Err("the background has 3 layers".to_string());
+        }
+        self.back.set_scroll(scrolls[0]);
+        self.middle.set_scroll(scrolls[1]);
+        self.front.set_scroll(scrolls[2]);
+        Ok(())
+    }
+}
+
+/// The sprites of a level: its background, and the look of its enemies.
+pub struct LevelAssets {
+    pub background: Background,
+    /// The sprites of the enemy kinds, by name, as they are rendered.
+    sprites: HashMap<String, Sprite>,
+}
+
+impl LevelAssets {
+    pub fn load(phi: &mut Phi, level: &Level) -> Result<LevelAssets, String> {
+        let mut layers = Vec::new();
+        for (layer, name) in level.layers.iter().zip(["back", "middle", "front"].iter()) {
+            let mut sprite = ParallaxSprite::load(&phi.renderer, &layer.sprite, layer.speed)
+                .map_err(|e| format!("background.{}: {}", name, e))?;
+            if let Some(tint) = layer.tint {
+                sprite = sprite.tinted(tint);
+            }
+            layers.push(sprite);
+        }
+
+        let mut sprites = HashMap::new();
+        for kind in &level.kinds {
+            let sprite = load_sprite(phi, kind).map_err(|e| format!("enemies.{}: {}", kind.name, e))?;
+            sprites.insert(kind.name.clone(), sprite);
+        }
+
+        let mut layers = layers.into_iter();
+        Ok(LevelAssets {
+            background: Background {
+                back: layers.next().unwrap(),
+                middle: layers.next().unwrap(),
+                front: layers.next().unwrap(),
+            },
+            sprites: sprites,
+        })
+    }
+
+    /// Creates the enemy kinds of `level`, which are as large as their
+    /// sprites and collide with their opaque pixels.
+    pub fn kinds(&self, level: &Level) -> LevelKinds {
+        LevelKinds::new(level, |name| {
+            let sprite = &self.sprites[name];
+            (sprite.size(), sprite.mask().cloned())
+        })
+    }
+
+    /// Returns the sprite the enemies of `kind` are rendered with.
+    pub fn sprite(&self, kind: &EnemyKind) -> &Sprite {
+        &self.sprites[&kind.name]
+    }
+}
+
+/// The enemy kinds of a level, and its boss.
+pub struct LevelKinds {
+    /// In the same order as the kinds of the level.
+    kinds: Vec<Rc<EnemyKind>>,
+    boss: Option<Rc<BossKind>>,
+}
+
+impl LevelKinds {
+    /// Creates the kinds of `level`. `shape` returns the size and the opaque
+    /// pixels, if known, of the sprite of the kind it is given the name of.
+    pub fn new<F>(level: &Level, mut shape: F) -> LevelKinds
+        where F: FnMut(&str) -> ((f64, f64), Option<PixelMask>)
+    {
+        let kinds: Vec<Rc<EnemyKind>> = level.kinds.iter().map(|kind| {
+            let (size, mask) = shape(&kind.name);
+            Rc::new(new_kind(kind, size, mask))
+        }).collect();
+
+        let boss = level.boss.as_ref().map(|spawn| {
+            let enemy = kinds[spawn.kind].clone();
+            let (w, h) = enemy.size;
+
+            // Weak points are relative to the center of the boss in the
+            // file, but to its top-left corner in hitboxes.
+            let weak_points = spawn.weak_points.iter().map(|point| WeakPoint {
+                shape: point.shape.translate(Vec2::new(w / 2.0, h / 2.0)),
+                multiplier: point.multiplier,
+            }).collect();
+
+            Rc::new(BossKind {
+                enemy: enemy,
+                position: spawn.position,
+                arena: spawn.arena,
+                entrance: spawn.entrance,
+                timeout: spawn.timeout,
+                armor: spawn.armor,
+                phases: spawn.phases.clone(),
+                weak_points: weak_points,
+            })
+        });
+
+        LevelKinds {
+            kinds: kinds,
+            boss: boss,
+        }
+    }
+
+    /// Returns the enemy kind called `name`.
+    pub fn kind(&self, name: &str) -> Option<Rc<EnemyKind>> {
+        self.kinds.iter().find(|kind| kind.name == name).cloned()
+    }
+
+    /// The names of every enemy kind of the level.
+    pub fn kind_names(&self) -> Vec<&str> {
+        self.kinds.iter().map(|kind| kind.name.as_str()).collect()
+    }
+
+    pub fn boss_kind(&self) -> Option<Rc<BossKind>> {
+        self.boss.clone()
+    }
+
+    /// Creates an enemy spawned by the `LevelRunner`.
+    pub fn enemy(&self, spawn: EnemySpawn) -> Enemy {
+        let mut enemy = Enemy::new(self.kinds[spawn.kind].clone(), spawn.movement);
+        enemy.health *= spawn.health;
+        enemy
+    }
+}
+
+/// Loads the sprite of the enemies of `kind`, as they are rendered.
+fn load_sprite(phi: &mut Phi, kind: &Kind) -> Result<Sprite, String> {
+    let mut sprite = Sprite::load_with_mask(&phi.renderer, &kind.sprite)?;
+
+    if let Some(frame) = kind.frame {
+        sprite = sprite.region(frame).ok_or("`frame` is outside of the sprite")?;
+    }
+
+    if kind.flip {
+        sprite = sprite.flipped();
+    }
+
+    if let Some(tint) = kind.tint {
+        sprite = sprite.tinted(tint);
+    }
+
+    Ok(sprite)
+}
+
+/// Creates the enemy kind described by `kind`, whose sprite is `size`
+/// pixels large.
+fn new_kind(kind: &Kind, size: (f64, f64), mask: Option<PixelMask>) -> EnemyKind {
+    let mut loaded = EnemyKind::new(&kind.name, size, kind.scale, kind.health, kind.speed, kind.score,
+                                    kind.weapon.clone());
+    loaded.mask = mask;
+    loaded.drop = kind.drop;
+    if let Some(ref hitbox) = kind.hitbox {
+        loaded.hitbox = hitbox.clone();
+    }
+
+    loaded
+}
+
+/// Reads the optional layer `name` of the background, which defaults to the
+/// `sprite` scrolling at `speed`.
+fn layer(background: &Table, name: &str, sprite: &str, speed: f64) -> Result<Layer, String> {
+    let table = match background.get(name) {
+        None => Table::new(),
+        Some(table) => table.as_table().ok_or(format!("`background.{}` must be a table", name))?.clone(),
+    };
+
+    let parse = || -> Result<Layer, String> {
+        Ok(Layer {
+            sprite: match table.get("sprite") {
+                None => sprite.to_string(),
+                Some(_) => string(&table, "sprite")?,
+            },
+            speed: number(&table, "speed", Some(speed))?,
+            tint: tint(&table)?,
+        })
+    };
+
+    parse().map_err(|e| format!("background.{}: {}", name, e))
+}
+
+fn enemy_kind(name: &str, table: &Table) -> Result<Kind, String> {
+    let frame = match table.get("frame") {
+        None => None,
+        Some(frame) => {
+            let frame = numbers(frame, 4).ok_or("`frame` must be an array of 4 numbers")?;
+            Some(Rectangle { x: frame[0], y: frame[1], w: frame[2], h: frame[3] })
+        },
+    };
+
+    let scale = number(table, "scale", Some(1.0))?;
+    Ok(Kind {
+        name: name.to_string(),
+        sprite: string(table, "sprite")?,
+        frame: frame,
+        flip: boolean(table, "flip", false)?,
+        tint: tint(table)?,
+        scale: scale,
+        health: number(table, "health", Some(1.0))?,
+        speed: number(table, "speed", None)?,
+        score: number(table, "score", Some(0.0))? as u32,
+        weapon: weapon(table)?,
+        drop: number(table, "drop", Some(0.0))?,
+        hitbox: hitbox(table, scale)?,
+    })
+}
+
+/// Reads the optional `tint` of a sprite.
+fn tint(table: &Table) -> Result<Option<Color>, String> {
+    match table.get("tint") {
+        None => Ok(None),
+        Some(tint) => {
+            let tint = numbers(tint, 3).ok_or("`tint` must be an array of 3 numbers")?;
+            Ok(Some(Color::RGB(tint[0] as u8, tint[1] as u8, tint[2] as u8)))
+        },
+    }
+}
+
+/// Reads the optional `hitbox` of an enemy kind, whose coordinates are in
+/// pixels of its frame, and scales it like the enemy.
+fn hitbox(table: &Table, scale: f64) -> Result<Option<Hitbox>, String> {
+    let list = match table.get("hitbox") {
+        None => return Ok(None),
+        Some(list) => list.as_array().ok_or("`hitbox` must be an array of tables")?,
+    };
+
+    let mut shapes = Vec::new();
+    for (i, shape) in list.iter().enumerate() {
+        let shape = shape.as_table().ok_or("`hitbox` must be an array of tables")?;
+        let parse_shape = || -> Result<Shape, String> {
+            Ok(match &string(shape, "shape")?[..] {
+                "rect" => {
+                    let rect = shape.get("rect").and_then(|rect| numbers(rect, 4))
+                        .ok_or("`rect` must be an array of 4 numbers")?;
+                    Shape::Rect(Rectangle { x: rect[0] * scale, y: rect[1] * scale, w: rect[2] * scale, h: rect[3] * scale })
+                },
+                "circle" => {
+                    let center = vec2(shape, "center", None)? * scale;
+                    Shape::Circle(Circle { x: center.x, y: center.y, r: number(shape, "radius", None)? * scale })
+                },
+                "capsule" => {
+                    let (from, to) = (vec2(shape, "from", None)? * scale, vec2(shape, "to", None)? * scale);
+                    Shape::Capsule(Capsule { x1: from.x, y1: from.y, x2: to.x, y2: to.y, r: number(shape, "radius", None)? * scale })
+                },
+                "polygon" => {
+                    let points = points(shape, "points")?.into_iter().map(|point| point * scale).collect();
+                    Shape::Polygon(Polygon::new(points).ok_or("`points` must contain 3 points at least")?)
+                },
+                other => return Err(format!("unknown shape `{}`", other)),
+            })
+        };
+        shapes.push(parse_shape().map_err(|e| format!("hitbox[{}]: {}", i, e))?);
+    }
+
+    if shapes.is_empty() {
+        return Err("`hitbox` must not be empty".to_string());
+    }
+    Ok(Some(Hitbox::new(shapes)))
+}
+
+fn boss_spawn(kind: usize, enemy: &Kind, table: &Table) -> Result<BossSpawn, String> {
+    let trigger = match table.get("at") {
+        Some(_) => BossTrigger::At(number(table, "at", None)?),
+        None => BossTrigger::AfterWaves,
+    };
+    let position = vec2(table, "position", None)?;
+
+    // Without phases, the boss simply moves and shoots like a regular enemy.
+    let mut phases = Vec::new();
+    match table.get("phases") {
+        None => {
+            let (pattern, speed) = movement(table)?;
+            phases.push(Phase {
+                threshold: 1.0,
+                pattern: pattern,
+                speed: speed.unwrap_or(enemy.speed),
+                weapons: enemy.weapon.iter().cloned().collect(),
+            });
+        },
+        Some(list) => {
+            let list = list.as_array().ok_or("`phases` must be an array of tables")?;
+            for (i, phase) in list.iter().enumerate() {
+                let phase = phase.as_table().ok_or("`phases` must be an array of tables")?;
+                let parse_phase = || -> Result<Phase, String> {
+                    let (pattern, speed) = match phase.get("movement") {
+                        Some(_) => movement(phase)?,
+                        None => (Pattern::Straight { direction: Vec2::zero() }, None),
+                    };
+
+                    let mut weapons = Vec::new();
+                    if let Some(list) = phase.get("weapons") {
+                        let list = list.as_array().ok_or("`weapons` must be an array of tables")?;
+                        for (i, w) in list.iter().enumerate() {
+                            let w = w.as_table().ok_or("`weapons` must be an array of tables")?;
+                            weapons.push(emitter(w).map_err(|e| format!("weapons[{}]: {}", i, e))?);
+                        }
+                    }
+
+                    Ok(Phase {
+                        threshold: number(phase, "health", if i == 0 { Some(1.0) } else { None })?,
+                        pattern: pattern,
+                        speed: speed.unwrap_or(enemy.speed),
+                        weapons: weapons,
+                    })
+                };
+                phases.push(parse_phase().map_err(|e| format!("phases[{}]: {}", i, e))?);
+            }
+
+            if phases.is_empty() {
+                return Err("`phases` must not be empty".to_string());
+            }
+            if phases.windows(2).any(|pair| pair[1].threshold >= pair[0].threshold) {
+                return Err("the `health` of the phases must be decreasing".to_string());
+            }
+        },
+    }
+
+    let mut weak_points = Vec::new();
+    if let Some(list) = table.get("weak_points") {
+        let list = list.as_array().ok_or("`weak_points` must be an array of tables")?;
+        for (i, point) in list.iter().enumerate() {
+            let point = point.as_table().ok_or("`weak_points` must be an array of tables")?;
+            let parse_point = || -> Result<WeakPoint, String> {
+                let center = vec2(point, "center", None)?;
+                Ok(WeakPoint {
+                    shape: Shape::Circle(Circle { x: center.x, y: center.y, r: number(point, "radius", None)? }),
+                    multiplier: number(point, "multiplier", Some(3.0))?,
+                })
+            };
+            weak_points.push(parse_point().map_err(|e| format!("weak_points[{}]: {}", i, e))?);
+        }
+    }
+
+    Ok(BossSpawn {
+        trigger: trigger,
+        kind: kind,
+        position: position,
+        arena: vec2(table, "arena", Some(position))?,
+        entrance: number(table, "entrance", Some(0.0))?,
+        timeout: match table.get("timeout") {
+            None => None,
+            Some(_) => Some(number(table, "timeout", None)?),
+        },
+        armor: number(table, "armor", Some(1.0))?,
+        phases: phases,
+        weak_points: weak_points,
+    })
+}
+
+/// Reads the optional `weapon` table of an enemy kind.
+fn weapon(table: &Table) -> Result<Option<Emitter>, String> {
+    match table.get("weapon") {
+        None => Ok(None),
+        Some(w) => {
+            let w = w.as_table().ok_or("`weapon` must be a table")?;
+            emitter(w).map(Some).map_err(|e| format!("weapon: {}", e))
+        },
+    }
+}
+
+fn emitter(w: &Table) -> Result<Emitter, String> {
+    let count = || number(w, "count", Some(1.0)).map(|n| n as u32);
+
+    let pattern = match &string(w, "pattern")?[..] {
+        "aimed" => BulletPattern::Aimed {
+            count: count()?,
+            spread: number(w, "spread", Some(0.0))?,
+        },
+        "spread" => BulletPattern::Spread {
+            count: count()?,
+            spread: number(w, "spread", None)?,
+            angle: number(w, "angle", Some(180.0))?,
+        },
+        "radial" => BulletPattern::Radial {
+            count: count()?,
+        },
+        "spiral" => BulletPattern::Spiral {
+            arms: number(w, "arms", Some(1.0))? as u32,
+            step: number(w, "step", None)?,
+        },
+        "rotating" => BulletPattern::Rotating {
+            count: count()?,
+            spread: number(w, "spread", Some(0.0))?,
+            rotation_speed: number(w, "rotation_speed", None)?,
+        },
+        other => return Err(format!("unknown pattern `{}`", other)),
+    };
+
+    let interval = number(w, "interval", None)?;
+    let mut emitter = Emitter::new(pattern, number(w, "speed", None)?, interval,
+                                   number(w, "delay", Some(interval))?);
+    emitter.radius = number(w, "radius", Some(emitter.radius))?;
+
+    Ok(emitter)
+}
+
+fn string(table: &Table, key: &str) -> Result<String, String> {
+    match table.get(key) {
+        Some(value) => value.as_str().map(|s| s.to_string()).ok_or(format!("`{}` must be a string", key)),
+        None => Err(format!("missing `{}`", key)),
+    }
+}
+
+fn number(table: &Table, key: &str, default: Option<f64>) -> Result<f64, String> {
+    match (table.get(key), default) {
+        (Some(value), _) => toml::number(value).ok_or(format!("`{}` must be a number", key)),
+        (None, Some(default)) => Ok(default),
+        (None, None) => Err(format!("missing `{}`", key)),
+    }
+}
+
+fn boolean(table: &Table, key: &str, default: bool) -> Result<bool, String> {
+    match table.get(key) {
+        Some(value) => value.as_bool().ok_or(format!("`{}` must be a boolean", key)),
+        None => Ok(default),
+    }
+}
+
+/// Reads an array of exactly `len` numbers.
+fn numbers(value: &Value, len: usize) -> Option<Vec<f64>> {
+    value.as_array()
+        .and_then(|items| items.iter().map(toml::number).collect::<Option<Vec<f64>>>())
+        .and_then(|items| if items.len() == len { Some(items) } else { None })
+}
+
+fn vec2(table: &Table, key: &str, default: Option<Vec2>) -> Result<Vec2, String> {
+    match (table.get(key), default) {
+        (Some(value), _) => numbers(value, 2)
+            .map(|xy| Vec2::new(xy[0], xy[1]))
+            .ok_or(format!("`{}` must be an array of 2 numbers", key)),
+        (None, Some(default)) => Ok(default),
+        (None, None) => Err(format!("missing `{}`", key)),
+    }
+}
+
+fn points(table: &Table, key: &str) -> Result<Vec<Vec2>, String> {
+    match table.get(key) {
+        None => Err(format!("missing `{}`", key)),
+        Some(points) => points.as_array()
+            .and_then(|points| points.iter()
+                .map(|point| numbers(point, 2).map(|xy| Vec2::new(xy[0], xy[1])))
+                .collect())
+            .ok_or(format!("`{}` must be an array of points such as [x, y]", key)),
+    }
+}
+
+/// Reads the optional `movement` table, and returns the pattern it describes
+/// and the speed it overrides, if any.
+fn movement(table: &Table) -> Result<(Pattern, Option<f64>), String> {
+    let m = match table.get("movement") {
+        None => return Ok((Pattern::default(), None)),
+        Some(m) => m.as_table().ok_or("`movement` must be a table")?,
+    };
+
+    let parse = || -> Result<(Pattern, Option<f64>), String> {
+        let left = Some(Vec2::new(-1.0, 0.0));
+
+        let pattern = match &string(m, "pattern")?[..] {
+            "straight" => Pattern::Straight {
+                direction: vec2(m, "direction", left)?,
+            },
+            "sine" => Pattern::Sine {
+                direction: vec2(m, "direction", left)?,
+                amplitude: number(m, "amplitude", None)?,
+                frequency: number(m, "frequency", Some(1.0))?,
+            },
+            "dive" => Pattern::Dive {
+                delay: number(m, "delay", Some(1.0))?,
+                dive_speed: number(m, "dive_speed", None)?,
+            },
+            "bezier" => {
+                let controls = points(m, "controls")?;
+                if controls.len() != 3 {
+                    return Err("`controls` must contain 3 points".to_string());
+                }
+                Pattern::Bezier { controls: [controls[0], controls[1], controls[2]] }
+            },
+            "hold" => Pattern::HoldAndRetreat {
+                target: vec2(m, "target", None)?,
+                hold: number(m, "hold", None)?,
+                retreat: vec2(m, "retreat", Some(Vec2::new(1.0, 0.0)))?,
+            },
+            "path" => {
+                let points = points(m, "points")?;
+                let loop_from = match m.get("loop_from") {
+                    None => None,
+                    Some(_) => Some(number(m, "loop_from", None)? as usize),
+                };
+
+                if loop_from.is_some_and(|i| i >= points.len()) {
+                    return Err("`loop_from` must be the index of a point of the path".to_string());
+                }
+
+                Pattern::Path { points: points, loop_from: loop_from }
+            },
+            other => return Err(format!("unknown pattern `{}`", other)),
+        };
+
+        let speed = match m.get("speed") {
+            None => None,
+            Some(_) => Some(number(m, "speed", None)?),
+        };
+
+        Ok((pattern, speed))
+    };
+
+    parse().map_err(|e| format!("movement: {}", e))
+}
+
+/// Turns a wave of the endless mode into one of the level.
+fn planned_wave(kinds: &[Kind], plan: WavePlan) -> Wave {
+    Wave {
+        at: plan.at,
+        interval: plan.interval,
+        position: plan.position,
+        slots: vec![Vec2::zero(); plan.count],
+        pattern: plan.pattern,
+        speed: Some(kinds[plan.kind].speed * plan.speed),
+        health: plan.health,
+        kind: plan.kind,
+    }
+}
+
+/// An enemy which should appear, to be created by `LevelKinds::enemy`.
+pub struct EnemySpawn {
+    /// The index of its kind in the level.
+    kind: usize,
+    movement: Movement,
+    /// Multiplies the health of the kind.
+    health: f64,
+}
+
+/// The enemies which should appear during a frame.
+pub struct Spawns {
+    pub enemies: Vec<EnemySpawn>,
+    /// Whether the boss of the level should appear.
+    pub boss: bool,
+}
+
+/// Keeps track of the time spent in a level, and tells which enemies should
+/// appear.
+pub struct LevelRunner {
+    level: Level,
+    /// Seconds since the start of the level.
+    time: f64,
+    /// The number of enemies already spawned by each wave.
+    spawned: Vec<usize>,
+    boss_spawned: bool,
+    finished: bool,
+    /// Adds waves to the level as it goes, in the endless mode.
+    generator: Option<Generator>,
+    /// Divides the time between the enemies of a wave.
+    pub spawn_rate: f64,
+}
+
+impl LevelRunner {
+    pub fn new(level: Level) -> LevelRunner {
+        LevelRunner {
+            spawned: vec![0; level.waves.len()],
+            level: level,
+            time: 0.0,
+            boss_spawned: false,
+            finished: false,
+            generator: None,
+            spawn_rate: 1.0,
+        }
+    }
+
+    /// Runs the waves of the level, followed by endless waves of its enemies
+    /// made up from `seed`.
+    pub fn endless(level: Level, seed: u64) -> LevelRunner {
+        let mut runner = LevelRunner::new(level);
+        if !runner.level.kinds.is_empty() {
+            runner.generator = Some(Generator::new(seed, runner.level.kinds.len()));
+        }
+        runner
+    }
+
+    /// The number of waves made up so far in the endless mode.
+    pub fn endless_waves(&self) -> u32 {
+        self.generator.as_ref().map_or(0, Generator::waves)
+    }
+
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+
+    /// Returns where the enemies due in the next `within` seconds will
+    /// appear, with the name of their kind and the seconds left before they
+    /// do. Waves of the endless mode are only known once they start.
+    pub fn upcoming_spawns(&self, within: f64) -> Vec<(Vec2, &str, f64)> {
+        let mut spawns = Vec::new();
+        for (wave, &spawned) in self.level.waves.iter().zip(self.spawned.iter()) {
+            let interval = wave.interval / self.spawn_rate;
+            for (i, &slot) in wave.slots.iter().enumerate().skip(spawned) {
+                let left = wave.at + interval * i as f64 - self.time;
+                if left <= within {
+                    spawns.push((wave.position + slot, &self.level.kinds[wave.kind].name[..], left.max(0.0)));
+                }
+            }
+        }
+        spawns
+    }
+
+    /// Returns whether the end condition of the level was met.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Saves how far the level went. The waves of the endless mode are not
+    /// saved, since they are made up again from the seed.
+    pub fn snapshot(&self) -> Value {
+        table(vec![
+            ("time", self.time.snapshot()),
+            ("spawned", self.spawned.snapshot()),
+            ("boss_spawned", self.boss_spawned.snapshot()),
+            ("finished", self.finished.snapshot()),
+            ("endless_waves", self.endless_waves().snapshot()),
+            ("spawn_rate", self.spawn_rate.snapshot()),
+        ])
+    }
+
+    /// Restores a snapshot of a runner of the same level, started with the
+    /// same seed. Must be called before the first update.
+    pub fn restore(&mut self, value: &Value) -> Result<(), String> {
+        let waves: u32 = field(value, "endless_waves")?;
+        if let Some(ref mut generator) = self.generator {
+            while generator.waves() < waves {
+                let plan = generator.next();
+                let wave = planned_wave(&self.level.kinds, plan);
+                self.level.waves.push(wave);
+            }
+        }
+
+        let spawned: Vec<usize> = field(value, "spawned")?;
+        let valid = spawned.len() == self.level.waves.len() &&
+            spawned.iter().zip(self.level.waves.iter()).all(|(&count, wave)| count <= wave.slots.len());
+        if !valid {
+            return Err("`spawned` does not match the waves of the level".to_string());
+        }
+
+        self.time = field(value, "time")?;
+        self.spawned = spawned;
+        self.boss_spawned = field(value, "boss_spawned")?;
+        self.finished = field(value, "finished")?;
+        self.spawn_rate = field(value, "spawn_rate")?;
+        Ok(())
+    }
+
+    /// Advances the level by `elapsed` seconds. `enemies` are those
+    /// currently in the level, and `boss` the state of its boss, if there is
+    /// one; the spawns should be added to them.
+    pub fn update(&mut self, elapsed: f64, enemies: &[Enemy], boss: Option<BossState>) -> Spawns {
+        self.time += elapsed;
+        let mut spawns = Spawns { enemies: Vec::new(), boss: false };
+
+        if let Some(ref mut generator) = self.generator {
+            while generator.next_at() <= self.time {
+                let plan = generator.next();
+                let wave = planned_wave(&self.level.kinds, plan);
+                self.level.waves.push(wave);
+                self.spawned.push(0);
+            }
+        }
+
+        for (wave, spawned) in self.level.waves.iter().zip(self.spawned.iter_mut()) {
+            let interval = wave.interval / self.spawn_rate;
+            while *spawned < wave.slots.len() && self.time >= wave.at + interval * *spawned as f64 {
+                spawns.enemies.push(EnemySpawn {
+                    kind: wave.kind,
+                    movement: Movement::new(
+                        wave.pattern.clone(),
+                        wave.speed.unwrap_or(self.level.kinds[wave.kind].speed),
+                        wave.position + wave.slots[*spawned]),
+                    health: wave.health,
+                });
+                *spawned += 1;
+            }
+        }
+
+        let waves_done = self.level.waves.iter().zip(self.spawned.iter())
+            .all(|(wave, &spawned)| spawned == wave.slots.len());
+        let no_enemies = enemies.is_empty() && spawns.enemies.is_empty();
+
+        if let Some(ref spawn) = self.level.boss {
+            let triggered = match spawn.trigger {
+                BossTrigger::At(at) => self.time >= at,
+                BossTrigger::AfterWaves => waves_done && no_enemies,
+            };
+
+            if triggered && !self.boss_spawned {
+                spawns.boss = true;
+                self.boss_spawned = true;
+            }
+        }
+
+        // The encounter is over once the boss was defeated, or escaped, but
+        // only a defeat counts as destroying it.
+        let boss = if self.boss_spawned && !spawns.boss { boss } else { None };
+        let boss_defeated = boss == Some(BossState::Defeated);
+        let boss_done = self.level.boss.is_none() || boss_defeated || boss == Some(BossState::Escaped);
+
+        self.finished = self.finished || match self.level.end {
+            EndCondition::WavesCleared => waves_done && boss_done && no_enemies,
+            EndCondition::Time(duration) => self.time >= duration,
+            EndCondition::BossDefeated => boss_defeated,
+            EndCondition::Never => false,
+        };
+
+        spawns
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn level_files() {
+        use views::game::level::{EndCondition, Level};
+        use views::game::stage;
+
+        // Every level of the game can be read, without loading its sprites.
+        let mut paths = stage::stages().unwrap();
+        paths.push("assets/levels/endless.toml".to_string());
+        for path in &paths {
+            if let Err(e) = Level::load(path) {
+                panic!("{}", e);
+            }
+        }
+
+        let level = Level::load("assets/levels/1.toml").unwrap();
+        assert_eq!(level.name, "Outer Rim");
+        assert_eq!(level.end, EndCondition::WavesCleared);
+
+        // Kinds are sorted by score, and waves refer to them by index.
+        let names: Vec<&str> = level.kinds.iter().map(|kind| kind.name.as_str()).collect();
+        assert_eq!(names, vec!["grunt", "gunship", "mothership"]);
+        assert!(level.waves.iter().all(|wave| wave.kind < 2));
+        assert!(level.kinds[1].hitbox.is_some());
+
+        let boss = level.boss.as_ref().unwrap();
+        assert_eq!(boss.kind, 2);
+        assert!(!boss.phases.is_empty());
+        assert!(!boss.weak_points.is_empty());
+    }
+
+    #[test]
+    fn level_errors() {
+        use phi::toml;
+        use views::game::level::Level;
+
+        let level = |text: &str| {
+            let doc = format!("name = \"Test\"\n\n[enemies.grunt]\nsprite = \"grunt.png\"\nspeed = 100\n{}", text);
+            Level::from_table(&toml::parse(&doc).unwrap()).map(|_| ())
+        };
+
+        assert_eq!(level(""), Ok(()));
+        assert_eq!(level("[[waves]]\nat = 0\nenemy = \"ghost\"\nposition = [0, 0]"),
+                   Err("waves[0]: unknown enemy `ghost`".to_string()));
+        assert_eq!(level("flip = 1"), Err("enemies.grunt: `flip` must be a boolean".to_string()));
+        assert_eq!(level("hitbox = [{ shape = \"polygon\", points = [[0, 0], [1, 1]] }]"),
+                   Err("enemies.grunt: hitbox[0]: `points` must contain 3 points at least".to_string()));
+        assert_eq!(level("hitbox = [{ shape = \"polygon\", points = [] }]"),
+                   Err("enemies.grunt: hitbox[0]: `points` must contain 3 points at least".to_string()));
+        assert_eq!(level("[boss]\nenemy = \"grunt\"\nposition = [0, 0]\nphases = [{ health = 1.0 }, { health = 1.0 }]"),
+                   Err("boss: the `health` of the phases must be decreasing".to_string()));
+        assert!(Level::from_table(&toml::parse("name = \"Test\"\nend = \"boss\"").unwrap()).is_err());
+        assert!(level("[boss]\nenemy = \"grunt\"\nposition = [0, 0]\ntimeout = 10").is_ok());
+        assert!(level("[[waves]]\nat = 0\nenemy = \"grunt\"\nposition = [0, 0]\nmovement = { pattern = \"path\", points = [[0, 0]], loop_from = 1 }").is_err());
+    }
+
+    #[test]
+    fn boss_outcomes() {
+        use phi::toml;
+        use views::game::boss::BossState;
+        use views::game::level::{Level, LevelRunner};
+
+        let start = |end: &str, timeout: &str| {
+            let doc = format!("name = \"Test\"\nend = \"{}\"\n\n\
+                               [enemies.mothership]\nsprite = \"boss.png\"\nspeed = 100\n\n\
+                               [boss]\nenemy = \"mothership\"\nposition = [0, 0]\n{}", end, timeout);
+            Level::from_table(&toml::parse(&doc).unwrap()).map(LevelRunner::new)
+        };
+
+        // Only a boss which cannot escape may end the level by itself.
+        assert!(start("boss", "timeout = 10").is_err());
+
+        for &end in &["waves", "boss"] {
+            // The boss appears once every wave is over, which is at once.
+            let mut runner = start(end, "").unwrap();
+            assert!(runner.update(0.1, &[], None).boss);
+            runner.update(0.1, &[], Some(BossState::Fighting(0)));
+            assert!(!runner.is_finished());
+
+            runner.update(0.1, &[], Some(BossState::Defeated));
+            assert!(runner.is_finished());
+
+            // An escaped boss only ends a level which did not require
+            // destroying it.
+            let mut runner = start(end, "").unwrap();
+            runner.update(0.1, &[], None);
+            runner.update(0.1, &[], Some(BossState::Escaped));
+            assert_eq!(runner.is_finished(), end == "waves");
+        }
+    }
+}
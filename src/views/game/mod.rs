@@ -0,0 +1,1821 @@
+pub mod boss;
+pub mod bullet;
+pub mod demo;
+pub mod difficulty;
+pub mod emitter;
+pub mod endless;
+pub mod enemy;
+pub mod hud;
+pub mod level;
+pub mod movement;
+pub mod pickup;
+pub mod save;
+pub mod scores;
+pub mod stage;
+pub mod state;
+pub mod weapon;
+
+use phi::{Phi, View, ViewAction};
+use phi::camera::Camera;
+use phi::console::{self, Commands};
+use phi::data::{Capsule, Circle, Hitbox, PixelMask, Rectangle, Shape, Vec2};
+use phi::gfx::{self, Sprite};
+use phi::input::{Controls, Input};
+use phi::rng::Rng;
+use phi::scheduler::{Scheduler, TimerId};
+use phi::snapshot::{field, table, variant, variant_name, Snapshot};
+use phi::toml::{self, Table, Value};
+use sdl2::pixels::Color;
+use sdl2::render::BlendMode;
+
+use sdl2::render::Renderer;
+use self::boss::Boss;
+use self::bullet::{BulletPool, Owner};
+use self::demo::{Playback, Recording, RECORDING_PATH};
+use self::difficulty::{Adaptive, Scaling};
+use self::endless::{format_seed, ENDLESS_PATH};
+use self::enemy::Enemy;
+use self::hud::{Anchor, Hud};
+use self::level::{Level, LevelAssets, LevelKinds, LevelRunner};
+use self::movement::{Movement, Pattern};
+use self::pickup::{Pickup, PickupKind};
+use self::save::{PlayerSave, SaveState};
+use self::scores::{Entry, HighScores, SCORES_PATH};
+use self::stage::{StageClearView, Summary};
+use self::state::StateMachine;
+use self::weapon::WeaponLevel;
+use std::mem;
+use std::time::{SystemTime, UNIX_EPOCH};
+use views::settings::Settings;
+
+/// Pixels traveled by the player's ship every second, when it is moving.
+const PLAYER_SPEED: f64 = 180.0;
+
+/// The speed of the ship while focused, for precise movements.
+const PLAYER_FOCUS_SPEED: f64 = 80.0;
+
+const MAX_LIVES: u32 = 9;
+
+/// Seconds during which the shield and the speed boost last.
+const SHIELD_DURATION: f64 = 10.0;
+const BOOST_DURATION: f64 = 8.0;
+
+/// Multiplies the speed of the ship while it is boosted.
+const BOOST_FACTOR: f64 = 1.5;
+
+/// The number of smart bombs the player starts with, and can hold at most.
+const PLAYER_BOMBS: u32 = 2;
+const MAX_BOMBS: u32 = 5;
+
+/// The damage dealt to every enemy on the screen by a smart bomb.
+const SMART_BOMB_DAMAGE: f64 = 10.0;
+
+/// Seconds during which the player cannot be hit after using a smart bomb.
+const SMART_BOMB_INVULNERABILITY: f64 = 1.5;
+
+/// Seconds taken by the flash of a smart bomb to fade out.
+const SMART_BOMB_FLASH: f64 = 0.5;
+
+/// The game slows down to this factor once a boss is defeated, and gets
+/// back to normal over that many seconds.
+const BOSS_SLOW_MOTION: f64 = 0.25;
+const BOSS_SLOW_MOTION_DURATION: f64 = 2.0;
+
+/// Seconds during which the player cannot be hit after losing a life.
+const INVULNERABILITY: f64 = 2.0;
+
+/// Seconds during which "Game Over" is displayed before going back to the menu.
+const GAME_OVER_DELAY: f64 = 3.0;
+
+/// Seconds taken by the title of a stage to go away, and by the screen to
+/// fade in at its start.
+const STAGE_INTRO: f64 = 2.5;
+const FADE_IN: f64 = 1.0;
+
+/// Seconds taken by the ships to warp away at the end of a stage, the last
+/// of which are spent fading out.
+const STAGE_OUTRO: f64 = 2.5;
+const FADE_OUT: f64 = 1.0;
+
+/// How fast the ships and the stars accelerate while warping away.
+const WARP_ACCELERATION: f64 = 900.0;
+const WARP_SCROLL: f64 = 6.0;
+
+/// The maximum number of bullets alive at the same time.
+const MAX_BULLETS: usize = 2048;
+
+/// Where the debug hotkeys save the game, and load it from.
+const QUICKSAVE_PATH: &str = "quicksave.toml";
+
+/// Enemies are removed once they are this many pixels away from the visible
+/// region, which leaves them room to appear from outside of the screen.
+const ENEMY_MARGIN: f64 = 250.0;
+
+/// The seconds ahead shown by the debug layer: how far along their path the
+/// enemies are followed, how soon enemies must appear for their spawn point
+/// to be marked, and how far velocities reach.
+const DEBUG_PATH: f64 = 3.0;
+const DEBUG_SPAWNS: f64 = 5.0;
+const DEBUG_VELOCITY: f64 = 0.25;
+
+struct Ship {
+    /// The index of the player controlling the ship.
+    id: usize,
+    controls: Controls,
+    pos: Rectangle,
+    bounds: Rectangle,
+    /// The opaque pixels of every stance, if they are known.
+    masks: Vec<Option<PixelMask>>,
+    stance: ShipStance,
+    hitbox: Hitbox,
+    /// Whether the ship is in focus mode: slower, with a narrower spread and
+    /// its hitbox visible.
+    focused: bool,
+
+    lives: u32,
+    bombs: u32,
+    score: u32,
+    /// The number of enemies destroyed, bullets fired, bullets which hit
+    /// something and lives lost during the current stage.
+    kills: u32,
+    shots: u32,
+    hits: u32,
+    misses: u32,
+    weapon: WeaponLevel,
+    /// Times what wears off. The effects last while their timer is pending.
+    timers: Scheduler<Effect>,
+    /// Set until the ship can be hit again.
+    invulnerable: Option<TimerId>,
+    shield: Option<TimerId>,
+    boost: Option<TimerId>,
+    /// Reloads the weapon every `interval`, while the trigger is held.
+    reload: Option<TimerId>,
+}
+
+/// What the timers of a ship stand for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Effect {
+    Invulnerable,
+    Shield,
+    Boost,
+    Reload,
+}
+
+impl Snapshot for Effect {
+    fn snapshot(&self) -> Value {
+        let name = match *self {
+            Effect::Invulnerable => "invulnerable",
+            Effect::Shield => "shield",
+            Effect::Boost => "boost",
+            Effect::Reload => "reload",
+        };
+        Value::String(name.to_string())
+    }
+
+    fn restore(value: &Value) -> Result<Effect, String> {
+        match value.as_str() {
+            Some("invulnerable") => Ok(Effect::Invulnerable),
+            Some("shield") => Ok(Effect::Shield),
+            Some("boost") => Ok(Effect::Boost),
+            Some("reload") => Ok(Effect::Reload),
+            _ => Err("unknown effect".to_string()),
+        }
+    }
+}
+
+/// The different states our ship might be in. In the image, they're ordered
+/// from left to right, then from top to bottom. The slow stances are used in
+/// focus mode.
+#[derive(Clone, Copy)]
+enum ShipStance {
+    UpNorm   = 0,
+    UpFast   = 1,
+    UpSlow   = 2,
+    MidNorm  = 3,
+    MidFast  = 4,
+    MidSlow  = 5,
+    DownNorm = 6,
+    DownFast = 7,
+    DownSlow = 8
+}
+
+impl ShipStance {
+    fn all() -> [ShipStance; 9] {
+        use self::ShipStance::*;
+        [UpNorm, UpFast, UpSlow, MidNorm, MidFast, MidSlow, DownNorm, DownFast, DownSlow]
+    }
+}
+
+impl Ship {
+    /// Creates the ship of player `id`, whose stances are `size` pixels large
+    /// and have the given opaque pixels. Every player's ship starts in a
+    /// different corner.
+    pub fn new(size: (f64, f64), masks: Vec<Option<PixelMask>>, bounds: Rectangle, id: usize, controls: Controls,
+               lives: u32) -> Ship {
+        let (w, h) = size;
+
+        Ship {
+            id: id,
+            controls: controls,
+            pos: Ship::start(id, bounds, w, h),
+            bounds: bounds,
+            masks: masks,
+            stance: ShipStance::MidNorm,
+            focused: false,
+
+            // The hull, plus the cockpit which sticks out at the front
+            hitbox: Hitbox::new(vec![
+                Shape::Capsule(Capsule { x1: w * 0.2, y1: h * 0.5, x2: w * 0.75, y2: h * 0.5, r: h * 0.2 }),
+                Shape::Circle(Circle { x: w * 0.75, y: h * 0.5, r: h * 0.15 }),
+            ]),
+
+            lives: lives,
+            bombs: PLAYER_BOMBS,
+            score: 0,
+            kills: 0,
+            shots: 0,
+            hits: 0,
+            misses: 0,
+            weapon: WeaponLevel::Single,
+            timers: Scheduler::new(),
+            invulnerable: None,
+            shield: None,
+            boost: None,
+            reload: None,
+        }
+    }
+
+    /// Where the ship of player `id` starts every stage.
+    fn start(id: usize, bounds: Rectangle, w: f64, h: f64) -> Rectangle {
+        Rectangle {
+            w: w,
+            h: h,
+            x: 64.0,
+            y: if id == 0 { 64.0 } else { bounds.y + bounds.h - 64.0 - h },
+        }
+    }
+
+    /// Brings the ship back to its starting position, keeping its lives,
+    /// score and upgrades, and starts counting its stats over.
+    fn start_stage(&mut self) {
+        self.pos = Ship::start(self.id, self.bounds, self.pos.w, self.pos.h);
+        self.stance = ShipStance::MidNorm;
+        self.end(Effect::Invulnerable);
+        self.end(Effect::Reload);
+        self.kills = 0;
+        self.shots = 0;
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    /// How well the player did during the current stage.
+    fn summary(&self) -> Summary {
+        Summary::new(self.kills, self.shots, self.hits, self.lives, self.misses)
+    }
+
+    /// Saves what changes during a stage. The masks, hitbox and controls
+    /// only depend on the player.
+    fn snapshot(&self) -> Value {
+        table(vec![
+            ("pos", self.pos.snapshot()),
+            ("stance", (self.stance as usize).snapshot()),
+            ("focused", self.focused.snapshot()),
+            ("lives", self.lives.snapshot()),
+            ("bombs", self.bombs.snapshot()),
+            ("score", self.score.snapshot()),
+            ("kills", self.kills.snapshot()),
+            ("shots", self.shots.snapshot()),
+            ("hits", self.hits.snapshot()),
+            ("misses", self.misses.snapshot()),
+            ("weapon", self.weapon.snapshot()),
+            ("timers", self.timers.snapshot()),
+            ("invulnerable", self.invulnerable.snapshot()),
+            ("shield", self.shield.snapshot()),
+            ("boost", self.boost.snapshot()),
+            ("reload", self.reload.snapshot()),
+        ])
+    }
+
+    /// Restores a snapshot of the ship of the same player.
+    fn restore(&mut self, value: &Value) -> Result<(), String> {
+        let stance: usize = field(value, "stance")?;
+        self.stance = *ShipStance::all().get(stance).ok_or("unknown stance")?;
+        self.pos = field(value, "pos")?;
+        self.focused = field(value, "focused")?;
+        self.lives = field(value, "lives")?;
+        self.bombs = field(value, "bombs")?;
+        self.score = field(value, "score")?;
+        self.kills = field(value, "kills")?;
+        self.shots = field(value, "shots")?;
+        self.hits = field(value, "hits")?;
+        self.misses = field(value, "misses")?;
+        self.weapon = field(value, "weapon")?;
+        self.timers = field(value, "timers")?;
+        self.invulnerable = field(value, "invulnerable")?;
+        self.shield = field(value, "shield")?;
+        self.boost = field(value, "boost")?;
+        self.reload = field(value, "reload")?;
+        Ok(())
+    }
+
+    /// Whether the ship currently has `effect`.
+    fn has(&self, effect: Effect) -> bool {
+        self.id_of(effect).is_some_and(|id| self.timers.is_pending(id))
+    }
+
+    fn id_of(&self, effect: Effect) -> Option<TimerId> {
+        match effect {
+            Effect::Invulnerable => self.invulnerable,
+            Effect::Shield => self.shield,
+            Effect::Boost => self.boost,
+            Effect::Reload => self.reload,
+        }
+    }
+
+    fn timer(&mut self, effect: Effect) -> &mut Option<TimerId> {
+        match effect {
+            Effect::Invulnerable => &mut self.invulnerable,
+            Effect::Shield => &mut self.shield,
+            Effect::Boost => &mut self.boost,
+            Effect::Reload => &mut self.reload,
+        }
+    }
+
+    /// Seconds left before `effect` wears off, 0 if the ship does not have
+    /// it.
+    fn left(&self, effect: Effect) -> f64 {
+        self.id_of(effect).and_then(|id| self.timers.remaining(id)).unwrap_or(0.0)
+    }
+
+    /// Gives `effect` to the ship for `duration` seconds, from now on.
+    fn apply(&mut self, effect: Effect, duration: f64) {
+        self.end(effect);
+        let id = self.timers.after(duration, effect);
+        *self.timer(effect) = Some(id);
+    }
+
+    fn end(&mut self, effect: Effect) {
+        if let Some(id) = self.timer(effect).take() {
+            self.timers.cancel(id);
+        }
+    }
+
+    /// The region the ship cannot leave.
+    pub fn bounds(&self) -> Rectangle {
+        self.bounds
+    }
+
+    /// Returns the ship's hitbox at its current position.
+    pub fn hitbox(&self) -> Hitbox {
+        self.hitbox.at(self.pos)
+    }
+
+    /// Returns whether an opaque pixel of the ship touches one of `mask`,
+    /// rendered over `rect`. This refines a test of the hitboxes, which must
+    /// be known to overlap; sprites without a mask are opaque everywhere.
+    pub fn touches(&self, mask: Option<&PixelMask>, rect: Rectangle) -> bool {
+        match (self.masks[self.stance as usize].as_ref(), mask) {
+            (Some(own), Some(mask)) => own.overlaps(self.pos, mask, rect),
+            _ => true,
+        }
+    }
+
+    pub fn update(&mut self, input: Input, elapsed: f64, bullets: &mut BulletPool) {
+        self.focused = input.focus;
+
+        let mut direction = Vec2::zero();
+        if input.up { direction.y -= 1.0; }
+        if input.down { direction.y += 1.0; }
+        if input.left { direction.x -= 1.0; }
+        if input.right { direction.x += 1.0; }
+
+        // Moving diagonally should not be any faster than moving straight.
+        let speed = if self.focused { PLAYER_FOCUS_SPEED } else { PLAYER_SPEED };
+        let speed = if self.has(Effect::Boost) { speed * BOOST_FACTOR } else { speed };
+        let moved = direction.normalize() * speed * elapsed;
+        let new_pos = self.pos.translate(moved);
+
+        self.pos = new_pos.restrict_to_bounds(self.bounds);
+        self.update_stance(moved.x, moved.y);
+        self.shoot(input.fire, elapsed, bullets);
+    }
+
+    /// Shoots from the front of the ship while `firing` is held: at once if
+    /// the weapon is loaded, then every time it is reloaded, which also
+    /// advances the other timers of the ship.
+    fn shoot(&mut self, firing: bool, elapsed: f64, bullets: &mut BulletPool) {
+        let mut volleys = 0;
+        for effect in self.timers.update(elapsed) {
+            match effect {
+                // Reloading while the trigger is released leaves the weapon
+                // loaded, until it is pulled again.
+                Effect::Reload if firing => volleys += 1,
+                Effect::Reload => self.end(Effect::Reload),
+                _ => {},
+            }
+        }
+
+        if firing && self.reload.is_none() {
+            volleys += 1;
+            self.reload = Some(self.timers.every(self.weapon.interval(), Effect::Reload));
+        }
+
+        let nose = Vec2::new(self.pos.x + self.pos.w, self.pos.center().y);
+        for _ in 0..volleys {
+            for bullet in self.weapon.volley(self.id, nose, self.focused) {
+                if bullets.spawn(bullet) {
+                    self.shots += 1;
+                }
+            }
+        }
+    }
+
+    /// Changes the weapon, which is loaded at once so that the new reload
+    /// interval applies.
+    fn set_weapon(&mut self, weapon: WeaponLevel) {
+        self.weapon = weapon;
+        self.end(Effect::Reload);
+    }
+
+    /// Loses a life, unless the ship is still recovering from the last hit or
+    /// protected by its shield. Returns whether the hit was taken.
+    pub fn hit(&mut self) -> bool {
+        if self.has(Effect::Invulnerable) || self.lives == 0 {
+            return false;
+        }
+
+        // The shield breaks instead, leaving a short time to get away.
+        if self.has(Effect::Shield) {
+            self.end(Effect::Shield);
+            self.apply(Effect::Invulnerable, INVULNERABILITY / 2.0);
+            return false;
+        }
+
+        self.lives -= 1;
+        self.misses += 1;
+        self.apply(Effect::Invulnerable, INVULNERABILITY);
+        let weapon = self.weapon.downgrade();
+        self.set_weapon(weapon);
+        self.end(Effect::Boost);
+        true
+    }
+
+    /// Applies the effect of a pickup.
+    pub fn power_up(&mut self, kind: PickupKind) {
+        match kind {
+            PickupKind::WeaponUp => if let Some(weapon) = self.weapon.upgrade() {
+                self.set_weapon(weapon);
+            },
+            PickupKind::Shield => self.apply(Effect::Shield, SHIELD_DURATION),
+            PickupKind::SpeedBoost => self.apply(Effect::Boost, BOOST_DURATION),
+            PickupKind::SmartBomb => self.bombs = (self.bombs + 1).min(MAX_BOMBS),
+            PickupKind::ExtraLife => self.lives = (self.lives + 1).min(MAX_LIVES),
+        }
+    }
+
+    fn update_stance(&mut self, dx: f64, dy: f64) {
+        self.stance =
+            if self.focused {
+                if dy < 0.0 { ShipStance::UpSlow }
+                else if dy > 0.0 { ShipStance::DownSlow }
+                else { ShipStance::MidSlow }
+            }
+            else if dx > 0.0 && dy < 0.0  { ShipStance::UpFast }
+            else if dx > 0.0 && dy == 0.0 { ShipStance::MidFast }
+            else if dx > 0.0 && dy > 0.0  { ShipStance::DownFast }
+            else if dy < 0.0              { ShipStance::UpNorm }
+            else if dy > 0.0              { ShipStance::DownNorm }
+            else                          { ShipStance::MidNorm };
+    }
+
+    /// Renders the ship with `sprites`, those of its stances.
+    pub fn render(&self, sprites: &[Sprite], renderer: &mut Renderer, camera: &Camera) {
+        // Blink while invulnerable
+        if self.lives == 0 || (self.left(Effect::Invulnerable) * 10.0) as u32 % 2 == 1 {
+            return;
+        }
+
+        sprites[self.stance as usize].render_world(renderer, camera, self.pos);
+
+        if self.has(Effect::Shield) {
+            renderer.set_draw_color(Color::RGB(60, 200, 255));
+            gfx::draw_rect(renderer, camera.to_screen(self.pos.scale(1.2)));
+        }
+    }
+
+    /// Shows what can actually hurt the ship while it is focused. It is
+    /// rendered on top of everything else, so that it is never hidden by
+    /// bullets.
+    pub fn render_hitbox(&self, renderer: &mut Renderer, camera: &Camera) {
+        if !self.focused || self.lives == 0 {
+            return;
+        }
+
+        renderer.set_draw_color(Color::RGB(255, 255, 255));
+        for shape in self.hitbox().shapes() {
+            gfx::draw_shape(renderer, camera, shape);
+        }
+    }
+}
+
+/// What the players are playing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// The scripted levels.
+    Story,
+    /// Waves made up from the seed, until every player is dead.
+    Endless(u64),
+}
+
+impl Snapshot for Mode {
+    fn snapshot(&self) -> Value {
+        match *self {
+            Mode::Story => variant("story", vec![]),
+            Mode::Endless(seed) => variant("endless", vec![("seed", seed.snapshot())]),
+        }
+    }
+
+    fn restore(value: &Value) -> Result<Mode, String> {
+        match &variant_name(value)?[..] {
+            "story" => Ok(Mode::Story),
+            "endless" => Ok(Mode::Endless(field(value, "seed")?)),
+            other => Err(format!("unknown mode `{}`", other)),
+        }
+    }
+}
+
+/// What carries over from one stage to the next.
+pub struct Run {
+    players: Vec<Ship>,
+    settings: Settings,
+    mode: Mode,
+    /// Only set if the difficulty is adaptive.
+    adaptive: Option<Adaptive>,
+    /// The index of the current stage.
+    stage: usize,
+}
+
+impl Run {
+    /// Moves the run out, leaving one without players behind.
+    fn take(&mut self) -> Run {
+        Run {
+            players: mem::take(&mut self.players),
+            settings: self.settings,
+            mode: self.mode,
+            adaptive: self.adaptive.take(),
+            stage: self.stage,
+        }
+    }
+
+    /// Saves the progress of the campaign, so that it can be continued from
+    /// `stage`.
+    fn save_progress(&self, stage: usize) {
+        if self.mode != Mode::Story {
+            return;
+        }
+
+        let save = SaveState {
+            stage: stage,
+            settings: self.settings,
+            players: self.players.iter().map(|player| PlayerSave {
+                score: player.score,
+                lives: player.lives,
+                bombs: player.bombs,
+                weapon: player.weapon,
+            }).collect(),
+        };
+
+        if let Err(e) = save.save() {
+            println!("Could not save the game: {}", e);
+        }
+    }
+
+    /// Records the scores of the players once the game is over, which also
+    /// ends the saved campaign.
+    fn save_scores(&self) {
+        if self.mode == Mode::Story {
+            SaveState::delete();
+        }
+
+        let mut scores = HighScores::load(SCORES_PATH);
+        for player in &self.players {
+            scores.add(Entry {
+                score: player.score,
+                difficulty: self.settings.difficulty,
+                adaptive: self.settings.adaptive,
+                seed: match self.mode {
+                    Mode::Story => None,
+                    Mode::Endless(seed) => Some(seed),
+                },
+            });
+        }
+
+        if let Err(e) = scores.save(SCORES_PATH) {
+            println!("Could not save the high scores: {}", e);
+        }
+    }
+}
+
+/// The parts of a stage, apart from the fight itself.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Transition {
+    /// The title of the stage is shown while the screen fades in.
+    Intro,
+    Playing,
+    /// The ships warp away, and the screen fades out.
+    Outro,
+}
+
+impl Snapshot for Transition {
+    fn snapshot(&self) -> Value {
+        let name = match *self {
+            Transition::Intro => "intro",
+            Transition::Playing => "playing",
+            Transition::Outro => "outro",
+        };
+        Value::String(name.to_string())
+    }
+
+    fn restore(value: &Value) -> Result<Transition, String> {
+        match value.as_str() {
+            Some("intro") => Ok(Transition::Intro),
+            Some("playing") => Ok(Transition::Playing),
+            Some("outro") => Ok(Transition::Outro),
+            _ => Err("unknown transition".to_string()),
+        }
+    }
+}
+
+/// What the timers of a game do when they fire.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Event {
+    /// Leaves the game to show the high scores.
+    ShowScores,
+    /// The flash of the last smart bomb faded out.
+    FlashOver,
+}
+
+impl Snapshot for Event {
+    fn snapshot(&self) -> Value {
+        let name = match *self {
+            Event::ShowScores => "show_scores",
+            Event::FlashOver => "flash_over",
+        };
+        Value::String(name.to_string())
+    }
+
+    fn restore(value: &Value) -> Result<Event, String> {
+        match value.as_str() {
+            Some("show_scores") => Ok(Event::ShowScores),
+            Some("flash_over") => Ok(Event::FlashOver),
+            _ => Err("unknown event".to_string()),
+        }
+    }
+}
+
+/// The commands of the debug console.
+#[derive(Clone, Debug, PartialEq)]
+enum Cheat {
+    /// Spawns enemies of a kind on the right of the screen.
+    Spawn(String, usize),
+    Lives(u32),
+    /// Sets whether the players can be hit, or toggles it.
+    God(Option<bool>),
+    /// Jumps to a stage of the campaign, counted from 1.
+    Stage(usize),
+    TimeScale(f64),
+    /// Shows the debug layer, or hides it.
+    Debug(Option<bool>),
+}
+
+fn cheats() -> Commands<Cheat> {
+    Commands::new()
+        .add("spawn", "<enemy> [count]", "Spawns enemies of the stage",
+             |args| Ok(Cheat::Spawn(console::arg(args, 0, None)?, console::arg(args, 1, Some(1))?)))
+        .add("lives", "<count>", "Sets the lives of every player",
+             |args| console::arg(args, 0, None).map(Cheat::Lives))
+        .add("god", "[on|off]", "Makes the players invulnerable",
+             |args| console::switch(args).map(Cheat::God))
+        .add("stage", "<number>", "Jumps to a stage of the campaign",
+             |args| console::arg(args, 0, None).map(Cheat::Stage))
+        .add("timescale", "<scale>", "Speeds the game up or slows it down",
+             |args| console::arg(args, 0, None).map(Cheat::TimeScale))
+        .add("debug", "[on|off]", "Shows colliders, paths and spawn points",
+             |args| console::switch(args).map(Cheat::Debug))
+}
+
+/// What happens during a stage, apart from how it looks: this is what
+/// snapshots save, and it can be played without a renderer.
+struct World {
+    run: Run,
+    transition: StateMachine<Transition>,
+    level: LevelRunner,
+    kinds: LevelKinds,
+    enemies: Vec<Enemy>,
+    boss: Option<Boss>,
+    bullets: BulletPool,
+    pickups: Vec<Pickup>,
+    /// Decides which enemies drop pickups, and which ones.
+    rng: Rng,
+    /// Set while the flash of the last smart bomb fades out.
+    flash: Option<TimerId>,
+    /// Whether every player lost their last life.
+    game_over: bool,
+    timers: Scheduler<Event>,
+}
+
+impl World {
+    /// Starts the stage run by `level`, whose enemies are of the given
+    /// `kinds`.
+    fn new(mut run: Run, level: LevelRunner, kinds: LevelKinds, seed: u64) -> World {
+        for player in &mut run.players {
+            player.start_stage();
+        }
+
+        World {
+            run: run,
+            transition: StateMachine::new(Transition::Intro),
+            level: level,
+            kinds: kinds,
+            enemies: vec![],
+            boss: None,
+            bullets: BulletPool::new(MAX_BULLETS),
+            pickups: vec![],
+            rng: Rng::new(seed),
+            flash: None,
+            game_over: false,
+            timers: Scheduler::new(),
+        }
+    }
+
+    /// Saves the whole state of the stage, so that it can be restored
+    /// exactly as it was.
+    fn snapshot(&self) -> Table {
+        let snapshot = table(vec![
+            ("settings", self.run.settings.snapshot()),
+            ("mode", self.run.mode.snapshot()),
+            ("stage", self.run.stage.snapshot()),
+            ("adaptive", self.run.adaptive.snapshot()),
+            ("players", Value::Array(self.run.players.iter().map(Ship::snapshot).collect())),
+            ("transition", self.transition.snapshot()),
+            ("level", self.level.snapshot()),
+            ("enemies", Value::Array(self.enemies.iter().map(Enemy::snapshot).collect())),
+            ("boss", Value::Array(self.boss.iter().map(Boss::snapshot).collect())),
+            ("bullets", self.bullets.snapshot()),
+            ("pickups", self.pickups.snapshot()),
+            ("rng", self.rng.snapshot()),
+            ("flash", self.flash.snapshot()),
+            ("game_over", self.game_over.snapshot()),
+            ("timers", self.timers.snapshot()),
+        ]);
+
+        match snapshot {
+            Value::Table(table) => table,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Restores what changes during the stage from a snapshot of the same
+    /// stage, played by as many players.
+    fn restore(&mut self, snapshot: &Table) -> Result<(), String> {
+        let snapshot = Value::Table(snapshot.clone());
+        let players: Vec<Value> = field(&snapshot, "players")?;
+        if players.len() != self.run.players.len() {
+            return Err(format!("players: expected {} players", self.run.players.len()));
+        }
+
+        for (player, saved) in self.run.players.iter_mut().zip(players.iter()) {
+            player.restore(saved).map_err(|e| format!("players: {}", e))?;
+        }
+
+        self.level.restore(&field(&snapshot, "level")?).map_err(|e| format!("level: {}", e))?;
+
+        let enemies: Vec<Value> = field(&snapshot, "enemies")?;
+        self.enemies.clear();
+        for enemy in &enemies {
+            let name: String = field(enemy, "kind")?;
+            let kind = self.kinds.kind(&name).ok_or(format!("enemies: unknown enemy `{}`", name))?;
+            self.enemies.push(Enemy::restore(enemy, kind).map_err(|e| format!("enemies: {}", e))?);
+        }
+
+        let boss: Option<Value> = field(&snapshot, "boss")?;
+        self.boss = match boss {
+            Some(boss) => {
+                let kind = self.kinds.boss_kind().ok_or("boss: the level has no boss")?;
+                Some(Boss::restore(&boss, kind).map_err(|e| format!("boss: {}", e))?)
+            },
+            None => None,
+        };
+
+        self.transition = field(&snapshot, "transition")?;
+        self.bullets = field(&snapshot, "bullets")?;
+        self.pickups = field(&snapshot, "pickups")?;
+        self.rng = field(&snapshot, "rng")?;
+        self.flash = field(&snapshot, "flash")?;
+        self.game_over = field(&snapshot, "game_over")?;
+        self.timers = field(&snapshot, "timers")?;
+        Ok(())
+    }
+
+    /// The multipliers applied to the enemies which spawn now.
+    fn scaling(&self) -> Scaling {
+        let scaling = self.run.settings.difficulty.scaling();
+        match self.run.adaptive {
+            Some(ref adaptive) => scaling.with_intensity(adaptive.intensity()),
+            None => scaling,
+        }
+    }
+
+    /// The fraction of the players' bullets which hit something.
+    fn accuracy(&self) -> f64 {
+        let shots: u32 = self.run.players.iter().map(|player| player.shots).sum();
+        let hits: u32 = self.run.players.iter().map(|player| player.hits).sum();
+        if shots == 0 { 0.0 } else { hits as f64 / shots as f64 }
+    }
+
+    /// Returns the center of the living player's ship closest to `point`,
+    /// which enemies aim for.
+    fn closest_player(&self, point: Vec2) -> Vec2 {
+        self.run.players.iter()
+            .filter(|player| player.lives > 0)
+            .map(|player| player.pos.center())
+            .fold(None, |closest: Option<Vec2>, center| match closest {
+                Some(closest) if closest.distance(point) <= center.distance(point) => Some(closest),
+                _ => Some(center),
+            })
+            .unwrap_or_else(|| self.run.players[0].pos.center())
+    }
+}
+
+pub struct ShipView {
+    world: World,
+    /// Whether the current stage is the last one.
+    last_stage: bool,
+    /// The sprites of the level.
+    assets: LevelAssets,
+    /// The sprites of every player's ship, one for each stance.
+    ships: Vec<Vec<Sprite>>,
+    hud: Hud,
+    /// The seed of the pickups, kept to record the game.
+    seed: u64,
+    /// Set while the attract mode plays a demo, whose inputs replace those
+    /// of the players.
+    demo: Option<Playback>,
+    /// Set while the game is recorded, with a debug hotkey.
+    recording: Option<Recording>,
+    /// The commands of the debug console, and what they changed.
+    commands: Commands<Cheat>,
+    god: bool,
+}
+
+impl ShipView {
+    /// Starts a game for 1 or 2 `players`, who share the enemies but have
+    /// their own lives, scores and weapons.
+    pub fn new(phi: &mut Phi, players: usize, settings: Settings, mode: Mode) -> Result<ShipView, String> {
+        let lives = settings.difficulty.scaling().lives;
+        let players = ShipView::ships(phi, players, lives)?;
+
+        ShipView::stage(phi, Run {
+            players: players,
+            settings: settings,
+            mode: mode,
+            adaptive: if settings.adaptive { Some(Adaptive::new()) } else { None },
+            stage: 0,
+        })
+    }
+
+    /// Continues a campaign from the stage it was saved at.
+    pub fn resume(phi: &mut Phi, save: SaveState) -> Result<ShipView, String> {
+        let mut players = ShipView::ships(phi, save.players.len(), 0)?;
+        for (player, saved) in players.iter_mut().zip(save.players.iter()) {
+            player.score = saved.score;
+            player.lives = saved.lives;
+            player.bombs = saved.bombs;
+            player.weapon = saved.weapon;
+        }
+
+        ShipView::stage(phi, Run {
+            players: players,
+            settings: save.settings,
+            mode: Mode::Story,
+            adaptive: if save.settings.adaptive { Some(Adaptive::new()) } else { None },
+            stage: save.stage,
+        })
+    }
+
+    /// Loads the sprites of the ships of `count` players. The spritesheet
+    /// contains a 3x3 grid with all the stances, and the second ship is
+    /// tinted to tell them apart.
+    fn ship_sprites(phi: &mut Phi, count: usize) -> Result<Vec<Vec<Sprite>>, String> {
+        let spritesheet = Sprite::load_with_mask(&phi.renderer, "assets/spaceship.png")?;
+        let (w, h) = spritesheet.size();
+        let w = w / 3.0;
+        let h = h / 3.0;
+
+        Ok((0..count).map(|id| {
+            let spritesheet = if id > 0 { spritesheet.tinted(Color::RGB(150, 255, 170)) } else { spritesheet.clone() };
+
+            let mut sprites = Vec::with_capacity(9);
+            for y in 0..3 {
+                for x in 0..3 {
+                    sprites.push(spritesheet.region(Rectangle {
+                        w: w,
+                        h: h,
+                        x: w * x as f64,
+                        y: h * y as f64,
+                    }).unwrap());
+                }
+            }
+            sprites
+        }).collect())
+    }
+
+    /// Creates the ships of `count` players, as large as their sprites.
+    fn ships(phi: &mut Phi, count: usize, lives: u32) -> Result<Vec<Ship>, String> {
+        let player_bounds = Rectangle {
+            x: 0.0,
+            y: 0.0,
+            w: phi.output_size().0 * 0.70,
+            h: phi.output_size().1
+        };
+
+        let controls = [Controls::player1(), Controls::player2()];
+        let sprites = ShipView::ship_sprites(phi, count.min(controls.len()))?;
+        Ok(sprites.iter().enumerate()
+            .map(|(id, stances)| {
+                let masks = stances.iter().map(|sprite| sprite.mask().cloned()).collect();
+                Ship::new(stances[0].size(), masks, player_bounds, id, controls[id], lives)
+            })
+            .collect())
+    }
+
+    /// Starts the current stage of the `run`.
+    pub fn stage(phi: &mut Phi, mut run: Run) -> Result<ShipView, String> {
+        phi.camera.reset();
+        phi.camera.shake_scale = run.settings.shake;
+
+        // Endless runs are entirely decided by their seed, including the
+        // pickups, so that they can be shared.
+        let (level_path, seed, last_stage) = match run.mode {
+            Mode::Story => {
+                let stages = stage::stages()?;
+                let seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+
+                // Saves may outlive stages which were removed since.
+                run.stage = run.stage.min(stages.len() - 1);
+                (stages[run.stage].clone(), seed, run.stage + 1 >= stages.len())
+            },
+            Mode::Endless(seed) => (ENDLESS_PATH.to_string(), seed, true),
+        };
+        let level = Level::load(&level_path)?;
+        let assets = LevelAssets::load(phi, &level).map_err(|e| format!("{}: {}", level_path, e))?;
+        let kinds = assets.kinds(&level);
+        let level = match run.mode {
+            Mode::Story => LevelRunner::new(level),
+            Mode::Endless(seed) => LevelRunner::endless(level, seed),
+        };
+        let ships = ShipView::ship_sprites(phi, run.players.len())?;
+
+        Ok(ShipView {
+            world: World::new(run, level, kinds, seed),
+            last_stage: last_stage,
+            assets: assets,
+            ships: ships,
+            hud: Hud::new(),
+            seed: seed,
+            demo: None,
+            recording: None,
+            commands: cheats(),
+            god: false,
+        })
+    }
+
+    /// Plays a recorded game, for the attract mode.
+    pub fn demo(phi: &mut Phi, recording: Recording) -> Result<ShipView, String> {
+        let mut view = ShipView::new(phi, recording.players, recording.settings, Mode::Story)?;
+        view.world.rng = Rng::new(recording.seed);
+        view.seed = recording.seed;
+        view.demo = Some(Playback::new(recording));
+        Ok(view)
+    }
+}
+
+impl ShipView {
+    /// Saves the whole state of the game, so that it can be restored exactly
+    /// as it was, along with the `camera`.
+    pub fn snapshot(&self, camera: &Camera) -> Table {
+        let mut snapshot = self.world.snapshot();
+        snapshot.insert("camera".to_string(), camera.snapshot());
+        snapshot.insert("background".to_string(), self.assets.background.scrolls().snapshot());
+        snapshot
+    }
+
+    /// Starts the game again from a snapshot. The assets of the stage are
+    /// loaded again, so the level files must not have changed since.
+    pub fn restore(phi: &mut Phi, snapshot: &Table) -> Result<ShipView, String> {
+        let value = Value::Table(snapshot.clone());
+        let settings: Settings = field(&value, "settings")?;
+        let players: Vec<Value> = field(&value, "players")?;
+        if players.is_empty() {
+            return Err("`players` must not be empty".to_string());
+        }
+
+        let ships = ShipView::ships(phi, players.len(), 0)?;
+        let mut view = ShipView::stage(phi, Run {
+            players: ships,
+            settings: settings,
+            mode: field(&value, "mode")?,
+            adaptive: field(&value, "adaptive")?,
+            stage: field(&value, "stage")?,
+        })?;
+
+        // How far the background scrolled is kept with its sprites.
+        view.world.restore(snapshot)?;
+        let scrolls: Vec<f64> = field(&value, "background")?;
+        view.assets.background.set_scrolls(&scrolls).map_err(|e| format!("background: {}", e))?;
+        phi.camera = field(&value, "camera")?;
+        phi.camera.shake_scale = settings.shake;
+        Ok(view)
+    }
+
+    /// Starts recording a new game, or saves the one being recorded to
+    /// `RECORDING_PATH`.
+    fn toggle_recording(&mut self, phi: &mut Phi) -> ViewAction {
+        match self.recording.take() {
+            Some(recording) => {
+                match recording.save(RECORDING_PATH) {
+                    Ok(()) => println!("Saved {:.1} seconds of recording to {}", recording.duration(), RECORDING_PATH),
+                    Err(e) => println!("Could not save the recording: {}", e),
+                }
+                ViewAction::None
+            },
+            None => {
+                let mut view = ShipView::new(phi, self.world.run.players.len(), self.world.run.settings, Mode::Story);
+                if let Ok(ref mut view) = view {
+                    view.recording = Some(Recording::new(view.seed, self.world.run.settings, self.world.run.players.len()));
+                }
+                play(phi, view)
+            },
+        }
+    }
+
+    /// Applies a command typed in the debug console.
+    fn cheat(&mut self, phi: &mut Phi, cheat: Cheat) -> ViewAction {
+        match cheat {
+            Cheat::Spawn(name, count) => {
+                let kind = match self.world.kinds.kind(&name) {
+                    Some(kind) => kind,
+                    None => {
+                        let names = self.world.kinds.kind_names().join(", ");
+                        phi.console.print(&format!("Unknown enemy `{}`, this stage has: {}", name, names));
+                        return ViewAction::None;
+                    },
+                };
+
+                // Spread vertically, just off the screen
+                let visible = phi.camera.visible();
+                let scaling = self.world.scaling();
+                for i in 0..count {
+                    let origin = Vec2::new(visible.x + visible.w + kind.size.0,
+                                           visible.y + visible.h * (i + 1) as f64 / (count + 1) as f64);
+                    let movement = Movement::new(Pattern::Straight { direction: Vec2::new(-1.0, 0.0) }, kind.speed, origin);
+                    let mut enemy = Enemy::new(kind.clone(), movement);
+                    enemy.scale(scaling);
+                    self.world.enemies.push(enemy);
+                }
+            },
+
+            Cheat::Lives(lives) => {
+                if lives == 0 {
+                    phi.console.print("lives: there must be at least one");
+                    return ViewAction::None;
+                }
+                for player in &mut self.world.run.players {
+                    player.lives = lives;
+                }
+            },
+
+            Cheat::God(on) => {
+                self.god = on.unwrap_or(!self.god);
+                phi.console.print(&format!("God mode {}", if self.god { "on" } else { "off" }));
+            },
+
+            Cheat::Stage(number) => {
+                let count = stage::stages().map(|stages| stages.len()).unwrap_or(0);
+                if self.world.run.mode != Mode::Story {
+                    phi.console.print("stage: only the campaign has stages");
+                } else if number == 0 || number > count {
+                    phi.console.print(&format!("stage: there are {} stages", count));
+                } else {
+                    let mut run = self.world.run.take();
+                    run.stage = number - 1;
+                    let view = ShipView::stage(phi, run);
+                    return play(phi, view);
+                }
+            },
+
+            Cheat::TimeScale(scale) => {
+                if let Err(e) = phi.time.set_scale(scale) {
+                    phi.console.print(&format!("timescale: {}", e));
+                }
+            },
+
+            Cheat::Debug(on) => phi.debug.enabled = on.unwrap_or(!phi.debug.enabled),
+        }
+
+        ViewAction::None
+    }
+
+    /// Shows what the game is doing on the debug layer: everything which can
+    /// collide, the region the players cannot leave, where the enemies are
+    /// heading and where the next ones will appear.
+    fn debug_draw(&self, phi: &mut Phi) {
+        let debug = &mut phi.debug;
+
+        let red = Color::RGB(255, 60, 60);
+        let enemies = self.world.enemies.iter().map(Enemy::hitbox).chain(self.world.boss.iter().map(Boss::hitbox));
+        for hitbox in enemies {
+            for shape in hitbox.shapes() {
+                debug.shape(shape, red);
+            }
+        }
+
+        for enemy in &self.world.enemies {
+            let center = enemy.pos.center();
+            let player = self.world.closest_player(center);
+            debug.path(enemy.path(DEBUG_PATH, player), Color::RGB(255, 140, 60));
+            debug.line(center, center + enemy.velocity(player) * DEBUG_VELOCITY, Color::RGB(255, 255, 255));
+        }
+
+        for (pos, kind, left) in self.world.level.upcoming_spawns(DEBUG_SPAWNS) {
+            let color = Color::RGB(200, 80, 255);
+            debug.circle(pos, 6.0, color);
+            debug.label(pos + Vec2::new(8.0, -6.0), &format!("{} {:.0}s", kind, left.ceil()), color);
+        }
+
+        let yellow = Color::RGB(255, 255, 60);
+        for bullet in self.world.bullets.iter() {
+            debug.shape(&bullet.hitbox(), yellow);
+            debug.line(bullet.pos, bullet.pos + bullet.vel * DEBUG_VELOCITY, yellow);
+        }
+
+        let green = Color::RGB(60, 255, 60);
+        for pickup in &self.world.pickups {
+            debug.shape(&pickup.hitbox(), green);
+        }
+
+        let blue = Color::RGB(60, 200, 255);
+        for player in self.world.run.players.iter().filter(|player| player.lives > 0) {
+            for shape in player.hitbox().shapes() {
+                debug.shape(shape, blue);
+            }
+        }
+
+        if let Some(player) = self.world.run.players.first() {
+            let bounds = player.bounds();
+            debug.rect(bounds, Color::RGB(120, 120, 120));
+            debug.label(bounds.origin() + Vec2::new(4.0, 4.0), "PLAYER BOUNDS", Color::RGB(120, 120, 120));
+        }
+    }
+
+    /// Saves the game to `QUICKSAVE_PATH`.
+    fn quick_save(&self, phi: &mut Phi) {
+        match toml::save(QUICKSAVE_PATH, &self.snapshot(&phi.camera)) {
+            Ok(()) => phi.console.print(&format!("Saved the game to {}", QUICKSAVE_PATH)),
+            Err(e) => phi.console.print(&format!("Could not save the game: {}", e)),
+        }
+    }
+
+    /// Loads the game saved by `quick_save`, if any.
+    fn quick_load(phi: &mut Phi) -> Option<ShipView> {
+        match toml::load(QUICKSAVE_PATH).and_then(|snapshot| ShipView::restore(phi, &snapshot)) {
+            Ok(view) => Some(view),
+            Err(e) => {
+                phi.console.print(&format!("Could not load the game: {}", e));
+                None
+            },
+        }
+    }
+
+    /// Applies the effects of the bullets and of the enemies hitting things.
+    fn collide(&mut self, phi: &mut Phi) {
+        // The players collect the pickups they fly into.
+        for player in self.world.run.players.iter_mut().filter(|player| player.lives > 0) {
+            let player_hitbox = player.hitbox();
+            let (collected, left): (Vec<Pickup>, Vec<Pickup>) = self.world.pickups.drain(..)
+                .partition(|pickup| player_hitbox.overlaps_shape(&pickup.hitbox()));
+            self.world.pickups = left;
+
+            for pickup in collected {
+                player.power_up(pickup.kind);
+            }
+        }
+
+        // The players' bullets damage the enemies and the boss.
+        let hitboxes: Vec<Hitbox> = self.world.enemies.iter().map(Enemy::hitbox).collect();
+        let boss_was_alive = self.world.boss.as_ref().is_some_and(Boss::is_alive);
+        {
+            let enemies = &mut self.world.enemies;
+            let boss = &mut self.world.boss;
+            let players = &mut self.world.run.players;
+            self.world.bullets.collide(|bullet| {
+                let player = match bullet.owner {
+                    Owner::Player(player) => player,
+                    Owner::Enemy => return false,
+                };
+
+                let shape = bullet.hitbox();
+                if let Some(ref mut boss) = *boss {
+                    if boss.hit(&shape, bullet.damage) {
+                        boss.last_hit = Some(player);
+                        players[player].hits += 1;
+                        return true;
+                    }
+                }
+
+                for (enemy, hitbox) in enemies.iter_mut().zip(hitboxes.iter()) {
+                    if enemy.health > 0.0 && hitbox.overlaps_shape(&shape) {
+                        enemy.health -= bullet.damage;
+                        enemy.last_hit = Some(player);
+                        players[player].hits += 1;
+                        return true;
+                    }
+                }
+
+                false
+            });
+        }
+
+        // The enemies' bullets and hulls damage the players, unless they
+        // cheat.
+        let god = self.god;
+        for player in self.world.run.players.iter_mut().filter(|player| player.lives > 0) {
+            let player_hitbox = player.hitbox();
+            let mut player_hit = false;
+
+            self.world.bullets.collide(|bullet| {
+                let hit = bullet.owner == Owner::Enemy && player_hitbox.overlaps_shape(&bullet.hitbox());
+                player_hit = player_hit || hit;
+                hit
+            });
+
+            for (enemy, hitbox) in self.world.enemies.iter_mut().zip(hitboxes.iter()) {
+                if enemy.health > 0.0 && player_hitbox.overlaps(hitbox) &&
+                   player.touches(enemy.kind.mask.as_ref(), enemy.pos) {
+                    player_hit = true;
+                    enemy.health = 0.0;
+                    enemy.last_hit = Some(player.id);
+                }
+            }
+
+            // Ramming into a boss does not destroy it, though.
+            if let Some(ref boss) = self.world.boss {
+                player_hit = player_hit || (boss.is_alive() && player_hitbox.overlaps(&boss.hitbox()) &&
+                                            player.touches(boss.kind.enemy.mask.as_ref(), boss.pos));
+            }
+
+            if player_hit && !god && player.hit() {
+                phi.camera.add_trauma(0.6);
+                self.world.bullets.clear(Owner::Enemy);
+
+                if let Some(ref mut adaptive) = self.world.run.adaptive {
+                    adaptive.on_death();
+                }
+            }
+        }
+
+        if !self.world.game_over && self.world.run.players.iter().all(|player| player.lives == 0) {
+            self.world.game_over = true;
+            self.world.timers.after(GAME_OVER_DELAY, Event::ShowScores);
+        }
+
+        // Destroyed enemies give points to whoever destroyed them and
+        // sometimes pickups, and the biggest ones shake the screen.
+        for enemy in self.world.enemies.iter().filter(|enemy| enemy.health <= 0.0) {
+            let player = &mut self.world.run.players[enemy.last_hit.unwrap_or(0)];
+            player.score += enemy.kind.score;
+            player.kills += 1;
+
+            if self.world.rng.next_f64() < enemy.kind.drop {
+                let kind = PickupKind::random(&mut self.world.rng);
+                self.world.pickups.push(Pickup::new(kind, enemy.pos.center()));
+            }
+
+            let size = enemy.pos.w * enemy.pos.h;
+            phi.camera.add_trauma((size / 20_000.0).min(0.5));
+        }
+        self.world.enemies.retain(|enemy| enemy.health > 0.0);
+
+        // The boss shakes the screen by itself while it is dying.
+        if let Some(ref boss) = self.world.boss {
+            if boss_was_alive && !boss.is_alive() {
+                self.world.run.players[boss.last_hit.unwrap_or(0)].score += boss.kind.enemy.score;
+                phi.time.slow_motion(BOSS_SLOW_MOTION, BOSS_SLOW_MOTION_DURATION);
+            }
+        }
+    }
+
+    /// Uses one of the smart bombs of `player`, if any is left: it destroys
+    /// the enemies' bullets, damages every enemy on the screen and protects
+    /// the player for a short while.
+    fn smart_bomb(&mut self, phi: &mut Phi, player: usize) {
+        {
+            let player = &mut self.world.run.players[player];
+            if player.bombs == 0 || player.lives == 0 {
+                return;
+            }
+
+            player.bombs -= 1;
+            if player.left(Effect::Invulnerable) < SMART_BOMB_INVULNERABILITY {
+                player.apply(Effect::Invulnerable, SMART_BOMB_INVULNERABILITY);
+            }
+        }
+        if let Some(flash) = self.world.flash.take() {
+            self.world.timers.cancel(flash);
+        }
+        self.world.flash = Some(self.world.timers.after(SMART_BOMB_FLASH, Event::FlashOver));
+
+        let visible = phi.camera.visible();
+
+        self.world.bullets.clear(Owner::Enemy);
+        for enemy in self.world.enemies.iter_mut().filter(|enemy| enemy.pos.overlaps(visible)) {
+            enemy.health -= SMART_BOMB_DAMAGE;
+            enemy.last_hit = Some(player);
+        }
+
+        if let Some(ref mut boss) = self.world.boss {
+            boss.damage(SMART_BOMB_DAMAGE);
+            boss.last_hit = Some(player);
+        }
+
+        phi.camera.add_trauma(0.5);
+    }
+}
+
+impl View for ShipView {
+    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        // Demos play at the pace they were recorded at, until a player does
+        // anything.
+        let mut elapsed = elapsed;
+        let mut recorded = None;
+        if let Some(ref mut demo) = self.demo {
+            match demo.next() {
+                Some((demo_elapsed, inputs)) if !phi.events.now.any() => {
+                    elapsed = demo_elapsed;
+                    recorded = Some(inputs);
+                },
+                _ => return main_menu(phi),
+            }
+        }
+
+        if phi.events.now.key_escape == Some(true) {
+            return ViewAction::Quit;
+        }
+
+        if cfg!(debug_assertions) {
+            if let Some(cheat) = phi.console.update(&self.commands) {
+                match self.cheat(phi, cheat) {
+                    ViewAction::None => {},
+                    action => return action,
+                }
+            }
+        }
+
+        // Debug hotkeys, to get back to a given moment of the game, and to
+        // record demos.
+        if cfg!(debug_assertions) {
+            if phi.events.now.key_f6 == Some(true) {
+                return self.toggle_recording(phi);
+            }
+
+            if phi.events.now.key_f5 == Some(true) {
+                self.quick_save(phi);
+            }
+
+            if phi.events.now.key_f9 == Some(true) {
+                if let Some(view) = ShipView::quick_load(phi) {
+                    return ViewAction::ChangeView { new_view: Box::new(view) };
+                }
+            }
+        }
+
+        if self.demo.is_some() && (self.world.game_over || self.world.level.is_finished()) {
+            return main_menu(phi);
+        }
+
+        // The timers stand still with the game while it is paused, and move
+        // with it when it is stepped.
+        let frozen = phi.time.is_frozen();
+        if frozen { self.world.timers.pause() } else { self.world.timers.resume() }
+        for player in &mut self.world.run.players {
+            if frozen { player.timers.pause() } else { player.timers.resume() }
+        }
+
+        for event in self.world.timers.update(elapsed) {
+            match event {
+                Event::ShowScores => {
+                    let run = self.world.run.take();
+                    run.save_scores();
+                    return ViewAction::ChangeView {
+                        new_view: Box::new(::views::high_scores::HighScoresView::new(phi, run.mode != Mode::Story))
+                    };
+                },
+                Event::FlashOver => self.world.flash = None,
+            }
+        }
+
+        // Once the level is over, the ships warp away before the stage is
+        // summed up.
+        self.world.transition.update(elapsed);
+        match self.world.transition.state() {
+            Transition::Intro if self.world.transition.time() >= STAGE_INTRO => {
+                self.world.transition.set(Transition::Playing);
+            },
+            Transition::Playing if self.world.level.is_finished() && !self.world.game_over => {
+                self.world.transition.set(Transition::Outro);
+                self.world.bullets.clear(Owner::Enemy);
+            },
+            Transition::Outro if self.world.transition.time() >= STAGE_OUTRO => {
+                return ViewAction::ChangeView {
+                    new_view: Box::new(StageClearView::new(phi, self.world.run.take(), self.last_stage))
+                };
+            },
+            _ => {},
+        }
+
+        let inputs: Vec<Input> = match recorded {
+            Some(inputs) => inputs,
+            // Keys held while typing in the console do not count.
+            None if phi.console.is_open() => vec![Input::default(); self.world.run.players.len()],
+            None => self.world.run.players.iter().map(|player| player.controls.read(&phi.events)).collect(),
+        };
+        if let Some(ref mut recording) = self.recording {
+            recording.record(elapsed, &inputs);
+        }
+
+        for id in 0..self.world.run.players.len() {
+            if self.world.run.players[id].lives == 0 {
+                continue;
+            }
+
+            if self.world.transition.state() == Transition::Outro {
+                let player = &mut self.world.run.players[id];
+                player.pos.x += WARP_ACCELERATION * self.world.transition.time() * elapsed;
+                player.update_stance(1.0, 0.0);
+                continue;
+            }
+
+            let input = inputs.get(id).cloned().unwrap_or_default();
+            self.world.run.players[id].update(input, elapsed, &mut self.world.bullets);
+
+            if input.bomb {
+                self.smart_bomb(phi, id);
+            }
+        }
+        phi.camera.update(elapsed);
+
+        // Update the enemies and the bullets, and forget about those which
+        // left the screen
+        let visible = phi.camera.visible();
+        let alive_area = Rectangle {
+            x: visible.x - ENEMY_MARGIN,
+            y: visible.y - ENEMY_MARGIN,
+            w: visible.w + 2.0 * ENEMY_MARGIN,
+            h: visible.h + 2.0 * ENEMY_MARGIN,
+        };
+
+        // Enemies go for the closest player.
+        let targets: Vec<Vec2> = self.world.enemies.iter().map(|enemy| self.world.closest_player(enemy.pos.center())).collect();
+        for (enemy, &target) in self.world.enemies.iter_mut().zip(targets.iter()) {
+            enemy.update(elapsed, target, visible, &mut self.world.bullets);
+        }
+        self.world.enemies.retain(|enemy| enemy.pos.overlaps(alive_area));
+
+        let boss_target = self.world.boss.as_ref().map(|boss| self.world.closest_player(boss.pos.center()));
+        if let (Some(boss), Some(target)) = (self.world.boss.as_mut(), boss_target) {
+            boss.update(elapsed, target, &mut self.world.bullets, &mut phi.camera);
+        }
+
+        self.world.bullets.update(elapsed, visible);
+
+        for pickup in &mut self.world.pickups {
+            pickup.update(elapsed);
+        }
+        self.world.pickups.retain(|pickup| !pickup.is_expired() && pickup.pos.x > visible.x);
+
+        // Nothing can stop the ships once they warp away.
+        if self.world.transition.state() != Transition::Outro {
+            self.collide(phi);
+        }
+
+        let accuracy = self.world.accuracy();
+        if let Some(ref mut adaptive) = self.world.run.adaptive {
+            adaptive.update(elapsed, accuracy);
+        }
+
+        // New enemies are adapted to the difficulty
+        if self.world.transition.state() == Transition::Playing {
+            let scaling = self.world.scaling();
+            self.world.level.spawn_rate = scaling.spawn_rate;
+            let spawns = self.world.level.update(elapsed, &self.world.enemies, self.world.boss.as_ref().map(Boss::state));
+
+            for spawn in spawns.enemies {
+                let mut enemy = self.world.kinds.enemy(spawn);
+                enemy.scale(scaling);
+                self.world.enemies.push(enemy);
+            }
+
+            if let (true, Some(kind)) = (spawns.boss, self.world.kinds.boss_kind()) {
+                let mut boss = Boss::new(kind);
+                boss.scale(scaling);
+                self.world.boss = Some(boss);
+            }
+        }
+
+        phi.perf.count("enemies", self.world.enemies.len() + self.world.boss.iter().count());
+        phi.perf.count("bullets", self.world.bullets.len());
+        phi.perf.count("pickups", self.world.pickups.len());
+        phi.perf.split();
+
+        // Clear the scene
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        // Render the Backgrounds, which speed up while warping away
+        let scroll = match self.world.transition.state() {
+            Transition::Outro => elapsed * (1.0 + WARP_SCROLL * self.world.transition.time()),
+            _ => elapsed,
+        };
+        self.assets.background.back.render_world(&mut phi.renderer, &phi.camera, scroll);
+        self.assets.background.middle.render_world(&mut phi.renderer, &phi.camera, scroll);
+
+        // Render the enemies, then the ship on top of them
+        if let Some(ref boss) = self.world.boss {
+            boss.render(self.assets.sprite(&boss.kind.enemy), &mut phi.renderer, &phi.camera);
+        }
+
+        for enemy in &self.world.enemies {
+            enemy.render(self.assets.sprite(&enemy.kind), &mut phi.renderer, &phi.camera);
+        }
+
+        for pickup in &self.world.pickups {
+            pickup.render(&mut phi.renderer, &phi.camera);
+        }
+
+        for player in &self.world.run.players {
+            player.render(&self.ships[player.id], &mut phi.renderer, &phi.camera);
+        }
+
+        self.world.bullets.render(&mut phi.renderer, &phi.camera);
+        for player in &self.world.run.players {
+            player.render_hitbox(&mut phi.renderer, &phi.camera);
+        }
+
+        if phi.debug.enabled {
+            self.debug_draw(phi);
+        }
+
+        // Render the foreground
+        self.assets.background.front.render_world(&mut phi.renderer, &phi.camera, scroll);
+
+        // The flash of a smart bomb covers the whole screen, then fades out.
+        if let Some(left) = self.world.flash.and_then(|flash| self.world.timers.remaining(flash)) {
+            cover(phi, Color::RGBA(255, 255, 255, (255.0 * left / SMART_BOMB_FLASH) as u8));
+        }
+
+        // Stages fade in from black, and out to it.
+        let time = self.world.transition.time();
+        let fade = match self.world.transition.state() {
+            Transition::Intro => 1.0 - time / FADE_IN,
+            Transition::Outro => 1.0 - (STAGE_OUTRO - time) / FADE_OUT,
+            Transition::Playing => 0.0,
+        };
+        if fade > 0.0 {
+            cover(phi, Color::RGBA(0, 0, 0, (255.0 * fade.min(1.0)) as u8));
+        }
+
+        // Render the HUD: the first player's status on the left, the
+        // second one's on the right. Scores and statuses pop when they
+        // change.
+        self.hud.update(elapsed);
+        for (player, &anchor) in self.world.run.players.iter().zip([Anchor::TopLeft, Anchor::TopRight].iter()) {
+            let id = |name: &str| format!("{}{}", name, player.id);
+
+            let score = format!("{}P {:06}", player.id + 1, player.score);
+            if self.hud.changed(&id("score"), &score) {
+                self.hud.pop(&id("score"));
+            }
+            self.hud.text(phi, &id("score"), &score, 20, anchor, 0.0);
+
+            let status = format!("LIVES {}  BOMBS {}", player.lives, player.bombs);
+            if self.hud.changed(&id("status"), &status) {
+                self.hud.pop(&id("status"));
+            }
+            self.hud.text(phi, &id("status"), &status, 16, anchor, 1.5);
+
+            let weapon = format!("WEAPON {}", player.weapon.name());
+            self.hud.text(phi, &id("weapon"), &weapon, 16, anchor, 2.5);
+
+            // The active buffs, and the seconds they have left
+            let mut line = 3.5;
+            for &(name, effect) in &[("SHIELD", Effect::Shield), ("BOOST", Effect::Boost)] {
+                let left = player.left(effect);
+                if left > 0.0 {
+                    let buff = format!("{} {}", name, left.ceil());
+                    self.hud.text(phi, &id(name), &buff, 16, anchor, line);
+                    line += 1.0;
+                }
+            }
+        }
+
+        if let Mode::Endless(seed) = self.world.run.mode {
+            let run = format!("WAVE {}  SEED {}", self.world.level.endless_waves(), format_seed(seed));
+            self.hud.text(phi, "run", &run, 16, Anchor::Top, 0.0);
+        }
+
+        if let Some(ref boss) = self.world.boss {
+            let screen = phi.output_size();
+            boss.render_health_bar(&mut phi.renderer, screen);
+        }
+
+        if self.world.transition.state() == Transition::Intro {
+            let title = match self.world.run.mode {
+                Mode::Story => format!("STAGE {}", self.world.run.stage + 1),
+                Mode::Endless(_) => "ENDLESS".to_string(),
+            };
+            let name = self.world.level.level().name.to_uppercase();
+            self.hud.text(phi, "stage_title", &title, 48, Anchor::Center, -0.5);
+            self.hud.text(phi, "stage_name", &name, 24, Anchor::Center, 1.5);
+        }
+
+        if self.world.game_over {
+            self.hud.text(phi, "game_over", "GAME OVER", 48, Anchor::Center, 0.0);
+        }
+
+        if self.demo.is_some() {
+            self.hud.text(phi, "demo", "DEMO - PRESS ANY KEY", 16, Anchor::Top, 0.0);
+        }
+
+        ViewAction::None
+    }
+}
+
+/// Goes back to the main menu, where demos end.
+fn main_menu(phi: &mut Phi) -> ViewAction {
+    ViewAction::ChangeView { new_view: Box::new(::views::main_menu::MainMenuView::new(phi)) }
+}
+
+/// Switches to the game `view`, or goes back to the main menu with the error
+/// which prevented it from starting, such as a broken level file.
+pub fn play(phi: &mut Phi, view: Result<ShipView, String>) -> ViewAction {
+    match view {
+        Ok(view) => ViewAction::ChangeView { new_view: Box::new(view) },
+        Err(e) => ViewAction::ChangeView {
+            new_view: Box::new(::views::main_menu::MainMenuView::with_error(phi, &e))
+        },
+    }
+}
+
+/// Covers the whole screen with `color`, which is usually translucent.
+fn cover(phi: &mut Phi, color: Color) {
+    let (w, h) = phi.output_size();
+    phi.renderer.set_blend_mode(BlendMode::Blend);
+    phi.renderer.set_draw_color(color);
+    gfx::fill_rect(&mut phi.renderer, Rectangle { x: 0.0, y: 0.0, w: w, h: h });
+    phi.renderer.set_blend_mode(BlendMode::None);
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn view_snapshots() {
+        use phi::camera::Camera;
+        use phi::data::{Rectangle, Vec2};
+        use phi::input::{Controls, Input};
+        use views::game::{Effect, Event, Mode, Run, Ship, Transition, World};
+        use views::game::level::{Level, LevelKinds, LevelRunner};
+        use views::game::pickup::{Pickup, PickupKind};
+        use views::settings::Settings;
+
+        // Nothing is rendered, so the sizes of the sprites are made up, and
+        // the ships and enemies have no masks.
+        let world = || {
+            let level = Level::load("assets/levels/1.toml").unwrap();
+            let kinds = LevelKinds::new(&level, |_| ((64.0, 64.0), None));
+            let bounds = Rectangle { x: 0.0, y: 0.0, w: 560.0, h: 600.0 };
+            let controls = [Controls::player1(), Controls::player2()];
+            let players = (0..2)
+                .map(|id| Ship::new((43.0, 39.0), vec![None; 9], bounds, id, controls[id], 3))
+                .collect();
+            let run = Run {
+                players: players,
+                settings: Settings::default(),
+                mode: Mode::Story,
+                adaptive: None,
+                stage: 0,
+            };
+            World::new(run, LevelRunner::new(level), kinds, 42)
+        };
+
+        // Play the start of the stage, with the first player flying up and
+        // shooting.
+        let camera = Camera::new((800.0, 600.0));
+        let visible = camera.visible();
+        let mut played = world();
+        played.transition.set(Transition::Playing);
+        for _ in 0..600 {
+            let elapsed = 1.0 / 60.0;
+            let spawns = played.level.update(elapsed, &played.enemies, None);
+            for spawn in spawns.enemies {
+                let enemy = played.kinds.enemy(spawn);
+                played.enemies.push(enemy);
+            }
+
+            let player = played.run.players[0].pos.center();
+            for enemy in &mut played.enemies {
+                enemy.update(elapsed, player, visible, &mut played.bullets);
+            }
+
+            let input = Input { up: true, fire: true, .. Input::from_bits(0) };
+            played.run.players[0].update(input, elapsed, &mut played.bullets);
+            played.bullets.update(elapsed, visible);
+        }
+
+        played.run.players[1].hit();
+        played.run.players[1].power_up(PickupKind::SpeedBoost);
+        played.pickups.push(Pickup::new(PickupKind::Shield, Vec2::new(300.0, 200.0)));
+        played.rng.next_u64();
+        played.flash = Some(played.timers.after(0.25, Event::FlashOver));
+        played.timers.after(2.0, Event::ShowScores);
+        assert!(!played.enemies.is_empty());
+        assert!(played.bullets.iter().count() > 0);
+
+        let snapshot = played.snapshot();
+        let mut restored = world();
+        restored.restore(&snapshot).unwrap();
+        assert_eq!(restored.snapshot(), snapshot);
+        assert_eq!(restored.enemies.len(), played.enemies.len());
+        assert_eq!(restored.run.players[1].lives, 2);
+        assert_eq!(restored.transition.state(), Transition::Playing);
+        assert!(restored.run.players[1].has(Effect::Boost));
+        assert_eq!(restored.timers.update(2.0), vec![Event::FlashOver, Event::ShowScores]);
+
+        // Snapshots of another number of players cannot be restored.
+        let mut alone = world();
+        alone.run.players.pop();
+        assert!(alone.restore(&snapshot).is_err());
+    }
+
+    #[test]
+    fn ship_effects() {
+        use phi::data::Rectangle;
+        use phi::input::{Controls, Input};
+        use views::game::{Effect, Ship, INVULNERABILITY};
+        use views::game::bullet::BulletPool;
+        use views::game::pickup::PickupKind;
+        use views::game::weapon::WeaponLevel;
+
+        let bounds = Rectangle { x: 0.0, y: 0.0, w: 560.0, h: 600.0 };
+        let mut ship = Ship::new((43.0, 39.0), vec![None; 9], bounds, 0, Controls::player1(), 3);
+        let mut bullets = BulletPool::new(1000);
+        let firing = Input { fire: true, .. Input::default() };
+        let frame = 0.01;
+
+        // The weapon fires at once, then every time it is reloaded, and is
+        // loaded again once the trigger was released long enough.
+        let volley = ship.weapon.volley(0, ship.pos.center(), false).len() as u32;
+        for _ in 0..100 {
+            ship.update(firing, frame, &mut bullets);
+        }
+        assert_eq!(ship.shots, 7 * volley);
+        ship.update(Input::default(), 0.2, &mut bullets);
+        assert!(ship.reload.is_none());
+        ship.update(firing, frame, &mut bullets);
+        assert_eq!(ship.shots, 8 * volley);
+
+        // Hits are ignored for a while, and the shield takes one instead.
+        assert!(ship.hit());
+        assert!(!ship.hit());
+        ship.update(Input::default(), INVULNERABILITY, &mut bullets);
+        assert!(!ship.has(Effect::Invulnerable));
+        ship.power_up(PickupKind::Shield);
+        assert!(!ship.hit());
+        assert!(!ship.has(Effect::Shield));
+        assert_eq!(ship.lives, 2);
+        assert_eq!(ship.left(Effect::Invulnerable), INVULNERABILITY / 2.0);
+
+        // The effects stop while the timers are paused.
+        ship.power_up(PickupKind::SpeedBoost);
+        ship.timers.pause();
+        ship.update(Input::default(), 100.0, &mut bullets);
+        assert!(ship.has(Effect::Boost));
+        ship.timers.resume();
+        ship.update(Input::default(), 100.0, &mut bullets);
+        assert!(!ship.has(Effect::Boost));
+        assert_eq!(ship.weapon, WeaponLevel::Single);
+    }
+}
@@ -0,0 +1,436 @@
+use phi::data::Vec2;
+use phi::snapshot::{field, table, variant, variant_name, Snapshot};
+use phi::toml::Value;
+use std::f64::consts::PI;
+
+/// The number of segments used to approximate Bézier curves.
+const BEZIER_STEPS: usize = 32;
+
+/// The seconds over which the velocity of a movement is measured.
+const VELOCITY_STEP: f64 = 1.0 / 60.0;
+
+/// The ways an enemy can move. Positions are relative to where the enemy
+/// appeared, so that the members of a wave can share the same pattern while
+/// keeping their place in the formation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pattern {
+    /// Goes in a straight line.
+    Straight { direction: Vec2 },
+
+    /// Goes in a straight line while oscillating perpendicularly to it.
+    /// `amplitude` is in pixels and `frequency` in oscillations per second.
+    Sine { direction: Vec2, amplitude: f64, frequency: f64 },
+
+    /// Goes to the left for `delay` seconds, then dives toward where the
+    /// player was at that time, at `dive_speed` pixels per second.
+    Dive { delay: f64, dive_speed: f64 },
+
+    /// Follows a cubic Bézier curve starting where the enemy appeared, then
+    /// keeps going in the same direction.
+    Bezier { controls: [Vec2; 3] },
+
+    /// Goes to `target`, stays there for `hold` seconds, then leaves in the
+    /// `retreat` direction.
+    HoldAndRetreat { target: Vec2, hold: f64, retreat: Vec2 },
+
+    /// Goes through every point, then keeps going in the same direction. If
+    /// `loop_from` is set, it instead goes back to this point once the last
+    /// one is reached.
+    Path { points: Vec<Vec2>, loop_from: Option<usize> },
+}
+
+impl Pattern {
+    /// Enemies come from the right of the screen by default.
+    pub fn default() -> Pattern {
+        Pattern::Straight { direction: Vec2::new(-1.0, 0.0) }
+    }
+}
+
+impl Snapshot for Pattern {
+    fn snapshot(&self) -> Value {
+        match *self {
+            Pattern::Straight { direction } =>
+                variant("straight", vec![("direction", direction.snapshot())]),
+            Pattern::Sine { direction, amplitude, frequency } =>
+                variant("sine", vec![("direction", direction.snapshot()), ("amplitude", amplitude.snapshot()),
+                                     ("frequency", frequency.snapshot())]),
+            Pattern::Dive { delay, dive_speed } =>
+                variant("dive", vec![("delay", delay.snapshot()), ("dive_speed", dive_speed.snapshot())]),
+            Pattern::Bezier { controls } =>
+                variant("bezier", vec![("controls", controls.to_vec().snapshot())]),
+            Pattern::HoldAndRetreat { target, hold, retreat } =>
+                variant("hold_and_retreat", vec![("target", target.snapshot()), ("hold", hold.snapshot()),
+                                                 ("retreat", retreat.snapshot())]),
+            Pattern::Path { ref points, loop_from } =>
+                variant("path", vec![("points", points.snapshot()), ("loop_from", loop_from.snapshot())]),
+        }
+    }
+
+    fn restore(value: &Value) -> Result<Pattern, String> {
+        Ok(match &variant_name(value)?[..] {
+            "straight" => Pattern::Straight { direction: field(value, "direction")? },
+            "sine" => Pattern::Sine {
+                direction: field(value, "direction")?,
+                amplitude: field(value, "amplitude")?,
+                frequency: field(value, "frequency")?,
+            },
+            "dive" => Pattern::Dive { delay: field(value, "delay")?, dive_speed: field(value, "dive_speed")? },
+            "bezier" => {
+                let controls: Vec<Vec2> = field(value, "controls")?;
+                if controls.len() != 3 {
+                    return Err("`controls` must have 3 points".to_string());
+                }
+                Pattern::Bezier { controls: [controls[0], controls[1], controls[2]] }
+            },
+            "hold_and_retreat" => Pattern::HoldAndRetreat {
+                target: field(value, "target")?,
+                hold: field(value, "hold")?,
+                retreat: field(value, "retreat")?,
+            },
+            "path" => Pattern::Path { points: field(value, "points")?, loop_from: field(value, "loop_from")? },
+            other => return Err(format!("unknown pattern `{}`", other)),
+        })
+    }
+}
+
+/// An enemy moving according to a `Pattern`.
+#[derive(Clone, Debug)]
+pub struct Movement {
+    pattern: Pattern,
+    /// Pixels traveled every second.
+    speed: f64,
+    origin: Vec2,
+    /// Seconds since the start of the movement.
+    time: f64,
+    pos: Vec2,
+    /// The direction of the last move, which is kept once a pattern is over.
+    direction: Vec2,
+    /// The index of the point of a `Path` the enemy is heading to.
+    target: usize,
+    /// The arc length of each step of a `Bezier` curve, and their positions.
+    curve: Vec<(f64, Vec2)>,
+}
+
+impl Movement {
+    pub fn new(pattern: Pattern, speed: f64, origin: Vec2) -> Movement {
+        let curve = match pattern {
+            Pattern::Bezier { controls } => {
+                let points = [origin, origin + controls[0], origin + controls[1], origin + controls[2]];
+                let mut curve = vec![(0.0, origin)];
+
+                for step in 1..BEZIER_STEPS + 1 {
+                    let point = bezier(&points, step as f64 / BEZIER_STEPS as f64);
+                    let (length, previous) = curve[step - 1];
+                    curve.push((length + previous.distance(point), point));
+                }
+
+                curve
+            },
+            _ => Vec::new(),
+        };
+
+        Movement {
+            pattern: pattern,
+            speed: speed,
+            origin: origin,
+            time: 0.0,
+            pos: origin,
+            direction: Vec2::new(-1.0, 0.0),
+            target: 0,
+            curve: curve,
+        }
+    }
+
+    pub fn pos(&self) -> Vec2 {
+        self.pos
+    }
+
+    /// Returns the positions the movement goes through during the next
+    /// `duration` seconds, every `step` seconds, starting with the current
+    /// one. The player is assumed to stay still.
+    pub fn predict(&self, duration: f64, step: f64, player: Vec2) -> Vec<Vec2> {
+        let mut movement = self.clone();
+        let mut points = vec![movement.pos];
+        let mut time = 0.0;
+        while time < duration {
+            points.push(movement.update(step, player));
+            time += step;
+        }
+        points
+    }
+
+    /// Returns the current velocity, in pixels per second, measured over a
+    /// short step. The player is assumed to stay still.
+    pub fn velocity(&self, player: Vec2) -> Vec2 {
+        let mut movement = self.clone();
+        (movement.update(VELOCITY_STEP, player) - self.pos) / VELOCITY_STEP
+    }
+
+    /// Moves by `elapsed` seconds and returns the new position. `player` is
+    /// the position of the player, which some patterns aim for.
+    pub fn update(&mut self, elapsed: f64, player: Vec2) -> Vec2 {
+        self.time += elapsed;
+        let distance = self.speed * elapsed;
+
+        match self.pattern {
+            Pattern::Straight { direction } => {
+                self.direction = direction.normalize();
+                self.pos += self.direction * distance;
+            },
+
+            Pattern::Sine { direction, amplitude, frequency } => {
+                let direction = direction.normalize();
+                let wave = (2.0 * PI * frequency * self.time).sin() * amplitude;
+                let previous = self.pos;
+                self.pos = self.origin + direction * self.speed * self.time + direction.perp() * wave;
+                self.direction = (self.pos - previous).normalize();
+            },
+
+            Pattern::Dive { delay, dive_speed } => {
+                if self.time - elapsed < delay {
+                    // Still approaching: lock on the player once the delay is over.
+                    self.direction = Vec2::new(-1.0, 0.0);
+                    self.pos += self.direction * distance;
+
+                    if self.time >= delay {
+                        self.direction = (player - self.pos).normalize();
+                    }
+                } else {
+                    self.pos += self.direction * dive_speed * elapsed;
+                }
+            },
+
+            Pattern::Bezier { .. } => {
+                // The curve is only followed forwards, from its start.
+                let traveled = (self.speed * self.time).max(0.0);
+                let &(total, end) = self.curve.last().unwrap();
+
+                if traveled >= total {
+                    let (_, before_end) = self.curve[BEZIER_STEPS - 1];
+                    self.direction = (end - before_end).normalize();
+                    self.pos = end + self.direction * (traveled - total);
+                } else {
+                    // The first step is at length 0, which `traveled` is not
+                    // below, so `i` is at least 1.
+                    let i = self.curve.iter().position(|&(length, _)| length > traveled)
+                        .unwrap_or(BEZIER_STEPS)
+                        .max(1);
+                    let (l0, p0) = self.curve[i - 1];
+                    let (l1, p1) = self.curve[i];
+                    self.direction = (p1 - p0).normalize();
+                    self.pos = p0.lerp(p1, (traveled - l0) / (l1 - l0));
+                }
+            },
+
+            Pattern::HoldAndRetreat { target, hold, retreat } => {
+                let target = self.origin + target;
+                let to_target = target - self.pos;
+                // Seconds of this frame spent after reaching the target
+                let mut remaining = elapsed;
+
+                if self.target == 0 {
+                    let length = to_target.length();
+                    if length > distance {
+                        self.direction = to_target.normalize();
+                        self.pos += self.direction * distance;
+                        remaining = 0.0;
+                    } else {
+                        // Remember when we arrived, to know when to leave.
+                        if length > 0.0 {
+                            remaining = elapsed - length / self.speed;
+                        }
+                        self.pos = target;
+                        self.target = 1;
+                        self.time = remaining;
+                    }
+                }
+
+                if self.target == 1 {
+                    let retreating = (self.time - hold).max(0.0).min(remaining);
+                    if retreating > 0.0 {
+                        self.direction = retreat.normalize();
+                        self.pos += self.direction * self.speed * retreating;
+                    }
+                }
+            },
+
+            Pattern::Path { ref points, loop_from } => {
+                let mut remaining = distance;
+
+                // Several points might be reached in a single frame.
+                while remaining > 0.0 && self.target < points.len() {
+                    let point = self.origin + points[self.target];
+                    let to_point = point - self.pos;
+                    let length = to_point.length();
+
+                    if length > remaining {
+                        self.direction = to_point.normalize();
+                        self.pos += self.direction * remaining;
+                        remaining = 0.0;
+                    } else {
+                        self.pos = point;
+                        remaining -= length;
+                        self.target += 1;
+
+                        if self.target == points.len() {
+                            if let Some(first) = loop_from {
+                                self.target = first;
+                            }
+                        }
+
+                        // Stop here if looping over a single point.
+                        if length == 0.0 {
+                            break;
+                        }
+                    }
+                }
+
+                self.pos += self.direction * remaining;
+            },
+        }
+
+        self.pos
+    }
+}
+
+/// The curve of a `Bezier` pattern is not saved, since it is computed again
+/// from the pattern.
+impl Snapshot for Movement {
+    fn snapshot(&self) -> Value {
+        table(vec![
+            ("pattern", self.pattern.snapshot()),
+            ("speed", self.speed.snapshot()),
+            ("origin", self.origin.snapshot()),
+            ("time", self.time.snapshot()),
+            ("pos", self.pos.snapshot()),
+            ("direction", self.direction.snapshot()),
+            ("target", self.target.snapshot()),
+        ])
+    }
+
+    fn restore(value: &Value) -> Result<Movement, String> {
+        let mut movement = Movement::new(field(value, "pattern")?, field(value, "speed")?, field(value, "origin")?);
+        movement.time = field(value, "time")?;
+        movement.pos = field(value, "pos")?;
+        movement.direction = field(value, "direction")?;
+        movement.target = field(value, "target")?;
+        Ok(movement)
+    }
+}
+
+/// Returns the point of the cubic Bézier curve defined by `points` at `t`.
+fn bezier(points: &[Vec2; 4], t: f64) -> Vec2 {
+    let u = 1.0 - t;
+    points[0] * (u * u * u) +
+    points[1] * (3.0 * u * u * t) +
+    points[2] * (3.0 * u * t * t) +
+    points[3] * (t * t * t)
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn movement_patterns() {
+        use phi::data::Vec2;
+        use views::game::movement::{Movement, Pattern};
+
+        let origin = Vec2::new(800.0, 300.0);
+        let player = Vec2::new(100.0, 300.0);
+        let close = |a: Vec2, b: Vec2| a.distance(b) < 1e-6;
+
+        let mut straight = Movement::new(Pattern::default(), 100.0, origin);
+        assert!(close(straight.update(0.5, player), Vec2::new(750.0, 300.0)));
+
+        // A sine wave is back on its axis every half period.
+        let mut sine = Movement::new(Pattern::Sine {
+            direction: Vec2::new(-1.0, 0.0), amplitude: 50.0, frequency: 1.0
+        }, 100.0, origin);
+        assert!(close(sine.update(0.25, player), Vec2::new(775.0, 250.0)));
+        assert!(close(sine.update(0.25, player), Vec2::new(750.0, 300.0)));
+
+        // Diving enemies aim for where the player was at the end of the delay.
+        let mut dive = Movement::new(Pattern::Dive { delay: 1.0, dive_speed: 200.0 }, 100.0, origin);
+        assert!(close(dive.update(1.0, Vec2::new(700.0, 400.0)), Vec2::new(700.0, 300.0)));
+        assert!(close(dive.update(0.5, player), Vec2::new(700.0, 400.0)));
+        assert!(close(dive.update(0.5, player), Vec2::new(700.0, 500.0)));
+
+        // A straight Bézier curve
+        let mut bezier = Movement::new(Pattern::Bezier {
+            controls: [Vec2::new(-100.0, 0.0), Vec2::new(-200.0, 0.0), Vec2::new(-300.0, 0.0)]
+        }, 100.0, origin);
+        assert!(close(bezier.update(1.5, player), Vec2::new(650.0, 300.0)));
+        assert!(close(bezier.update(2.0, player), Vec2::new(450.0, 300.0)));
+
+        // Curves are not followed backwards, nor before they start.
+        let mut bezier = Movement::new(Pattern::Bezier {
+            controls: [Vec2::new(-100.0, 0.0), Vec2::new(-200.0, 0.0), Vec2::new(-300.0, 0.0)]
+        }, -100.0, origin);
+        assert!(close(bezier.update(0.0, player), origin));
+        assert!(close(bezier.update(1.0, player), origin));
+
+        // Predictions leave the movement where it was.
+        let path = straight.predict(1.0, 0.5, player);
+        assert_eq!(path.len(), 3);
+        assert!(close(path[2], Vec2::new(650.0, 300.0)));
+        assert!(close(straight.pos(), Vec2::new(750.0, 300.0)));
+        assert!(close(straight.velocity(player), Vec2::new(-100.0, 0.0)));
+        assert!(close(dive.velocity(player), Vec2::new(0.0, 200.0)));
+
+        let mut hold = Movement::new(Pattern::HoldAndRetreat {
+            target: Vec2::new(-100.0, 0.0), hold: 1.0, retreat: Vec2::new(1.0, 0.0)
+        }, 100.0, origin);
+        assert!(close(hold.update(1.0, player), Vec2::new(700.0, 300.0)));
+        assert!(close(hold.update(0.5, player), Vec2::new(700.0, 300.0)));
+        assert!(close(hold.update(0.75, player), Vec2::new(725.0, 300.0)));
+        assert!(close(hold.update(0.5, player), Vec2::new(775.0, 300.0)));
+
+        // The rest of the frame in which the target is reached counts
+        // towards the hold.
+        let mut hold = Movement::new(Pattern::HoldAndRetreat {
+            target: Vec2::new(-100.0, 0.0), hold: 1.0, retreat: Vec2::new(1.0, 0.0)
+        }, 100.0, origin);
+        assert!(close(hold.update(1.5, player), Vec2::new(700.0, 300.0)));
+        assert!(close(hold.update(0.75, player), Vec2::new(725.0, 300.0)));
+
+        // Paths can reach several points in a single frame, and loop.
+        let mut path = Movement::new(Pattern::Path {
+            points: vec![Vec2::new(-100.0, 0.0), Vec2::new(-100.0, 100.0), Vec2::new(-100.0, 0.0)],
+            loop_from: Some(1),
+        }, 100.0, origin);
+        assert!(close(path.update(1.5, player), Vec2::new(700.0, 350.0)));
+        assert!(close(path.update(1.0, player), Vec2::new(700.0, 350.0)));
+        assert!(close(path.update(1.0, player), Vec2::new(700.0, 350.0)));
+    }
+
+    #[test]
+    fn movement_snapshots() {
+        use phi::data::Vec2;
+        use phi::snapshot::{field, table, Snapshot};
+        use phi::toml::{self, Value};
+        use views::game::movement::{Movement, Pattern};
+
+        let origin = Vec2::new(800.0, 300.0);
+        let player = Vec2::new(100.0, 250.0);
+        let patterns = vec![
+            Pattern::Sine { direction: Vec2::new(-1.0, 0.2), amplitude: 60.0, frequency: 0.7 },
+            Pattern::Dive { delay: 0.5, dive_speed: 300.0 },
+            Pattern::Bezier { controls: [Vec2::new(-300.0, 0.0), Vec2::new(-300.0, 200.0), Vec2::new(0.0, 200.0)] },
+            Pattern::HoldAndRetreat { target: Vec2::new(-150.0, 0.0), hold: 0.4, retreat: Vec2::new(1.0, 1.0) },
+            Pattern::Path { points: vec![Vec2::new(-50.0, 0.0), Vec2::new(-50.0, 50.0)], loop_from: Some(0) },
+        ];
+
+        for pattern in patterns {
+            let mut movement = Movement::new(pattern, 110.0, origin);
+            movement.update(0.9, player);
+
+            // Restored from a document, including the curve of Bézier patterns
+            let text = toml::to_string(table(vec![("movement", movement.snapshot())]).as_table().unwrap()).unwrap();
+            let read = Value::Table(toml::parse(&text).unwrap());
+            let mut restored: Movement = field(&read, "movement").unwrap();
+
+            for _ in 0..30 {
+                assert_eq!(movement.update(0.07, player), restored.update(0.07, player));
+            }
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use phi::camera::Camera;
+use phi::data::{Circle, Rectangle, Shape, Vec2};
+use phi::gfx;
+use phi::rng::Rng;
+use phi::snapshot::{field, table, Snapshot};
+use phi::toml::Value;
+use sdl2::pixels::Color;
+use sdl2::render::Renderer;
+
+/// Seconds during which a pickup can be collected.
+const LIFETIME: f64 = 10.0;
+
+/// Pixels traveled to the left by pickups every second.
+const DRIFT_SPEED: f64 = 60.0;
+
+const SIZE: f64 = 20.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PickupKind {
+    /// Upgrades the weapon of the player's ship.
+    WeaponUp,
+    /// Absorbs hits for a while.
+    Shield,
+    /// Makes the ship faster for a while.
+    SpeedBoost,
+    /// Gives a smart bomb to the player.
+    SmartBomb,
+    ExtraLife,
+}
+
+impl PickupKind {
+    /// Picks a kind at random, the most useful ones being the rarest.
+    pub fn random(rng: &mut Rng) -> PickupKind {
+        let weights = [
+            (PickupKind::WeaponUp, 40.0),
+            (PickupKind::Shield, 18.0),
+            (PickupKind::SpeedBoost, 18.0),
+            (PickupKind::SmartBomb, 16.0),
+            (PickupKind::ExtraLife, 8.0),
+        ];
+
+        let total: f64 = weights.iter().map(|&(_, weight)| weight).sum();
+        let mut roll = rng.range(0.0, total);
+
+        for &(kind, weight) in &weights {
+            if roll < weight {
+                return kind;
+            }
+            roll -= weight;
+        }
+
+        PickupKind::WeaponUp
+    }
+
+    fn color(self) -> Color {
+        match self {
+            PickupKind::WeaponUp => Color::RGB(255, 160, 30),
+            PickupKind::Shield => Color::RGB(60, 200, 255),
+            PickupKind::SpeedBoost => Color::RGB(80, 230, 80),
+            PickupKind::SmartBomb => Color::RGB(240, 50, 50),
+            PickupKind::ExtraLife => Color::RGB(255, 120, 220),
+        }
+    }
+}
+
+impl Snapshot for PickupKind {
+    fn snapshot(&self) -> Value {
+        let name = match *self {
+            PickupKind::WeaponUp => "weapon_up",
+            PickupKind::Shield => "shield",
+            PickupKind::SpeedBoost => "speed_boost",
+            PickupKind::SmartBomb => "smart_bomb",
+            PickupKind::ExtraLife => "extra_life",
+        };
+        Value::String(name.to_string())
+    }
+
+    fn restore(value: &Value) -> Result<PickupKind, String> {
+        match value.as_str() {
+            Some("weapon_up") => Ok(PickupKind::WeaponUp),
+            Some("shield") => Ok(PickupKind::Shield),
+            Some("speed_boost") => Ok(PickupKind::SpeedBoost),
+            Some("smart_bomb") => Ok(PickupKind::SmartBomb),
+            Some("extra_life") => Ok(PickupKind::ExtraLife),
+            _ => Err("unknown pickup".to_string()),
+        }
+    }
+}
+
+/// A power-up dropped by a destroyed enemy, which the player collects by
+/// flying into it.
+pub struct Pickup {
+    pub kind: PickupKind,
+    /// The center of the pickup.
+    pub pos: Vec2,
+    /// Seconds since it was dropped.
+    age: f64,
+}
+
+impl Pickup {
+    pub fn new(kind: PickupKind, pos: Vec2) -> Pickup {
+        Pickup {
+            kind: kind,
+            pos: pos,
+            age: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, elapsed: f64) {
+        self.age += elapsed;
+        self.pos.x -= DRIFT_SPEED * elapsed;
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.age >= LIFETIME
+    }
+
+    pub fn hitbox(&self) -> Shape {
+        Shape::Circle(Circle { x: self.pos.x, y: self.pos.y, r: SIZE / 2.0 })
+    }
+
+    pub fn render(&self, renderer: &mut Renderer, camera: &Camera) {
+        // Blink when about to disappear
+        if LIFETIME - self.age < 2.0 && (self.age * 8.0) as u32 % 2 == 1 {
+            return;
+        }
+
+        let rect = Rectangle::from_center(self.pos, SIZE, SIZE);
+        renderer.set_draw_color(Color::RGB(255, 255, 255));
+        gfx::fill_rect(renderer, camera.to_screen(rect));
+        renderer.set_draw_color(self.kind.color());
+        gfx::fill_rect(renderer, camera.to_screen(rect.scale(0.7)));
+    }
+}
+
+impl Snapshot for Pickup {
+    fn snapshot(&self) -> Value {
+        table(vec![
+            ("kind", self.kind.snapshot()),
+            ("pos", self.pos.snapshot()),
+            ("age", self.age.snapshot()),
+        ])
+    }
+
+    fn restore(value: &Value) -> Result<Pickup, String> {
+        Ok(Pickup {
+            kind: field(value, "kind")?,
+            pos: field(value, "pos")?,
+            age: field(value, "age")?,
+        })
+    }
+}
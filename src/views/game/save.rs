@@ -0,0 +1,161 @@
+use phi::toml::{self, Table, Value};
+use std::fs;
+use std::path::Path;
+use views::game::weapon::WeaponLevel;
+use views::settings::Settings;
+
+/// Where the progress of the campaign is kept, relative to the working
+/// directory.
+pub const SAVE_PATH: &str = "save.toml";
+
+/// Incremented whenever the format changes in a way older versions of the
+/// game would misunderstand. Adding keys does not require it, since unknown
+/// keys are ignored.
+pub const SAVE_VERSION: u32 = 1;
+
+/// What a player had when the save was made.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerSave {
+    pub score: u32,
+    pub lives: u32,
+    pub bombs: u32,
+    pub weapon: WeaponLevel,
+}
+
+/// The progress of a campaign, saved at the start of every stage so that the
+/// players can continue from there.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveState {
+    /// The index of the stage to continue from.
+    pub stage: usize,
+    pub settings: Settings,
+    pub players: Vec<PlayerSave>,
+}
+
+impl SaveState {
+    /// Returns whether there is a campaign to continue.
+    pub fn exists() -> bool {
+        Path::new(SAVE_PATH).exists()
+    }
+
+    /// Reads the saved campaign, if there is a valid one.
+    pub fn load() -> Option<SaveState> {
+        if !SaveState::exists() {
+            return None;
+        }
+
+        match toml::load(SAVE_PATH).and_then(|table| SaveState::from_table(&table)) {
+            Ok(save) => Some(save),
+            Err(e) => {
+                println!("Could not read the saved game: {}", e);
+                None
+            },
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        toml::save(SAVE_PATH, &self.to_table())
+    }
+
+    /// Forgets the saved campaign, once it is over.
+    pub fn delete() {
+        if SaveState::exists() {
+            if let Err(e) = fs::remove_file(SAVE_PATH) {
+                println!("Could not delete the saved game: {}", e);
+            }
+        }
+    }
+
+    pub fn from_table(table: &Table) -> Result<SaveState, String> {
+        let version = table.get("version").and_then(toml::number).ok_or("missing `version`")? as u32;
+        if version > SAVE_VERSION {
+            return Err(format!("saved by a newer version of the game (format {})", version));
+        }
+
+        let stage = table.get("stage").and_then(toml::number).ok_or("missing `stage`")?;
+
+        let mut players = Vec::new();
+        let list = table.get("players").and_then(Value::as_array).ok_or("`players` must be an array of tables")?;
+        for player in list {
+            let player = player.as_table().ok_or("`players` must be an array of tables")?;
+            let count = |key: &str| player.get(key).and_then(toml::number).map(|n| n as u32)
+                .ok_or(format!("every player needs a `{}`", key));
+
+            players.push(PlayerSave {
+                score: count("score")?,
+                lives: count("lives")?,
+                bombs: count("bombs")?,
+                weapon: player.get("weapon").and_then(Value::as_str).and_then(WeaponLevel::from_name)
+                    .ok_or("every player needs a valid `weapon`")?,
+            });
+        }
+
+        if players.is_empty() {
+            return Err("`players` must not be empty".to_string());
+        }
+
+        Ok(SaveState {
+            stage: stage as usize,
+            settings: Settings::from_table(table),
+            players: players,
+        })
+    }
+
+    pub fn to_table(&self) -> Table {
+        let players = self.players.iter().map(|player| {
+            let mut table = Table::new();
+            table.insert("score".to_string(), Value::Integer(player.score as i64));
+            table.insert("lives".to_string(), Value::Integer(player.lives as i64));
+            table.insert("bombs".to_string(), Value::Integer(player.bombs as i64));
+            table.insert("weapon".to_string(), Value::String(player.weapon.name().to_lowercase()));
+            Value::Table(table)
+        }).collect();
+
+        let mut table = self.settings.to_table();
+        table.insert("version".to_string(), Value::Integer(SAVE_VERSION as i64));
+        table.insert("stage".to_string(), Value::Integer(self.stage as i64));
+        table.insert("players".to_string(), Value::Array(players));
+        table
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn save_states() {
+        use phi::pacing::Pacing;
+        use phi::toml;
+        use views::game::difficulty::Difficulty;
+        use views::game::save::{PlayerSave, SaveState};
+        use views::game::weapon::WeaponLevel;
+        use views::settings::Settings;
+
+        let save = SaveState {
+            stage: 2,
+            settings: Settings {
+                difficulty: Difficulty::Hard,
+                adaptive: true,
+                shake: 0.5,
+                volume: 0.3,
+                resolution: (1280, 720),
+                pacing: Pacing::Limited(144),
+            },
+            players: vec![
+                PlayerSave { score: 12300, lives: 2, bombs: 1, weapon: WeaponLevel::Spread },
+                PlayerSave { score: 800, lives: 0, bombs: 0, weapon: WeaponLevel::Single },
+            ],
+        };
+
+        let text = toml::to_string(&save.to_table()).unwrap();
+        assert_eq!(SaveState::from_table(&toml::parse(&text).unwrap()), Ok(save));
+
+        // Keys added by later versions are ignored, but not newer formats.
+        let player = "[[players]]\nscore = 1\nlives = 1\nbombs = 1\nweapon = \"laser\"\n";
+        let later = format!("version = 1\nstage = 0\nunknown = true\n{}", player);
+        assert_eq!(SaveState::from_table(&toml::parse(&later).unwrap()).unwrap().players.len(), 1);
+
+        let newer = format!("version = 2\nstage = 0\n{}", player);
+        assert!(SaveState::from_table(&toml::parse(&newer).unwrap()).is_err());
+        assert!(SaveState::from_table(&toml::parse("version = 1\nstage = 0").unwrap()).is_err());
+    }
+}
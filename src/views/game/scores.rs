@@ -0,0 +1,183 @@
+use phi::toml::{self, Table, Value};
+use views::game::difficulty::Difficulty;
+use views::game::endless::{format_seed, parse_seed};
+
+/// Where the high scores are kept, relative to the working directory.
+pub const SCORES_PATH: &str = "scores.toml";
+
+/// The number of scores kept for every difficulty.
+pub const TABLE_SIZE: usize = 10;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub score: u32,
+    pub difficulty: Difficulty,
+    /// Whether the adaptive difficulty was enabled.
+    pub adaptive: bool,
+    /// The seed of the run, if it was played in the endless mode. These
+    /// scores have their own tables.
+    pub seed: Option<u64>,
+}
+
+impl Entry {
+    fn same_table(&self, other: &Entry) -> bool {
+        self.difficulty == other.difficulty && self.seed.is_some() == other.seed.is_some()
+    }
+}
+
+/// The best scores ever reached, in a separate table for every difficulty of
+/// both the scripted levels and the endless mode.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HighScores {
+    /// Sorted from best to worst.
+    entries: Vec<Entry>,
+}
+
+impl HighScores {
+    pub fn new() -> HighScores {
+        HighScores {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Reads the high scores at `path`. Nobody should be prevented from
+    /// playing by a corrupted file, so this falls back to empty tables.
+    pub fn load(path: &str) -> HighScores {
+        match toml::load(path).and_then(|table| HighScores::from_table(&table)) {
+            Ok(scores) => scores,
+            Err(e) => {
+                println!("Could not read the high scores: {}", e);
+                HighScores::new()
+            },
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        toml::save(path, &self.to_table())
+    }
+
+    pub fn from_table(table: &Table) -> Result<HighScores, String> {
+        let mut scores = HighScores::new();
+
+        if let Some(list) = table.get("scores") {
+            let list = list.as_array().ok_or("`scores` must be an array of tables")?;
+            for entry in list {
+                let entry = entry.as_table().ok_or("`scores` must be an array of tables")?;
+                let difficulty = entry.get("difficulty").and_then(Value::as_str).and_then(Difficulty::from_name);
+                let score = entry.get("score").and_then(toml::number);
+                let seed = match entry.get("seed") {
+                    None => None,
+                    Some(seed) => Some(seed.as_str().and_then(parse_seed).ok_or("`seed` must be hexadecimal")?),
+                };
+
+                match (difficulty, score) {
+                    (Some(difficulty), Some(score)) => {
+                        scores.add(Entry {
+                            score: score as u32,
+                            difficulty: difficulty,
+                            adaptive: entry.get("adaptive").and_then(Value::as_bool).unwrap_or(false),
+                            seed: seed,
+                        });
+                    },
+                    _ => return Err("every score needs a `difficulty` and a `score`".to_string()),
+                }
+            }
+        }
+
+        Ok(scores)
+    }
+
+    pub fn to_table(&self) -> Table {
+        let entries = self.entries.iter().map(|entry| {
+            let mut table = Table::new();
+            table.insert("score".to_string(), Value::Integer(entry.score as i64));
+            table.insert("difficulty".to_string(), Value::String(entry.difficulty.name().to_string()));
+            table.insert("adaptive".to_string(), Value::Boolean(entry.adaptive));
+            if let Some(seed) = entry.seed {
+                table.insert("seed".to_string(), Value::String(format_seed(seed)));
+            }
+            Value::Table(table)
+        }).collect();
+
+        let mut table = Table::new();
+        table.insert("scores".to_string(), Value::Array(entries));
+        table
+    }
+
+    /// Returns the best scores reached at `difficulty`, in the `endless`
+    /// mode or not, best first.
+    pub fn table(&self, difficulty: Difficulty, endless: bool) -> Vec<&Entry> {
+        self.entries.iter()
+            .filter(|entry| entry.difficulty == difficulty && entry.seed.is_some() == endless)
+            .collect()
+    }
+
+    /// Records a score. Returns its rank in its table (starting at 0), or
+    /// `None` if it was not good enough to be kept.
+    pub fn add(&mut self, entry: Entry) -> Option<usize> {
+        // Ties are ranked below the scores which were reached first.
+        let position = self.entries.iter().position(|other| other.score < entry.score).unwrap_or(self.entries.len());
+        let rank = self.entries[..position].iter().filter(|other| other.same_table(&entry)).count();
+
+        if rank >= TABLE_SIZE {
+            return None;
+        }
+
+        self.entries.insert(position, entry.clone());
+
+        // Forget the score which was pushed out of the table, if any.
+        if let Some(last) = self.entries.iter().enumerate()
+            .filter(|&(_, other)| other.same_table(&entry))
+            .map(|(i, _)| i)
+            .nth(TABLE_SIZE) {
+            self.entries.remove(last);
+        }
+
+        Some(rank)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn high_scores() {
+        use phi::toml;
+        use views::game::difficulty::Difficulty;
+        use views::game::scores::{Entry, HighScores, TABLE_SIZE};
+
+        let entry = |score, difficulty| Entry { score: score, difficulty: difficulty, adaptive: false, seed: None };
+        let mut scores = HighScores::new();
+
+        assert_eq!(scores.add(entry(100, Difficulty::Normal)), Some(0));
+        assert_eq!(scores.add(entry(300, Difficulty::Normal)), Some(0));
+        assert_eq!(scores.add(entry(100, Difficulty::Normal)), Some(2));
+        assert_eq!(scores.add(entry(200, Difficulty::Hard)), Some(0));
+
+        // Every difficulty has its own table.
+        let normal: Vec<u32> = scores.table(Difficulty::Normal, false).iter().map(|e| e.score).collect();
+        assert_eq!(normal, vec![300, 100, 100]);
+        assert_eq!(scores.table(Difficulty::Hard, false).len(), 1);
+        assert_eq!(scores.table(Difficulty::Easy, false).len(), 0);
+
+        // Once a table is full, only better scores get in.
+        for score in 0..TABLE_SIZE {
+            scores.add(entry(1000 + score as u32, Difficulty::Easy));
+        }
+        assert_eq!(scores.add(entry(1000, Difficulty::Easy)), None);
+        assert_eq!(scores.add(entry(5000, Difficulty::Easy)), Some(0));
+        assert_eq!(scores.table(Difficulty::Easy, false).len(), TABLE_SIZE);
+        assert_eq!(scores.table(Difficulty::Easy, false).last().unwrap().score, 1001);
+        assert_eq!(scores.table(Difficulty::Normal, false).len(), 3);
+
+        // So does the endless mode, which remembers the seed of every run.
+        let endless = Entry { seed: Some(0xC0FFEE), .. entry(50, Difficulty::Normal) };
+        assert_eq!(scores.add(endless.clone()), Some(0));
+        assert_eq!(scores.table(Difficulty::Normal, true), vec![&endless]);
+        assert_eq!(scores.table(Difficulty::Normal, false).len(), 3);
+
+        // Written and read back as TOML
+        let text = toml::to_string(&scores.to_table()).unwrap();
+        assert_eq!(HighScores::from_table(&toml::parse(&text).unwrap()), Ok(scores));
+        assert!(HighScores::from_table(&toml::parse("[[scores]]\nscore = 1").unwrap()).is_err());
+    }
+}
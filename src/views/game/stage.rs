@@ -0,0 +1,186 @@
+use phi::{Phi, View, ViewAction};
+use phi::data::Rectangle;
+use phi::gfx::Sprite;
+use phi::toml::{self, Table, Value};
+use sdl2::pixels::Color;
+use views::game::{play, Mode, Run, ShipView};
+
+/// Lists the levels of the game, in the order in which they are played.
+pub const STAGES_PATH: &str = "assets/levels/stages.toml";
+
+/// Points awarded at the end of a stage for every percent of accuracy, and
+/// for every life left.
+const ACCURACY_BONUS: u32 = 50;
+const LIFE_BONUS: u32 = 1000;
+
+/// Awarded to the players who did not lose any life during the stage.
+const NO_MISS_BONUS: u32 = 5000;
+
+const FONT: &str = "assets/belligerent.ttf";
+
+/// Returns the paths of the level files of every stage, of which there is
+/// at least one.
+pub fn stages() -> Result<Vec<String>, String> {
+    stage_list(&toml::load(STAGES_PATH)?).map_err(|e| format!("{}: {}", STAGES_PATH, e))
+}
+
+fn stage_list(doc: &Table) -> Result<Vec<String>, String> {
+    let stages: Vec<String> = doc.get("stages")
+        .and_then(Value::as_array)
+        .and_then(|stages| stages.iter().map(|stage| stage.as_str().map(|s| s.to_string())).collect())
+        .ok_or("`stages` must be an array of paths")?;
+
+    if stages.is_empty() {
+        return Err("`stages` must not be empty".to_string());
+    }
+    Ok(stages)
+}
+
+/// How well a player did during a stage.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Summary {
+    pub kills: u32,
+    /// The fraction of the shots which hit something.
+    pub accuracy: f64,
+    pub bonus: u32,
+}
+
+impl Summary {
+    /// `lost` is the number of lives lost during the stage, and `lives` the
+    /// number of those left.
+    pub fn new(kills: u32, shots: u32, hits: u32, lives: u32, lost: u32) -> Summary {
+        let accuracy = if shots == 0 { 0.0 } else { hits as f64 / shots as f64 };
+
+        // Dead players get nothing.
+        let bonus = if lives == 0 {
+            0
+        } else {
+            let percent = (accuracy * 100.0).round() as u32;
+            percent * ACCURACY_BONUS + lives * LIFE_BONUS + if lost == 0 { NO_MISS_BONUS } else { 0 }
+        };
+
+        Summary {
+            kills: kills,
+            accuracy: accuracy,
+            bonus: bonus,
+        }
+    }
+}
+
+/// Shown between two stages, and after the last one: sums up how every
+/// player did, and awards the bonus points.
+pub struct StageClearView {
+    /// The run continues once the players are ready.
+    run: Run,
+    /// Whether that was the last stage.
+    last: bool,
+    lines: Vec<Sprite>,
+}
+
+impl StageClearView {
+    pub fn new(phi: &mut Phi, mut run: Run, last: bool) -> StageClearView {
+        let white = Color::RGB(255, 255, 255);
+        let grey = Color::RGB(200, 200, 200);
+
+        let title = if last { "ALL STAGES CLEAR".to_string() } else { format!("STAGE {} CLEAR", run.stage + 1) };
+        let mut lines = vec![phi.ttf_str_sprite(&title, FONT, 48, white).unwrap()];
+
+        for player in &mut run.players {
+            let summary = player.summary();
+            player.score += summary.bonus;
+
+            let text = [
+                format!("{}P", player.id + 1),
+                format!("KILLS {}", summary.kills),
+                format!("ACCURACY {}%", (summary.accuracy * 100.0).round()),
+                format!("BONUS {}", summary.bonus),
+                format!("SCORE {:06}", player.score),
+            ];
+            for (i, line) in text.iter().enumerate() {
+                let color = if i == 0 { white } else { grey };
+                lines.push(phi.ttf_str_sprite(line, FONT, 24, color).unwrap());
+            }
+        }
+
+        lines.push(phi.ttf_str_sprite("Press Space to continue", FONT, 20, grey).unwrap());
+
+        // The players may quit now, and continue from the next stage later.
+        if !last {
+            run.save_progress(run.stage + 1);
+        }
+
+        StageClearView {
+            run: run,
+            last: last,
+            lines: lines,
+        }
+    }
+}
+
+impl View for StageClearView {
+    fn render(&mut self, phi: &mut Phi, _: f64) -> ViewAction {
+        if phi.events.now.quit || phi.events.now.key_escape == Some(true) {
+            return ViewAction::Quit;
+        }
+
+        if phi.events.now.key_space == Some(true) || phi.events.now.key_return == Some(true) {
+            let mut run = self.run.take();
+
+            if self.last {
+                run.save_scores();
+                return ViewAction::ChangeView {
+                    new_view: Box::new(::views::high_scores::HighScoresView::new(phi, run.mode != Mode::Story))
+                };
+            }
+
+            run.stage += 1;
+            let view = ShipView::stage(phi, run);
+            return play(phi, view);
+        }
+
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        let (win_w, _) = phi.output_size();
+        let mut y = 60.0;
+        for sprite in &self.lines {
+            let (w, h) = sprite.size();
+            sprite.render(&mut phi.renderer, Rectangle { x: (win_w - w) / 2.0, y: y, w: w, h: h });
+            y += h * 1.3;
+        }
+
+        ViewAction::None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn stage_summary() {
+        use views::game::stage::Summary;
+
+        let summary = Summary::new(12, 200, 50, 2, 1);
+        assert_eq!(summary.kills, 12);
+        assert_eq!(summary.accuracy, 0.25);
+        assert_eq!(summary.bonus, 25 * 50 + 2 * 1000);
+
+        // Not losing any life is worth a lot.
+        assert_eq!(Summary::new(12, 200, 50, 2, 0).bonus, summary.bonus + 5000);
+
+        // Not shooting at all is not an error, and dead players get nothing.
+        assert_eq!(Summary::new(0, 0, 0, 1, 0).accuracy, 0.0);
+        assert_eq!(Summary::new(30, 100, 100, 0, 3).bonus, 0);
+    }
+
+    #[test]
+    fn stage_lists() {
+        use phi::toml;
+        use views::game::stage::stage_list;
+
+        let list = |text: &str| stage_list(&toml::parse(text).unwrap());
+        assert_eq!(list("stages = [\"1.toml\", \"2.toml\"]"), Ok(vec!["1.toml".to_string(), "2.toml".to_string()]));
+        assert_eq!(list("stages = []"), Err("`stages` must not be empty".to_string()));
+        assert!(list("stages = [1]").is_err());
+        assert!(list("").is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use phi::snapshot::{field, table, Snapshot};
+use phi::toml::Value;
+
+/// Keeps track of the current state of an entity's AI, and of how long it
+/// has been in it. The states are usually a small `enum`, and the logic of
+/// each of them a branch of a `match` on `state()`:
+///
+/// ```ignore
+/// self.machine.update(elapsed);
+/// match self.machine.state() {
+///     State::Waiting if self.machine.time() > 2.0 => self.machine.set(State::Attacking),
+///     State::Attacking if self.machine.entered() => self.fire(),
+///     _ => {},
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct StateMachine<S> {
+    state: S,
+    /// Seconds spent in the current state.
+    time: f64,
+    /// Whether the state was changed since the last update.
+    changed: bool,
+    /// Whether the last update was the first one in the current state.
+    entered: bool,
+}
+
+impl<S: Copy + PartialEq> StateMachine<S> {
+    pub fn new(initial: S) -> StateMachine<S> {
+        StateMachine {
+            state: initial,
+            time: 0.0,
+            // The initial state is entered on the first update.
+            changed: true,
+            entered: false,
+        }
+    }
+
+    pub fn state(&self) -> S {
+        self.state
+    }
+
+    /// Seconds spent in the current state, as of the last update.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Returns whether the last update was the first one in the current
+    /// state, which is when states usually set themselves up.
+    pub fn entered(&self) -> bool {
+        self.entered
+    }
+
+    /// Must be called once per frame, before looking at the state. Time
+    /// starts counting from the first update after a change of state.
+    pub fn update(&mut self, elapsed: f64) {
+        if self.changed {
+            self.changed = false;
+            self.entered = true;
+            self.time = 0.0;
+        } else {
+            self.entered = false;
+            self.time += elapsed;
+        }
+    }
+
+    /// Switches to `state` (even if it is the current one, which restarts
+    /// it). The change is effective immediately, but `entered()` and `time()`
+    /// only reflect it after the next update.
+    pub fn set(&mut self, state: S) {
+        self.state = state;
+        self.changed = true;
+    }
+}
+
+impl<S: Snapshot + Copy + PartialEq> Snapshot for StateMachine<S> {
+    fn snapshot(&self) -> Value {
+        table(vec![
+            ("state", self.state.snapshot()),
+            ("time", self.time.snapshot()),
+            ("changed", self.changed.snapshot()),
+            ("entered", self.entered.snapshot()),
+        ])
+    }
+
+    fn restore(value: &Value) -> Result<StateMachine<S>, String> {
+        Ok(StateMachine {
+            state: field(value, "state")?,
+            time: field(value, "time")?,
+            changed: field(value, "changed")?,
+            entered: field(value, "entered")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn state_machine() {
+        use views::game::state::StateMachine;
+
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        enum State { Idle, Busy(u32) }
+
+        let mut machine = StateMachine::new(State::Idle);
+        machine.update(0.5);
+        assert!(machine.entered());
+        assert_eq!(machine.time(), 0.0);
+
+        machine.update(0.5);
+        machine.update(0.25);
+        assert!(!machine.entered());
+        assert_eq!(machine.time(), 0.75);
+
+        machine.set(State::Busy(1));
+        assert_eq!(machine.state(), State::Busy(1));
+        assert!(!machine.entered());
+
+        machine.update(0.5);
+        assert!(machine.entered());
+        assert_eq!(machine.time(), 0.0);
+        machine.update(0.5);
+        assert_eq!(machine.time(), 0.5);
+
+        // Setting the current state again restarts it.
+        machine.set(State::Busy(1));
+        machine.update(0.5);
+        assert!(machine.entered());
+        assert_eq!(machine.time(), 0.0);
+    }
+}
@@ -0,0 +1,134 @@
+use phi::data::Vec2;
+use phi::snapshot::Snapshot;
+use phi::toml::Value;
+use views::game::bullet::{Bullet, Owner};
+
+/// The weapon of the player's ship, which is upgraded by collecting
+/// power-ups.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WeaponLevel {
+    Single,
+    /// Two parallel shots.
+    Double,
+    /// Five shots fanned in front of the ship.
+    Spread,
+    /// A continuous stream of fast, powerful shots.
+    Laser,
+}
+
+impl WeaponLevel {
+    /// The next level, if this is not the best one already.
+    pub fn upgrade(self) -> Option<WeaponLevel> {
+        match self {
+            WeaponLevel::Single => Some(WeaponLevel::Double),
+            WeaponLevel::Double => Some(WeaponLevel::Spread),
+            WeaponLevel::Spread => Some(WeaponLevel::Laser),
+            WeaponLevel::Laser => None,
+        }
+    }
+
+    /// The previous level, which the weapon falls back to when the ship is
+    /// hit.
+    pub fn downgrade(self) -> WeaponLevel {
+        match self {
+            WeaponLevel::Single | WeaponLevel::Double => WeaponLevel::Single,
+            WeaponLevel::Spread => WeaponLevel::Double,
+            WeaponLevel::Laser => WeaponLevel::Spread,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WeaponLevel::Single => "SINGLE",
+            WeaponLevel::Double => "DOUBLE",
+            WeaponLevel::Spread => "SPREAD",
+            WeaponLevel::Laser => "LASER",
+        }
+    }
+
+    /// Reads a name returned by `name`, in any case.
+    pub fn from_name(name: &str) -> Option<WeaponLevel> {
+        let mut level = Some(WeaponLevel::Single);
+        while let Some(current) = level {
+            if current.name().eq_ignore_ascii_case(name) {
+                return Some(current);
+            }
+            level = current.upgrade();
+        }
+
+        None
+    }
+
+    /// Seconds between two volleys.
+    pub fn interval(self) -> f64 {
+        match self {
+            WeaponLevel::Laser => 0.05,
+            _ => 0.15,
+        }
+    }
+
+    /// Returns the bullets of a single volley fired by `player` from
+    /// `origin`. While `focused`, the shots are closer to each other.
+    pub fn volley(self, player: usize, origin: Vec2, focused: bool) -> Vec<Bullet> {
+        let narrow = if focused { 0.5 } else { 1.0 };
+        let shot = |offset: f64, angle: f64, speed: f64, radius: f64, damage: f64| Bullet {
+            pos: origin + Vec2::new(0.0, offset * narrow),
+            vel: Vec2::from_angle((angle * narrow).to_radians()) * speed,
+            radius: radius,
+            damage: damage,
+            owner: Owner::Player(player),
+        };
+
+        match self {
+            WeaponLevel::Single => vec![shot(0.0, 0.0, 600.0, 4.0, 1.0)],
+            WeaponLevel::Double => vec![shot(-8.0, 0.0, 600.0, 4.0, 1.0), shot(8.0, 0.0, 600.0, 4.0, 1.0)],
+            WeaponLevel::Spread => [-20.0, -10.0, 0.0, 10.0, 20.0].iter()
+                .map(|&angle| shot(0.0, angle, 600.0, 4.0, 1.0))
+                .collect(),
+            WeaponLevel::Laser => vec![shot(0.0, 0.0, 1200.0, 3.0, 0.8)],
+        }
+    }
+}
+
+impl Snapshot for WeaponLevel {
+    fn snapshot(&self) -> Value {
+        Value::String(self.name().to_lowercase())
+    }
+
+    fn restore(value: &Value) -> Result<WeaponLevel, String> {
+        value.as_str().and_then(WeaponLevel::from_name).ok_or("unknown weapon".to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn weapon_levels() {
+        use phi::data::Vec2;
+        use views::game::weapon::WeaponLevel;
+
+        let mut level = WeaponLevel::Single;
+        let mut volleys = vec![level.volley(0, Vec2::zero(), false).len()];
+        while let Some(next) = level.upgrade() {
+            level = next;
+            volleys.push(level.volley(0, Vec2::zero(), false).len());
+        }
+
+        assert_eq!(level, WeaponLevel::Laser);
+        assert_eq!(volleys, vec![1, 2, 5, 1]);
+
+        // Every shot goes forward, and focusing narrows the spread.
+        let spread = |focused| WeaponLevel::Spread.volley(0, Vec2::zero(), focused).iter()
+            .map(|bullet| bullet.vel.angle().to_degrees().round())
+            .collect::<Vec<f64>>();
+        assert_eq!(spread(false), vec![-20.0, -10.0, 0.0, 10.0, 20.0]);
+        assert_eq!(spread(true), vec![-10.0, -5.0, 0.0, 5.0, 10.0]);
+
+        assert_eq!(WeaponLevel::Laser.downgrade(), WeaponLevel::Spread);
+        assert_eq!(WeaponLevel::Single.downgrade(), WeaponLevel::Single);
+
+        assert_eq!(WeaponLevel::from_name("spread"), Some(WeaponLevel::Spread));
+        assert_eq!(WeaponLevel::from_name(WeaponLevel::Laser.name()), Some(WeaponLevel::Laser));
+        assert_eq!(WeaponLevel::from_name("railgun"), None);
+    }
+}
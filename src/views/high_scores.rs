@@ -0,0 +1,101 @@
+use phi::{Phi, View, ViewAction};
+use phi::data::Rectangle;
+use phi::gfx::Sprite;
+use sdl2::pixels::Color;
+use views::game::difficulty::Difficulty;
+use views::game::endless::format_seed;
+use views::game::scores::{HighScores, SCORES_PATH, TABLE_SIZE};
+
+const FONT: &str = "assets/belligerent.ttf";
+
+/// Shows the best scores of every difficulty, side by side, either for the
+/// scripted levels or for the endless mode.
+pub struct HighScoresView {
+    endless: bool,
+    title: Sprite,
+    /// The title and lines of every column.
+    columns: Vec<Vec<Sprite>>,
+}
+
+impl HighScoresView {
+    pub fn new(phi: &mut Phi, endless: bool) -> HighScoresView {
+        let scores = HighScores::load(SCORES_PATH);
+        let mut columns = Vec::new();
+
+        let title = if endless { "< ENDLESS >" } else { "< STORY >" };
+        let title = phi.ttf_str_sprite(title, FONT, 24, Color::RGB(255, 255, 255)).unwrap();
+
+        for &difficulty in Difficulty::all().iter() {
+            let title = difficulty.name().to_uppercase();
+            let mut column = vec![phi.ttf_str_sprite(&title, FONT, 32, Color::RGB(255, 255, 255)).unwrap()];
+
+            // Endless runs can be replayed with their seed.
+            let table = scores.table(difficulty, endless);
+            for i in 0..TABLE_SIZE {
+                let line = match table.get(i) {
+                    Some(entry) => format!("{:2}. {:06}{} {}", i + 1, entry.score,
+                                           if entry.adaptive { "*" } else { "" },
+                                           entry.seed.map(format_seed).unwrap_or(String::new())),
+                    None => format!("{:2}. ------", i + 1),
+                };
+                // Smaller, to make room for the seeds
+                let size = if endless { 16 } else { 20 };
+                column.push(phi.ttf_str_sprite(&line, FONT, size, Color::RGB(220, 220, 220)).unwrap());
+            }
+
+            columns.push(column);
+        }
+
+        HighScoresView {
+            endless: endless,
+            title: title,
+            columns: columns,
+        }
+    }
+}
+
+impl View for HighScoresView {
+    fn render(&mut self, phi: &mut Phi, _: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        if phi.events.now.key_escape == Some(true) || phi.events.now.key_space == Some(true) {
+            return ViewAction::ChangeView {
+                new_view: Box::new(::views::main_menu::MainMenuView::new(phi))
+            };
+        }
+
+        if phi.events.now.key_left == Some(true) || phi.events.now.key_right == Some(true) {
+            return ViewAction::ChangeView {
+                new_view: Box::new(HighScoresView::new(phi, !self.endless))
+            };
+        }
+
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        let (win_w, _) = phi.output_size();
+        let column_w = win_w / self.columns.len() as f64;
+
+        let (w, h) = self.title.size();
+        self.title.render(&mut phi.renderer, Rectangle { x: (win_w - w) / 2.0, y: 30.0, w: w, h: h });
+
+        for (i, column) in self.columns.iter().enumerate() {
+            let mut y = 90.0;
+
+            for sprite in column {
+                let (w, h) = sprite.size();
+                sprite.render(&mut phi.renderer, Rectangle {
+                    x: column_w * (i as f64 + 0.5) - w / 2.0,
+                    y: y,
+                    w: w,
+                    h: h,
+                });
+                y += h * 1.4;
+            }
+        }
+
+        ViewAction::None
+    }
+}
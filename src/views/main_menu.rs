@@ -1,99 +1,225 @@
 use phi::{Phi, View, ViewAction};
 use phi::data::Rectangle;
-use phi::gfx::Sprite;
+use phi::gfx::{ParallaxSprite, Sprite};
+use phi::menu::{Menu, Page};
+use phi::pacing::Pacing;
+use phi::tween::{Animation, Delay, Easing, Group, Tween};
 use sdl2::pixels::Color;
+use std::cell::Cell;
+use std::rc::Rc;
+use views::game::difficulty::Difficulty;
+use views::game::{self, Mode, ShipView};
+use views::game::demo::{Recording, DEMO_PATH};
+use views::game::save::SaveState;
+use views::settings::{Settings, RESOLUTIONS};
 
-struct Action {
-    /// The function which should be executed if the action is chosen.
-    func: Box<Fn(&mut Phi) -> ViewAction>,
+const FONT: &str = "assets/belligerent.ttf";
 
-    /// The sprite which is rendered when the player does not focus on this
-    /// action's label.
-    idle_sprite: Sprite,
+/// Seconds without any input before the attract mode plays the demo.
+const ATTRACT_DELAY: f64 = 20.0;
 
-    /// The sprite which is rendered when the player "focuses" a label with the
-    /// directional keys.
-    hover_sprite: Sprite,
+pub struct MainMenuView {
+    menu: Menu,
+    back: ParallaxSprite,
+    middle: ParallaxSprite,
+    front: ParallaxSprite,
+    logo: Sprite,
+    /// Drops and fades the logo in when the menu opens.
+    intro: Group,
+    logo_y: Rc<Cell<f64>>,
+    logo_tint: Rc<Cell<Color>>,
+    /// Set once the logo has dropped in.
+    landed: Rc<Cell<bool>>,
+    /// Seconds since the last input.
+    idle: f64,
+    /// Seconds since the logo dropped in, to make it bob.
+    time: f64,
+    /// Why the last game could not start, if it did not.
+    error: Option<Sprite>,
 }
 
-impl Action {
-    fn new(phi: &mut Phi, label: &'static str, func: Box<Fn(&mut Phi) -> ViewAction>) -> Action {
-        Action {
-            func: func,
-            idle_sprite: phi.ttf_str_sprite(label, "assets/belligerent.ttf", 32, Color::RGB(220, 220, 220)).unwrap(),
-            hover_sprite: phi.ttf_str_sprite(label, "assets/belligerent.ttf", 38, Color::RGB(255, 255, 255)).unwrap(),
+impl MainMenuView {
+    pub fn new(phi: &mut Phi) -> MainMenuView {
+        // Menus are not shaken, and the demo starts from a still camera.
+        phi.camera.reset();
+
+        // Shared by the options, which change them, and the games they start.
+        let settings = Rc::new(Cell::new(Settings::load()));
+
+        let start = |players: usize| {
+            let settings = settings.clone();
+            move |phi: &mut Phi| {
+                let view = ShipView::new(phi, players, settings.get(), Mode::Story);
+                game::play(phi, view)
+            }
+        };
+
+        let endless = {
+            let settings = settings.clone();
+            move |phi: &mut Phi| ViewAction::ChangeView {
+                new_view: Box::new(::views::seed::SeedView::new(phi, settings.get()))
+            }
+        };
+
+        let new_game = Page::new("NEW GAME")
+            .action("1 Player", start(1))
+            .action("2 Players", start(2))
+            .action("Endless", endless)
+            .back("Back");
+
+        let difficulties = Difficulty::all();
+        let current = difficulties.iter().position(|&d| d == settings.get().difficulty).unwrap_or(0);
+        // Custom frame rates and sizes, only set in the file, are kept as an
+        // option.
+        let mut pacings = Pacing::all();
+        if !pacings.contains(&settings.get().pacing) {
+            pacings.push(settings.get().pacing);
+        }
+        let pacing = pacings.iter().position(|&p| p == settings.get().pacing).unwrap_or(0);
+        let mut resolutions = RESOLUTIONS.to_vec();
+        if !resolutions.contains(&settings.get().resolution) {
+            resolutions.push(settings.get().resolution);
+        }
+        let resolution = resolutions.iter().position(|&r| r == settings.get().resolution).unwrap_or(0);
+
+        let options = Page::new("OPTIONS")
+            .cycler("Difficulty", difficulties.iter().map(|d| d.name().to_string()).collect(), current,
+                    change(&settings, move |s, i| s.difficulty = difficulties[i]))
+            .toggle("Adaptive", settings.get().adaptive, change(&settings, |s, on| s.adaptive = on))
+            .slider("Screen shake", settings.get().shake, (0.0, 1.0), 0.1,
+                    change(&settings, |s, shake| s.shake = shake))
+            .slider("Volume", settings.get().volume, (0.0, 1.0), 0.1,
+                    change(&settings, |s, volume| s.volume = volume))
+            .cycler("Resolution", resolutions.iter().map(|&(w, h)| format!("{}x{}", w, h)).collect(), resolution,
+                    change(&settings, move |s, i| s.resolution = resolutions[i]))
+            .cycler("Frame rate", pacings.iter().map(|p| p.label()).collect(), pacing,
+                    change(&settings, move |s, i| s.pacing = pacings[i]))
+            .back("Back");
+
+        // The campaign can only be continued if it was saved.
+        let save = SaveState::load();
+        let can_continue = save.is_some();
+
+        let root = Page::new("")
+            .action("Continue", move |phi| match save {
+                Some(ref save) => {
+                    let view = ShipView::resume(phi, save.clone());
+                    game::play(phi, view)
+                },
+                None => ViewAction::None,
+            })
+            .enabled(can_continue)
+            .submenu("New Game", new_game)
+            .submenu("Options", options)
+            .action("High Scores", |phi| ViewAction::ChangeView {
+                new_view: Box::new(::views::high_scores::HighScoresView::new(phi, false))
+            })
+            .action("Quit", |_| ViewAction::Quit);
+
+        let logo_y = Rc::new(Cell::new(0.0));
+        let logo_tint = Rc::new(Cell::new(Color::RGB(0, 0, 0)));
+        let landed = Rc::new(Cell::new(false));
+        let drop = {
+            let landed = landed.clone();
+            Tween::new(-100.0, 40.0, 0.8, Easing::BounceOut).bound(&logo_y).on_complete(move || landed.set(true))
+        };
+        let intro = Group::sequence()
+            .then(Delay::new(0.2))
+            .then(Group::parallel()
+                .then(drop)
+                .then(Tween::new(Color::RGB(0, 0, 0), Color::RGB(255, 255, 255), 0.5, Easing::QuadOut)
+                    .bound(&logo_tint)));
+
+        MainMenuView {
+            menu: Menu::new(root, FONT),
+            back: ParallaxSprite::load(&phi.renderer, "assets/starBG.png", 20.0).unwrap(),
+            middle: ParallaxSprite::load(&phi.renderer, "assets/starMG.png", 40.0).unwrap(),
+            front: ParallaxSprite::load(&phi.renderer, "assets/starFG.png", 80.0).unwrap(),
+            logo: phi.ttf_str_sprite("RUSTY SHOOTER", FONT, 64, Color::RGB(255, 220, 60)).unwrap(),
+            intro: intro,
+            logo_y: logo_y,
+            logo_tint: logo_tint,
+            landed: landed,
+            idle: 0.0,
+            time: 0.0,
+            error: None,
         }
     }
-}
 
-pub struct MainMenuView {
-    actions: Vec<Action>,
-    selected: i8,
+    /// Opens the menu with `error` shown below it, such as the reason why a
+    /// game could not start.
+    pub fn with_error(phi: &mut Phi, error: &str) -> MainMenuView {
+        println!("{}", error);
+        MainMenuView {
+            error: phi.ttf_str_sprite(error, FONT, 16, Color::RGB(255, 80, 80)),
+            .. MainMenuView::new(phi)
+        }
+    }
 }
 
-impl MainMenuView {
-    pub fn new(phi: &mut Phi) -> MainMenuView {
-        MainMenuView {
-            actions: vec![
-                Action::new(phi, "New Game", Box::new(|phi| {
-                    ViewAction::ChangeView { new_view: Box::new(::views::game::ShipView::new(phi)) }
-                })),
-                Action::new(phi, "Quit", Box::new(|_| {
-                    ViewAction::Quit
-                })),
-            ],
-
-            selected: 0,
+/// Returns a callback which applies `apply` to the `settings`, then saves
+/// them.
+fn change<T, F>(settings: &Rc<Cell<Settings>>, apply: F) -> impl Fn(T)
+where F: Fn(&mut Settings, T) {
+    let settings = settings.clone();
+    move |value| {
+        let mut changed = settings.get();
+        apply(&mut changed, value);
+        settings.set(changed);
+
+        if let Err(e) = changed.save() {
+            println!("Could not save the settings: {}", e);
         }
     }
 }
 
 impl View for MainMenuView {
-    fn render(&mut self, phi: &mut Phi, _: f64) -> ViewAction {
-        if phi.events.now.quit || phi.events.now.key_escape == Some(true) {
+    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if phi.events.now.quit {
             return ViewAction::Quit;
         }
 
-        if phi.events.now.key_space == Some(true) {
-            return (self.actions[self.selected as usize].func)(phi);
+        // Left alone, the menu plays a demo of the game.
+        if phi.events.now.any() || phi.events.now.mouse_moved {
+            self.idle = 0.0;
+        } else {
+            self.idle += elapsed;
         }
 
-        if phi.events.now.key_down == Some(true) {
-            self.selected += 1;
-            if self.selected >= self.actions.len() as i8 {
-                self.selected = 0;
-            }
-        } else if phi.events.now.key_up == Some(true) {
-            self.selected -= 1;
-            if self.selected < 0 {
-                self.selected = (self.actions.len() - 1) as i8;
+        if self.idle >= ATTRACT_DELAY {
+            match Recording::load(DEMO_PATH).and_then(|recording| ShipView::demo(phi, recording)) {
+                Ok(view) => return ViewAction::ChangeView { new_view: Box::new(view) },
+                Err(e) => {
+                    println!("Could not play the demo: {}", e);
+                    self.idle = 0.0;
+                },
             }
         }
 
-        // Clear the screen
         phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
         phi.renderer.clear();
 
-        for (i, action) in self.actions.iter().enumerate() {
-            let sprite_to_render = if self.selected == i as i8 {
-                &action.hover_sprite
-            } else {
-                &action.idle_sprite
-            };
+        self.back.render(&mut phi.renderer, None, elapsed);
+        self.middle.render(&mut phi.renderer, None, elapsed);
+        self.front.render(&mut phi.renderer, None, elapsed);
 
-            let (w, h) = sprite_to_render.size();
+        // The logo slowly bobs above the menu, once it has dropped in.
+        self.intro.advance(elapsed);
+        if self.landed.get() {
+            self.time += elapsed;
+        }
+        let bob = 6.0 * (self.time * 1.5).sin();
+        let (win_w, _) = phi.output_size();
+        let (w, h) = self.logo.size();
+        let dest = Rectangle { x: (win_w - w) / 2.0, y: self.logo_y.get() + bob, w: w, h: h };
+        self.logo.tinted(self.logo_tint.get()).render(&mut phi.renderer, dest);
 
+        if let Some(ref error) = self.error {
             let (win_w, win_h) = phi.output_size();
-            sprite_to_render.render(&mut phi.renderer, Rectangle {
-                x: (win_w - w) / 2.0,
-                //? We place every element under the previous one.
-                y: (win_h - h) / 2.0 + h * 1.5 * i as f64,
-                w: w,
-                h: h,
-            });
+            let (w, h) = error.size();
+            error.render(&mut phi.renderer, Rectangle { x: (win_w - w) / 2.0, y: win_h - h - 20.0, w: w, h: h });
         }
 
-        ViewAction::None
+        self.menu.update(phi, elapsed, |_| ViewAction::Quit)
     }
 }
-
@@ -1,2 +1,5 @@
 pub mod main_menu;
 pub mod game;
+pub mod high_scores;
+pub mod seed;
+pub mod settings;
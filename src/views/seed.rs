@@ -0,0 +1,108 @@
+use phi::{Phi, View, ViewAction};
+use phi::data::Rectangle;
+use phi::gfx::Sprite;
+use sdl2::pixels::Color;
+use views::game::endless::{format_seed, parse_seed, random_seed};
+use views::game::{play, Mode, ShipView};
+use views::settings::Settings;
+
+const FONT: &str = "assets/belligerent.ttf";
+
+/// The longest seed which can be typed.
+const SEED_DIGITS: usize = 8;
+
+/// Shows the seed of the next endless run before it starts, and lets the
+/// player type another one to replay a run.
+pub struct SeedView {
+    settings: Settings,
+    /// The seed of the next run, shown until the player types another one.
+    suggested: String,
+    /// The seed, as typed so far.
+    text: String,
+    /// Only rendered again when the text changes.
+    seed_sprite: Option<Sprite>,
+    lines: Vec<Sprite>,
+}
+
+impl SeedView {
+    pub fn new(phi: &mut Phi, settings: Settings) -> SeedView {
+        let white = Color::RGB(255, 255, 255);
+        let grey = Color::RGB(180, 180, 180);
+
+        SeedView {
+            settings: settings,
+            suggested: format_seed(random_seed()),
+            text: String::new(),
+            seed_sprite: None,
+            lines: vec![
+                phi.ttf_str_sprite("ENDLESS", FONT, 48, white).unwrap(),
+                phi.ttf_str_sprite("Type a seed to replay a run", FONT, 20, grey).unwrap(),
+                phi.ttf_str_sprite("Enter: start   Escape: back", FONT, 20, grey).unwrap(),
+            ],
+        }
+    }
+}
+
+impl View for SeedView {
+    fn render(&mut self, phi: &mut Phi, _: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        if phi.events.now.key_escape == Some(true) {
+            return ViewAction::ChangeView {
+                new_view: Box::new(::views::main_menu::MainMenuView::new(phi))
+            };
+        }
+
+        if phi.events.now.key_return == Some(true) || phi.events.now.key_space == Some(true) {
+            let text = if self.text.is_empty() { &self.suggested } else { &self.text };
+            if let Some(seed) = parse_seed(text) {
+                let view = ShipView::new(phi, 1, self.settings, Mode::Endless(seed));
+                return play(phi, view);
+            }
+        }
+
+        // Only hexadecimal digits can be typed.
+        let typed: String = phi.events.now.text.chars()
+            .filter(|c| c.is_ascii_hexdigit())
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+
+        if phi.events.now.key_backspace == Some(true) {
+            self.text.pop();
+            self.seed_sprite = None;
+        }
+
+        if !typed.is_empty() {
+            self.text.push_str(&typed);
+            self.text.truncate(SEED_DIGITS);
+            self.seed_sprite = None;
+        }
+
+        // Typing replaces the suggested seed, which is greyed out.
+        if self.seed_sprite.is_none() {
+            self.seed_sprite = if self.text.is_empty() {
+                phi.ttf_str_sprite(&format!("SEED {}", self.suggested), FONT, 32, Color::RGB(120, 120, 120))
+            } else {
+                phi.ttf_str_sprite(&format!("SEED {}_", self.text), FONT, 32, Color::RGB(255, 255, 255))
+            };
+        }
+
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        let (win_w, win_h) = phi.output_size();
+        let seed_sprite = self.seed_sprite.as_ref().unwrap();
+        let sprites = [&self.lines[0], seed_sprite, &self.lines[1], &self.lines[2]];
+
+        let mut y = win_h * 0.25;
+        for sprite in sprites.iter() {
+            let (w, h) = sprite.size();
+            sprite.render(&mut phi.renderer, Rectangle { x: (win_w - w) / 2.0, y: y, w: w, h: h });
+            y += h * 1.6;
+        }
+
+        ViewAction::None
+    }
+}
@@ -0,0 +1,111 @@
+use phi::SCREEN_SIZE;
+use phi::pacing::Pacing;
+use phi::snapshot::Snapshot;
+use phi::toml::{self, Table, Value};
+use views::game::difficulty::Difficulty;
+
+/// Where the settings are kept, relative to the working directory.
+const SETTINGS_PATH: &str = "settings.toml";
+
+/// The sizes of the window offered in the options, in pixels.
+pub const RESOLUTIONS: [(u32, u32); 5] = [(800, 600), (1024, 768), (1280, 720), (1600, 900), (1920, 1080)];
+
+/// The options chosen by the players in the main menu, which are remembered
+/// from one session to the next.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Settings {
+    pub difficulty: Difficulty,
+    /// Whether the intensity of the game adapts to how well the players do.
+    pub adaptive: bool,
+    /// Multiplies the screen shake, between 0 (none) and 1.
+    pub shake: f64,
+    /// The volume of the sounds, between 0 (muted) and 1. Nothing plays any
+    /// sound yet, but the choice is remembered for when something does.
+    pub volume: f64,
+    /// The size of the window, in pixels, which only changes once the game
+    /// is started again.
+    pub resolution: (u32, u32),
+    /// How the frames are paced, which only changes once the game is
+    /// started again.
+    pub pacing: Pacing,
+}
+
+impl Settings {
+    pub fn default() -> Settings {
+        Settings {
+            difficulty: Difficulty::Normal,
+            adaptive: false,
+            shake: 1.0,
+            volume: 1.0,
+            resolution: SCREEN_SIZE,
+            pacing: Pacing::Vsync,
+        }
+    }
+
+    /// Reads the settings, falling back to the default ones if they were
+    /// never saved. Unknown or invalid values are ignored.
+    pub fn load() -> Settings {
+        let table = toml::load(SETTINGS_PATH).unwrap_or_else(|_| Table::new());
+        Settings::from_table(&table)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        toml::save(SETTINGS_PATH, &self.to_table())
+    }
+
+    pub fn from_table(table: &Table) -> Settings {
+        let default = Settings::default();
+
+        Settings {
+            difficulty: table.get("difficulty").and_then(Value::as_str)
+                .and_then(Difficulty::from_name)
+                .unwrap_or(default.difficulty),
+            adaptive: table.get("adaptive").and_then(Value::as_bool).unwrap_or(default.adaptive),
+            shake: table.get("shake").and_then(toml::number).map_or(default.shake, |shake| shake.clamp(0.0, 1.0)),
+            volume: table.get("volume").and_then(toml::number).map_or(default.volume, |volume| volume.clamp(0.0, 1.0)),
+            resolution: match table.get("resolution").and_then(Value::as_array).map(|size| &size[..]) {
+                Some([w, h]) => match (toml::number(w), toml::number(h)) {
+                    (Some(w), Some(h)) if w >= 1.0 && h >= 1.0 => (w as u32, h as u32),
+                    _ => default.resolution,
+                },
+                _ => default.resolution,
+            },
+            pacing: match (table.get("pacing").and_then(Value::as_str), table.get("fps").and_then(toml::number)) {
+                (Some("vsync"), _) => Pacing::Vsync,
+                (Some("limited"), Some(fps)) if fps >= 1.0 => Pacing::Limited(fps as u32),
+                (Some("uncapped"), _) => Pacing::Uncapped,
+                _ => default.pacing,
+            },
+        }
+    }
+
+    pub fn to_table(self) -> Table {
+        let mut table = Table::new();
+        table.insert("difficulty".to_string(), Value::String(self.difficulty.name().to_string()));
+        table.insert("adaptive".to_string(), Value::Boolean(self.adaptive));
+        table.insert("shake".to_string(), Value::Float(self.shake));
+        table.insert("volume".to_string(), Value::Float(self.volume));
+        let (w, h) = self.resolution;
+        table.insert("resolution".to_string(), Value::Array(vec![Value::Integer(w as i64), Value::Integer(h as i64)]));
+        let pacing = match self.pacing {
+            Pacing::Vsync => "vsync",
+            Pacing::Limited(fps) => {
+                table.insert("fps".to_string(), Value::Integer(fps as i64));
+                "limited"
+            },
+            Pacing::Uncapped => "uncapped",
+        };
+        table.insert("pacing".to_string(), Value::String(pacing.to_string()));
+        table
+    }
+}
+
+impl Snapshot for Settings {
+    fn snapshot(&self) -> Value {
+        Value::Table(self.to_table())
+    }
+
+    fn restore(value: &Value) -> Result<Settings, String> {
+        value.as_table().map(Settings::from_table).ok_or("expected a table".to_string())
+    }
+}